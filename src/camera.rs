@@ -1,20 +1,80 @@
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
 use crate::canvas::Canvas;
-use crate::geometry::{Matrix4x4, Vec4};
-use crate::ray::Ray;
+use crate::color::Color;
+use crate::geometry::{Handedness, Matrix4x4, Quaternion, Vec4};
+use crate::ray::{Ray, RayDifferential};
+use crate::util;
 use crate::world::World;
 
+pub struct RenderProgress {
+    pub pixels_done: usize,
+    pub total_pixels: usize,
+    pub elapsed: Duration,
+    pub pixels_per_second: f32,
+    pub eta: Duration,
+    // Pixels so far whose color came out NaN or infinite, they are written as black or clamped
+    pub invalid_pixels: usize,
+}
+
+impl RenderProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total_pixels == 0 {
+            return 1.0;
+        }
+        return self.pixels_done as f32 / self.total_pixels as f32;
+    }
+}
+
+// Auxiliary outputs rendered alongside the final image for compositing and denoising
+pub struct Aovs {
+    pub beauty: Canvas,
+    // World-space normals remapped from -1..1 to 0..1 per channel
+    pub normal: Canvas,
+    // Grayscale depth, white at the nearest hit fading to black at the farthest, misses are black
+    pub depth: Canvas,
+    // Surface color before lighting
+    pub albedo: Canvas,
+    // Unquantized distance to the first hit per pixel in row-major order, infinite for misses
+    pub depth_values: Vec<f32>,
+}
+
 pub struct Camera {
     pub hsize: f32,
     pub vsize: f32,
     pub field_of_view: f32,
-    pub transform: Matrix4x4,
+    // Private so the cached inverse can't go stale, use set_transform or set_view_transform
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     pub pixel_size: f32,
     pub half_width: f32,
     pub half_height: f32,
+    // Seed for dithering the rendered image, see Canvas::dither
+    pub dither: Option<u64>,
+    // Antialiasing: each pixel averages samples x samples jittered rays, 1 traces the single ray through
+    // the pixel centre. The jitter is seeded from `seed` and the pixel coordinates alone, so a pixel
+    // comes out the same whatever order or thread it is rendered in
+    pub samples: usize,
+    pub seed: u64,
 }
 
 impl Camera {
+    // Fractional sizes are rounded to whole pixels up front, so the canvas and the pixel size agree
     pub fn new(hsize: f32, vsize: f32, field_of_view: f32) -> Self {
+        let valid_size = |size: f32| size.is_finite() && size >= 1.0;
+        if !valid_size(hsize) || !valid_size(vsize) {
+            panic!("Camera size must be at least 1x1 pixels, got {}x{}", hsize, vsize);
+        }
+        if !(field_of_view > 0.0 && field_of_view < std::f32::consts::PI) {
+            panic!("Camera field of view must be between 0 and PI radians, got {}", field_of_view);
+        }
+
+        let (hsize, vsize) = (hsize.round(), vsize.round());
+
         let half_view = (field_of_view / 2.0).tan();
         let aspect = hsize / vsize;
 
@@ -35,52 +95,425 @@ impl Camera {
             vsize,
             field_of_view,
             transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
             pixel_size,
             half_width,
             half_height,
+            dither: None,
+            samples: 1,
+            seed: 0,
         };
     }
 
+    // Derives the height from the width so the image always has the requested aspect ratio (width / height)
+    pub fn with_aspect(width: f32, aspect: f32, field_of_view: f32) -> Self {
+        if !(aspect.is_finite() && aspect > 0.0) {
+            panic!("Camera aspect ratio must be positive, got {}", aspect);
+        }
+
+        let height = f32::max((width / aspect).round(), 1.0);
+        return Camera::new(width, height, field_of_view);
+    }
+
+    // with_aspect with the arguments in the order the field of view is usually picked in
+    pub fn from_horizontal(width: f32, field_of_view: f32, aspect: f32) -> Self {
+        return Camera::with_aspect(width, aspect, field_of_view);
+    }
+
+    // Canvas size in whole pixels. hsize and vsize are public, so they are rounded here as well in
+    // case they were set to fractions after construction
+    pub fn width(&self) -> usize {
+        return self.hsize.round() as usize;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.vsize.round() as usize;
+    }
+
+    // Left-handed like the rest of the crate, see Handedness
     pub fn set_view_transform(&mut self, from: Vec4, to: Vec4, up: Vec4) {
-        let forward = (to - from).normalize();
-        let upn= up.normalize();
-        let left = forward.cross(&upn);
-        let true_up = left.cross(&forward);
+        self.set_view_transform_handed(from, to, up, Handedness::Left);
+    }
+
+    pub fn set_view_transform_handed(&mut self, from: Vec4, to: Vec4, up: Vec4, handedness: Handedness) {
+        self.set_transform(Matrix4x4::view_transformation_handed(from, to, up, handedness));
+    }
+
+    // Places the camera on a sphere of `radius` around `target`, looking at it. Azimuth 0 is on the -z
+    // side and turns towards +x as it grows, elevation is the angle above the xz plane. The up vector
+    // tilts with the elevation, so looking straight down or up is still well defined
+    pub fn orbit(&mut self, target: Vec4, radius: f32, azimuth: f32, elevation: f32) {
+        let (sin_azimuth, cos_azimuth) = azimuth.sin_cos();
+        let (sin_elevation, cos_elevation) = elevation.sin_cos();
+
+        let offset = Vec4::vector(cos_elevation * sin_azimuth, sin_elevation, -cos_elevation * cos_azimuth);
+        let up = Vec4::vector(-sin_elevation * sin_azimuth, cos_elevation, sin_elevation * cos_azimuth);
+
+        self.set_view_transform(target + offset * radius, target, up);
+    }
+
+    pub fn transform(&self) -> &Matrix4x4 {
+        return &self.transform;
+    }
 
-        let orientation = Matrix4x4::new([
-            *left.x(), *left.y(), *left.z(), 0.0,
-            *true_up.x(), *true_up.y(), *true_up.z(), 0.0,
-            -*forward.x(), -*forward.y(), -*forward.z(), 0.0,
-            0.0, 0.0, 0.0, 1.0,
-        ]);
+    pub fn inverse_transform(&self) -> &Matrix4x4 {
+        return &self.inverse_transform;
+    }
 
-        self.transform = orientation * Matrix4x4::translation(-from.x(), -from.y(), -from.z());
+    // The inverse is computed once here rather than for every pixel in ray_for_pixel
+    pub fn set_transform(&mut self, matrix: Matrix4x4) {
+        self.transform = matrix;
+        self.inverse_transform = matrix.invert();
     }
 
+    // The returned ray carries differentials towards the next pixel right and down so patterns can
+    // filter over the pixel's footprint
     pub fn ray_for_pixel(&self, px: f32, py: f32) -> Ray {
+        let (origin, direction) = self.pixel_direction(px, py);
+        let (x_origin, x_direction) = self.pixel_direction(px + 1.0, py);
+        let (y_origin, y_direction) = self.pixel_direction(px, py + 1.0);
+
+        let differential = RayDifferential {
+            x_origin,
+            x_direction,
+            y_origin,
+            y_direction,
+        };
+
+        return Ray::with_differential(origin, direction, differential);
+    }
+
+    fn pixel_direction(&self, px: f32, py: f32) -> (Vec4, Vec4) {
         let xoffset = (px + 0.5) * self.pixel_size;
         let yoffset = (py + 0.5) * self.pixel_size;
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
 
-        let pixel = self.transform.invert() * Vec4::point(world_x, world_y, -1.0);
-        let origin = self.transform.invert() * Vec4::point(0.0, 0.0, 0.0);
+        let pixel = self.inverse_transform * Vec4::point(world_x, world_y, -1.0);
+        let origin = self.inverse_transform * Vec4::ORIGIN;
         let direction = (pixel - origin).normalize();
 
-        return Ray::new(origin, direction);
+        return (origin, direction);
     }
 
-    pub fn render(&self, world: &World) -> Canvas {
-        let mut image = Canvas::new(self.hsize as usize, self.vsize as usize);
-         for y in 0..self.vsize as usize - 1 {
-            for x in 0..self.hsize as usize - 1 {
+    // Final color of the pixel, averaged over the antialiasing samples when there are several
+    pub fn pixel_color(&self, world: &World, x: usize, y: usize) -> Color {
+        if self.samples <= 1 {
+            return world.color_at_bounces(self.ray_for_pixel(x as f32, y as f32), world.max_bounces);
+        }
+
+        let mut rng = self.pixel_rng(x, y, 0);
+        let cells = self.samples as f32;
+        let mut total = Color::black();
+
+        // One sample per cell of a samples x samples grid over the pixel, jittered within its cell.
+        // ray_for_pixel aims at the centre of a pixel, hence the half pixel shift
+        for i in 0..self.samples {
+            for j in 0..self.samples {
+                let px = x as f32 + (j as f32 + rng()) / cells - 0.5;
+                let py = y as f32 + (i as f32 + rng()) / cells - 0.5;
+                total = total + world.color_at_bounces(self.ray_for_pixel(px, py), world.max_bounces);
+            }
+        }
+
+        return total * (1.0 / (cells * cells));
+    }
+
+    // Random numbers in [0, 1) for one pixel from a splitmix64 sequence whose start depends only on the
+    // seed, the pixel coordinates and `stream`, which gives each accumulated frame its own numbers
+    fn pixel_rng(&self, x: usize, y: usize, stream: u64) -> impl FnMut() -> f32 {
+        let mut state = self.seed
+            ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ stream.wrapping_mul(0x1656_67B1_9E37_79F9);
+
+        return move || {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            return (z >> 40) as f32 / (1u64 << 24) as f32;
+        };
+    }
+
+    // One step of progressive refinement: traces a single ray through a random point of every pixel and
+    // folds it into the running average in `prev`, row-major like render_to_colors. Frame 0 starts the
+    // average over, frame n weighs the new sample by 1 / (n + 1), so the noise of antialiasing and any
+    // other per-ray randomness settles the longer it runs. Each frame's jitter is seeded like pixel_color
+    pub fn render_accumulate(&self, world: &World, prev: &mut Vec<Color>, frame: u32) {
+        let width = self.width();
+        let height = self.height();
+        if frame == 0 || prev.len() != width * height {
+            prev.clear();
+            prev.resize(width * height, Color::black());
+        }
+
+        let weight = 1.0 / (frame as f32 + 1.0);
+        for y in 0..height {
+            for x in 0..width {
+                let mut rng = self.pixel_rng(x, y, frame as u64 + 1);
+                let ray = self.ray_for_pixel(x as f32 + rng() - 0.5, y as f32 + rng() - 0.5);
+                let sample = world.color_at_bounces(ray, world.max_bounces);
+
+                let average = &mut prev[x + y * width];
+                *average = *average + (sample - *average) * weight;
+            }
+        }
+    }
+
+    // Unquantized colors for every pixel in row-major order: the pixel at (x, y) is at index
+    // x + y * hsize, the same layout as Canvas::buffer
+    pub fn render_to_colors(&self, world: &World) -> Vec<Color> {
+        let height = self.height();
+        return (0..height).flat_map(|y| self.render_scanline(world, y)).collect();
+    }
+
+    // Unquantized colors for the hsize pixels of row y, left to right, so rows can be rendered
+    // separately and put together elsewhere. Rows below the image are empty
+    pub fn render_scanline(&self, world: &World, y: usize) -> Vec<Color> {
+        if y >= self.height() {
+            return Vec::new();
+        }
+
+        let width = self.width();
+        return (0..width).map(|x| self.pixel_color(world, x, y)).collect();
+    }
+
+    // How many intersections the ray through each pixel finds in row-major order, counting every
+    // surface along the ray and not just the visible one
+    pub fn intersection_counts(&self, world: &World) -> Vec<usize> {
+        let width = self.width();
+        let height = self.height();
+        let mut counts: Vec<usize> = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                counts.push(world.intersect_world(self.ray_for_pixel(x as f32, y as f32)).len());
+            }
+        }
+
+        return counts;
+    }
+
+    // intersection_counts drawn with Color::heat_ramp, red for the busiest pixel and black for pixels
+    // whose rays hit nothing, to spot overlapping geometry
+    pub fn render_intersection_heatmap(&self, world: &World) -> Canvas {
+        let width = self.width();
+        let counts = self.intersection_counts(world);
+        let most = counts.iter().copied().max().unwrap_or(0).max(1);
+
+        let mut image = Canvas::new(width, self.height());
+        for (i, count) in counts.iter().enumerate() {
+            image.set_color(i % width, i / width, &Color::heat_ramp(*count as f32 / most as f32));
+        }
+
+        return image;
+    }
+
+    pub fn render_aovs(&self, world: &World) -> Aovs {
+        let width = self.width();
+        let height = self.height();
+
+        // The beauty pass is the same image render gives, antialiasing and values above 1 included
+        let mut beauty = Canvas::with_hdr(width, height);
+        beauty.dither = self.dither;
+        let mut normal = Canvas::new(width, height);
+        let mut depth = Canvas::new(width, height);
+        let mut albedo = Canvas::new(width, height);
+        let mut depth_values: Vec<f32> = vec![f32::INFINITY; width * height];
+
+        for y in 0..height {
+            for x in 0..width {
+                beauty.set_color(x, y, &self.pixel_color(world, x, y));
+
                 let ray = self.ray_for_pixel(x as f32, y as f32);
-                let color = world.color_at(ray, 5);
+
+                if let Some(sample) = world.first_hit(ray) {
+                    let n = sample.normal;
+                    normal.set_color(x, y, &Color::new(n.x() * 0.5 + 0.5, n.y() * 0.5 + 0.5, n.z() * 0.5 + 0.5));
+                    albedo.set_color(x, y, &sample.albedo);
+                    depth_values[x + y * width] = sample.depth;
+                }
+            }
+        }
+
+        let hits: Vec<f32> = depth_values.iter().copied().filter(|d| d.is_finite()).collect();
+        if let (Some(near), Some(far)) = (util::min_f32(&hits), util::max_f32(&hits)) {
+            let range = far - near;
+
+            for y in 0..height {
+                for x in 0..width {
+                    let d = depth_values[x + y * width];
+                    if d.is_finite() {
+                        let shade = if range > 0.0 { 1.0 - (d - near) / range } else { 1.0 };
+                        depth.set_color(x, y, &Color::new(shade, shade, shade));
+                    }
+                }
+            }
+        }
+
+        return Aovs {
+            beauty,
+            normal,
+            depth,
+            albedo,
+            depth_values,
+        };
+    }
+
+    // Left and right eye cameras with their origins ipd apart along the camera's own horizontal axis.
+    // Camera space +x points to the left of the image (see ray_for_pixel), so the left eye sits at +ipd / 2
+    pub fn stereo_pair(&self, ipd: f32) -> (Camera, Camera) {
+        let eye = |offset: f32| -> Camera {
+            let mut camera = Camera::new(self.hsize, self.vsize, self.field_of_view);
+            camera.set_transform(Matrix4x4::translation(-offset, 0.0, 0.0) * self.transform);
+            camera.dither = self.dither;
+            camera.samples = self.samples;
+            camera.seed = self.seed;
+            return camera;
+        };
+
+        return (eye(ipd / 2.0), eye(-ipd / 2.0));
+    }
+
+    // Renders the left and right eye images for stereoscopic viewing, see stereo_pair
+    pub fn render_stereo(&self, world: &World, ipd: f32) -> (Canvas, Canvas) {
+        let (left, right) = self.stereo_pair(ipd);
+        return (left.render(world), right.render(world));
+    }
+
+    // One full turn of the shape with this id about `axis` through its origin, split into `frames` evenly
+    // spaced renders. The shape's transform is put back once the last frame is done
+    pub fn render_turntable(&self, world: &mut World, shape: Uuid, axis: Vec4, frames: usize) -> Result<Vec<Canvas>, String> {
+        let index = world
+            .objects
+            .iter()
+            .position(|object| *object.id() == shape)
+            .ok_or(format!("No shape with id {} in the world", shape))?;
+
+        let original = *world.objects[index].transform();
+        let pivot = original * Vec4::ORIGIN;
+        let to_pivot = Matrix4x4::translation(*pivot.x(), *pivot.y(), *pivot.z());
+        let from_pivot = Matrix4x4::translation(-pivot.x(), -pivot.y(), -pivot.z());
+
+        let mut images = Vec::with_capacity(frames);
+        for frame in 0..frames {
+            let angle = 2.0 * PI * frame as f32 / frames as f32;
+            let rotation = Quaternion::from_axis_angle(&axis, angle).to_matrix();
+            world.objects[index].set_transform(to_pivot * rotation * from_pivot * original);
+            images.push(self.render(world));
+        }
+
+        world.objects[index].set_transform(original);
+        return Ok(images);
+    }
+
+    // Renders a turntable and writes it as a looping GIF where one turn takes `duration`. GIF delays
+    // are in hundredths of a second and most viewers slow anything under 2 down, so that is the floor
+    pub fn render_turntable_gif(&self, world: &mut World, shape: Uuid, axis: Vec4, frames: usize, duration: Duration, file_path: &str) -> Result<(), String> {
+        let images = self.render_turntable(world, shape, axis, frames)?;
+
+        let hundredths = duration.as_secs_f32() * 100.0 / frames.max(1) as f32;
+        let delay = hundredths.round().clamp(2.0, u16::MAX as f32) as u16;
+
+        return Canvas::write_gif(&images, file_path, delay);
+    }
+
+    // The image also keeps the unquantized colors, see Canvas::hdr, so it can be saved with save_hdr or save_exr
+    pub fn render(&self, world: &World) -> Canvas {
+        return self.render_with_progress(world, |_| {});
+    }
+
+    // Calls `progress` once per finished row with the elapsed time, throughput and an estimate of the time left
+    pub fn render_with_progress<F: FnMut(&RenderProgress)>(&self, world: &World, progress: F) -> Canvas {
+        return self.render_rows(world, progress, None).unwrap();
+    }
+
+    // Checks `cancel` before every row and gives up with None once another thread has set it, e.g. when
+    // the user closes the window mid render. A finished render returns the same image as render
+    pub fn render_cancellable(&self, world: &World, cancel: &AtomicBool) -> Option<Canvas> {
+        return self.render_rows(world, |_| {}, Some(cancel));
+    }
+
+    // The same image as render, with the rows shared out between `threads` threads. Every pixel is
+    // computed independently of the others, so the result doesn't depend on the thread count
+    pub fn render_parallel(&self, world: &World, threads: usize) -> Canvas {
+        let threads = threads.max(1);
+        let rows = self.height();
+        let columns = self.width();
+
+        let bands: Vec<Vec<(usize, Vec<Color>)>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread| {
+                    scope.spawn(move || {
+                        (thread..rows)
+                            .step_by(threads)
+                            .map(|y| (y, (0..columns).map(|x| self.pixel_color(world, x, y)).collect()))
+                            .collect()
+                    })
+                })
+                .collect();
+
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        let mut image = Canvas::with_hdr(self.width(), self.height());
+        image.dither = self.dither;
+        for (y, row) in bands.into_iter().flatten() {
+            for (x, color) in row.iter().enumerate() {
+                image.set_color(x, y, color);
+            }
+        }
+
+        return image;
+    }
+
+    fn render_rows<F: FnMut(&RenderProgress)>(&self, world: &World, mut progress: F, cancel: Option<&AtomicBool>) -> Option<Canvas> {
+        let mut image = Canvas::with_hdr(self.width(), self.height());
+        image.dither = self.dither;
+        let rows = self.height();
+        let columns = self.width();
+        // Progress and the ETA are measured against the whole image
+        let total_pixels = self.width() * self.height();
+        let start = Instant::now();
+        let mut invalid_pixels = 0;
+
+        for y in 0..rows {
+            if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+                return None;
+            }
+
+            for x in 0..columns {
+                let color = self.pixel_color(world, x, y);
+                if !color.is_finite() {
+                    invalid_pixels += 1;
+                }
                 image.set_color(x, y, &color);
             }
-         }
 
-         return image;
+            let pixels_done = (y + 1) * columns;
+            let elapsed = start.elapsed();
+            let seconds = elapsed.as_secs_f32();
+
+            let pixels_per_second = if seconds > 0.0 { pixels_done as f32 / seconds } else { 0.0 };
+            let eta = if pixels_per_second > 0.0 {
+                Duration::from_secs_f32((total_pixels - pixels_done) as f32 / pixels_per_second)
+            } else {
+                Duration::ZERO
+            };
+
+            progress(&RenderProgress {
+                pixels_done,
+                total_pixels,
+                elapsed,
+                pixels_per_second,
+                eta,
+                invalid_pixels,
+            });
+        }
+
+        return Some(image);
     }
 }
\ No newline at end of file
@@ -1,22 +1,97 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use gif::{Encoder, Frame, Repeat};
+
 use crate::color::Color;
 
+// 8x8 Bayer matrix, each cell's rank in the ordered dither
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
 pub struct Canvas {
     pub width: usize, 
     pub height: usize,
     pub buffer: Vec<u32>,
+    // Seed for ordered dithering in set_color, None quantizes by rounding down. The seed shifts the
+    // 8x8 threshold matrix, so only its lowest 6 bits change the result
+    pub dither: Option<u64>,
+    // Unquantized copy of every color passed to set_color, kept when the canvas is made with_hdr so
+    // values above 1.0 survive for save_hdr and save_exr
+    pub hdr: Option<Vec<Color>>,
+}
+
+// Row by row walk over a canvas yielding (x, y, color), see Canvas::pixels
+pub struct Pixels<'a> {
+    canvas: &'a Canvas,
+    index: usize,
+}
+
+impl<'a> Iterator for Pixels<'a> {
+    type Item = (usize, usize, Color);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pixel = self.canvas.buffer.get(self.index)?;
+        let (x, y) = (self.index % self.canvas.width, self.index / self.canvas.width);
+        self.index += 1;
+
+        return Some((x, y, Color::from_rgb(*pixel)));
+    }
+}
+
+impl<'a> IntoIterator for &'a Canvas {
+    type Item = (usize, usize, Color);
+    type IntoIter = Pixels<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.pixels();
+    }
 }
 
 impl Canvas {
+    // Zero sized canvases are bumped up to a single pixel so there is always something to index
     pub fn new(width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+
         return Self {
             width,
             height,
             buffer: vec![0; width * height],
+            dither: None,
+            hdr: None,
         };
     }
 
+    pub fn with_dither(width: usize, height: usize, seed: u64) -> Self {
+        let mut canvas = Canvas::new(width, height);
+        canvas.dither = Some(seed);
+        return canvas;
+    }
+
+    pub fn with_hdr(width: usize, height: usize) -> Self {
+        let mut canvas = Canvas::new(width, height);
+        canvas.hdr = Some(vec![Color::black(); canvas.width * canvas.height]);
+        return canvas;
+    }
+
+    // Threshold in [0, 1) for the pixel, averaging to one half over every 8x8 block
+    fn dither_threshold(seed: u64, x: usize, y: usize) -> f32 {
+        let column = (x as u64 + seed) % 8;
+        let row = (y as u64 + seed / 8) % 8;
+        return (BAYER[row as usize][column as usize] as f32 + 0.5) / 64.0;
+    }
+
     pub fn clear(&mut self) {
-        let black = Color::new(0.0, 0.0, 0.0);
+        let black = Color::black();
 
         for x in 0..self.width {
             for y in 0..self.height {
@@ -26,18 +101,287 @@ impl Canvas {
     }
 
     pub fn set_color(&mut self, x: usize, y: usize, color: &Color) {
-        if x > self.width-1 ||y > self.height-1 {
+        if x >= self.width || y >= self.height {
             return;
         }
         //let y_offset = self.height - y - 1;
-        self.buffer[x + y * self.width] = color.rgb();
+        if let Some(hdr) = &mut self.hdr {
+            hdr[x + y * self.width] = *color;
+        }
+        self.buffer[x + y * self.width] = match self.dither {
+            Some(seed) => color.rgb_dithered(Canvas::dither_threshold(seed, x, y)),
+            None => color.rgb(),
+        };
     }   
 
     pub fn color_at(&self, x: usize, y: usize) -> &u32 {
-        if x > self.width-1 ||y > self.height-1 {
+        if x >= self.width || y >= self.height {
             return &0;
         }
         //let y_offset = self.height - y - 1;
         return &self.buffer[x + y * self.width];
     }
+
+    // Float color of the pixel, straight from the hdr buffer when there is one and otherwise unpacked
+    // from the 8 bit buffer. Black outside the canvas
+    pub fn hdr_color_at(&self, x: usize, y: usize) -> Color {
+        if x >= self.width || y >= self.height {
+            return Color::black();
+        }
+
+        return match &self.hdr {
+            Some(hdr) => hdr[x + y * self.width],
+            None => Color::from_rgb(self.buffer[x + y * self.width]),
+        };
+    }
+
+    // Every pixel with its coordinates, left to right then top to bottom, unpacked back into a Color
+    pub fn pixels(&self) -> Pixels<'_> {
+        return Pixels {
+            canvas: self,
+            index: 0,
+        };
+    }
+
+    // Scale for the colors of a render that brings the 95th percentile luminance of this image to 0.9,
+    // so a few highlights can clip but the bulk of the picture fills the range. 1 for a black image.
+    // Measured on the unquantized colors when the canvas keeps them, so it can also darken a render
+    // that is brighter than 1. NaN and infinite pixels are left out of the measurement
+    pub fn auto_exposure(&self) -> f32 {
+        let mut luminances: Vec<f32> = self
+            .pixels()
+            .map(|(x, y, _)| self.hdr_color_at(x, y).luminance())
+            .filter(|luminance| luminance.is_finite())
+            .collect();
+        luminances.sort_by(f32::total_cmp);
+
+        let index = ((luminances.len() as f32 * 0.95).ceil() as usize).saturating_sub(1);
+        let reference = luminances.get(index).copied().unwrap_or(0.0);
+        if reference <= 0.0 {
+            return 1.0;
+        }
+
+        return 0.9 / reference;
+    }
+
+    // Copies src into this canvas with its top left corner at (x, y). Whatever falls past the right or
+    // bottom edge is dropped
+    pub fn blit(&mut self, src: &Canvas, x: usize, y: usize) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let columns = src.width.min(self.width - x);
+        let rows = src.height.min(self.height - y);
+
+        for row in 0..rows {
+            let from = row * src.width;
+            let to = x + (y + row) * self.width;
+            self.buffer[to..to + columns].copy_from_slice(&src.buffer[from..from + columns]);
+
+            if let Some(hdr) = &mut self.hdr {
+                for column in 0..columns {
+                    hdr[to + column] = src.hdr_color_at(column, row);
+                }
+            }
+        }
+    }
+
+    // Writes the float colors as a Radiance .hdr file, one shared exponent byte per pixel (RGBE) with
+    // flat, uncompressed scanlines. Negative and non finite channels are written as 0
+    pub fn save_hdr(&self, file_path: &str) -> Result<(), String> {
+        let file = File::create(file_path).map_err(|e| format!("{}: {}", file_path, e))?;
+        let mut writer = BufWriter::new(file);
+
+        let mut bytes = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", self.height, self.width).into_bytes();
+        bytes.reserve(self.width * self.height * 4);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                bytes.extend_from_slice(&Canvas::rgbe(self.hdr_color_at(x, y)));
+            }
+        }
+
+        writer.write_all(&bytes).map_err(|e| format!("{}: {}", file_path, e))?;
+        return writer.flush().map_err(|e| format!("{}: {}", file_path, e));
+    }
+
+    fn rgbe(color: Color) -> [u8; 4] {
+        let channel = |value: f32| if value.is_finite() { value.max(0.0) } else { 0.0 };
+        let (r, g, b) = (channel(*color.r()), channel(*color.g()), channel(*color.b()));
+
+        let brightest = r.max(g).max(b);
+        if brightest < 1e-32 {
+            return [0, 0, 0, 0];
+        }
+
+        // Exponent with the brightest channel's mantissa in [0.5, 1), scaled into [128, 256)
+        let exponent = brightest.log2().floor() as i32 + 1;
+        let scale = 256.0 / 2f32.powi(exponent);
+        let byte = |value: f32| (value * scale).min(255.0) as u8;
+
+        return [byte(r), byte(g), byte(b), (exponent + 128).clamp(0, 255) as u8];
+    }
+
+    // Writes the float colors as an uncompressed single part OpenEXR file with 32 bit float R, G and B
+    // channels, which keeps everything above 1.0 for tone mapping in a compositor
+    pub fn save_exr(&self, file_path: &str) -> Result<(), String> {
+        let width = i32::try_from(self.width).map_err(|_| format!("{} pixels is too wide for an EXR", self.width))?;
+        let height = i32::try_from(self.height).map_err(|_| format!("{} pixels is too tall for an EXR", self.height))?;
+
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+
+        let mut attribute = |name: &str, kind: &str, value: &[u8]| {
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(kind.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&(value.len() as i32).to_le_bytes());
+            bytes.extend_from_slice(value);
+        };
+
+        // Channels are listed, and stored in each scanline, in alphabetical order
+        let mut channels: Vec<u8> = Vec::new();
+        for name in ["B", "G", "R"] {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&2i32.to_le_bytes()); // FLOAT
+            channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+            channels.extend_from_slice(&1i32.to_le_bytes());
+            channels.extend_from_slice(&1i32.to_le_bytes());
+        }
+        channels.push(0);
+
+        let window: Vec<u8> = [0, 0, width - 1, height - 1].iter().flat_map(|v| v.to_le_bytes()).collect();
+
+        attribute("channels", "chlist", &channels);
+        attribute("compression", "compression", &[0]);
+        attribute("dataWindow", "box2i", &window);
+        attribute("displayWindow", "box2i", &window);
+        attribute("lineOrder", "lineOrder", &[0]);
+        attribute("pixelAspectRatio", "float", &1f32.to_le_bytes());
+        attribute("screenWindowCenter", "v2f", &[0; 8]);
+        attribute("screenWindowWidth", "float", &1f32.to_le_bytes());
+        bytes.push(0);
+
+        // Offset table pointing at every scanline, each of which holds its y, its size and then the
+        // blue, green and red values of the whole row
+        let line_size = self.width * 3 * 4;
+        let first_line = bytes.len() + self.height * 8;
+        for y in 0..self.height {
+            let offset = (first_line + y * (line_size + 8)) as u64;
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+
+        for y in 0..self.height {
+            bytes.extend_from_slice(&(y as i32).to_le_bytes());
+            bytes.extend_from_slice(&(line_size as i32).to_le_bytes());
+
+            let row: Vec<Color> = (0..self.width).map(|x| self.hdr_color_at(x, y)).collect();
+            for channel in [Color::b, Color::g, Color::r] {
+                for color in &row {
+                    bytes.extend_from_slice(&channel(color).to_le_bytes());
+                }
+            }
+        }
+
+        let file = File::create(file_path).map_err(|e| format!("{}: {}", file_path, e))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&bytes).map_err(|e| format!("{}: {}", file_path, e))?;
+        return writer.flush().map_err(|e| format!("{}: {}", file_path, e));
+    }
+
+    // Writes the canvases as a looping animated GIF showing each for `delay` hundredths of a second.
+    // A GIF frame holds at most 256 colors, so each one gets its own palette picked by NeuQuant
+    pub fn write_gif(frames: &[Canvas], file_path: &str, delay: u16) -> Result<(), String> {
+        let first = frames.first().ok_or(String::from("An animation needs at least one frame"))?;
+        if frames.iter().any(|frame| frame.width != first.width || frame.height != first.height) {
+            return Err(String::from("Animation frames must all be the same size"));
+        }
+
+        let width = u16::try_from(first.width).map_err(|_| format!("{} pixels is too wide for a GIF", first.width))?;
+        let height = u16::try_from(first.height).map_err(|_| format!("{} pixels is too tall for a GIF", first.height))?;
+
+        let file = File::create(file_path).map_err(|e| format!("{}: {}", file_path, e))?;
+        let mut encoder = Encoder::new(file, width, height, &[]).map_err(|e| format!("{}: {}", file_path, e))?;
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("{}: {}", file_path, e))?;
+
+        for canvas in frames {
+            let rgb: Vec<u8> = canvas
+                .buffer
+                .iter()
+                .flat_map(|pixel| [(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8])
+                .collect();
+
+            let mut frame = Frame::from_rgb(width, height, &rgb);
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(|e| format!("{}: {}", file_path, e))?;
+        }
+
+        return Ok(());
+    }
+
+    // Largest difference in any channel of any pixel, and how many pixels differ at all. Compares the
+    // float colors where the canvases have them and the 8 bit colors otherwise
+    pub fn diff(&self, other: &Canvas) -> Result<(f32, usize), String> {
+        let errors = self.pixel_errors(other)?;
+        let max_error = errors.iter().fold(0.0, |max: f32, error| max.max(*error));
+        let differing = errors.iter().filter(|error| **error > 0.0).count();
+
+        return Ok((max_error, differing));
+    }
+
+    // Heatmap of where the canvases differ, black where they match and up the Color::heat_ramp to red
+    // at the pixel with the largest difference
+    pub fn diff_image(&self, other: &Canvas) -> Result<Canvas, String> {
+        let errors = self.pixel_errors(other)?;
+        let max_error = errors.iter().fold(0.0, |max: f32, error| max.max(*error));
+
+        let mut image = Canvas::new(self.width, self.height);
+        for (i, error) in errors.iter().enumerate() {
+            let t = if max_error > 0.0 { error / max_error } else { 0.0 };
+            image.set_color(i % self.width, i / self.width, &Color::heat_ramp(t));
+        }
+
+        return Ok(image);
+    }
+
+    // Largest channel difference per pixel in row-major order
+    fn pixel_errors(&self, other: &Canvas) -> Result<Vec<f32>, String> {
+        if self.width != other.width || self.height != other.height {
+            return Err(format!(
+                "Can't compare a {}x{} image with a {}x{} one",
+                self.width, self.height, other.width, other.height,
+            ));
+        }
+
+        let mut errors: Vec<f32> = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (a, b) = (self.hdr_color_at(x, y), other.hdr_color_at(x, y));
+                let error = (a.r() - b.r()).abs().max((a.g() - b.g()).abs()).max((a.b() - b.b()).abs());
+                errors.push(error);
+            }
+        }
+
+        return Ok(errors);
+    }
+
+    // Red-cyan 3D image: red comes from the left eye and green and blue from the right
+    pub fn anaglyph(left: &Canvas, right: &Canvas) -> Result<Canvas, String> {
+        if left.width != right.width || left.height != right.height {
+            return Err(format!(
+                "Anaglyph images must be the same size, got {}x{} and {}x{}",
+                left.width, left.height, right.width, right.height,
+            ));
+        }
+
+        let mut image = Canvas::new(left.width, left.height);
+        for i in 0..image.buffer.len() {
+            image.buffer[i] = (left.buffer[i] & 0xFF0000) | (right.buffer[i] & 0x00FFFF);
+        }
+
+        return Ok(image);
+    }
 }
\ No newline at end of file
@@ -9,7 +9,13 @@ pub struct Color {
 }
 
 impl Color {
-    pub fn new(r: f32, g: f32, b: f32) -> Self {
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+    pub const RED: Color = Color::new(1.0, 0.0, 0.0);
+    pub const GREEN: Color = Color::new(0.0, 1.0, 0.0);
+    pub const BLUE: Color = Color::new(0.0, 0.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
         return Self {
             r, 
             g, 
@@ -17,6 +23,55 @@ impl Color {
         };
     }
 
+    pub const fn black() -> Self {
+        return Color::BLACK;
+    }
+
+    pub const fn white() -> Self {
+        return Color::WHITE;
+    }
+
+    pub fn r(&self) -> &f32 {
+        return &self.r;
+    }
+
+    pub fn g(&self) -> &f32 {
+        return &self.g;
+    }
+
+    pub fn b(&self) -> &f32 {
+        return &self.b;
+    }
+
+    // False once a channel has gone NaN or infinite, usually the sign of a shading bug
+    pub fn is_finite(&self) -> bool {
+        return self.r.is_finite() && self.g.is_finite() && self.b.is_finite();
+    }
+
+    // Perceived brightness of linear RGB with the Rec. 709 weights
+    pub fn luminance(&self) -> f32 {
+        return 0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b;
+    }
+
+    // Gray of the same luminance
+    pub fn grayscale(&self) -> Color {
+        let luminance = self.luminance();
+        return Color::new(luminance, luminance, luminance);
+    }
+
+    // Debug color ramp from black at 0 through blue, green and yellow to red at 1, values outside
+    // are clamped
+    pub fn heat_ramp(t: f32) -> Color {
+        const STOPS: [Color; 5] = [Color::BLACK, Color::BLUE, Color::GREEN, Color::new(1.0, 1.0, 0.0), Color::RED];
+
+        let scaled = util::clamp_f32(t, 0.0, 1.0) * (STOPS.len() - 1) as f32;
+        let index = (scaled.floor() as usize).min(STOPS.len() - 2);
+        let fraction = scaled - index as f32;
+
+        return STOPS[index] * (1.0 - fraction) + STOPS[index + 1] * fraction;
+    }
+
+    // NaN channels come out as 0 and infinite ones as 0 or 255 depending on the sign
     pub fn rgb(&self) -> u32 {
         let r = util::clamp_f32(self.r, 0.0, 1.0);
         let g = util::clamp_f32(self.g, 0.0, 1.0);
@@ -25,6 +80,79 @@ impl Color {
         let (r, g, b) = ((255.999 * r) as u32, (255.999 * g) as u32, (255.999 * b) as u32);
         return (r << 16) | (g << 8) | b;
     }
+
+    // Unpacks a 0xRRGGBB pixel as written by rgb back into channels in [0, 1]
+    pub fn from_rgb(pixel: u32) -> Self {
+        let channel = |shift: u32| ((pixel >> shift) & 0xFF) as f32 / 255.0;
+        return Color::new(channel(16), channel(8), channel(0));
+    }
+
+    // Like rgb, but each channel is rounded up to the next level once its fraction passes `threshold`
+    // instead of always rounding down. Varying the threshold per pixel over [0, 1) dithers the
+    // quantization so smooth gradients don't band
+    pub fn rgb_dithered(&self, threshold: f32) -> u32 {
+        let channel = |value: f32| ((255.0 * util::clamp_f32(value, 0.0, 1.0) + threshold) as u32).min(255);
+        return (channel(self.r) << 16) | (channel(self.g) << 8) | channel(self.b);
+    }
+
+    // Color of a black body at `kelvin`, e.g. 2700 for a warm bulb or 6500 for daylight. The chromaticity
+    // comes from Kang et al.'s fit of the Planckian locus, valid from 1667K to 25000K (temperatures
+    // outside are clamped), and is converted to linear RGB scaled so the brightest channel is 1
+    pub fn from_temperature(kelvin: f32) -> Self {
+        // The published coefficients carry more digits than f32 holds
+        let t = util::clamp_f32(kelvin, 1667.0, 25000.0) as f64;
+        let (t2, t3) = (t * t, t * t * t);
+
+        let x = if t <= 4000.0 {
+            -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910
+        } else {
+            -3.0258469e9 / t3 + 2.1070379e6 / t2 + 0.2226347e3 / t + 0.240390
+        };
+
+        let (x2, x3) = (x * x, x * x * x);
+        let y = if t <= 2222.0 {
+            -1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+        } else if t <= 4000.0 {
+            -0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+        } else {
+            3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+        };
+
+        // CIE XYZ with a luminance of 1, then into linear sRGB
+        let (cx, cy, cz) = ((x / y) as f32, 1.0, ((1.0 - x - y) / y) as f32);
+        let r = (3.2406 * cx - 1.5372 * cy - 0.4986 * cz).max(0.0);
+        let g = (-0.9689 * cx + 1.8758 * cy + 0.0415 * cz).max(0.0);
+        let b = (0.0557 * cx - 0.2040 * cy + 1.0570 * cz).max(0.0);
+
+        let brightest = util::max3(r, g, b);
+        return Color::new(r / brightest, g / brightest, b / brightest);
+    }
+
+    // Channels are divided by 255 as they are, without sRGB decoding. Images are written with the
+    // channel values unencoded too (see rgb), so a surface lit at full strength shows the exact code
+    // that was picked, #808080 is 0.5 in and 0x80 out
+    pub fn from_hex(hex: u32) -> Self {
+        let channel = |shift: u32| ((hex >> shift) & 0xff) as f32 / 255.0;
+        return Color::new(channel(16), channel(8), channel(0));
+    }
+
+    // Accepts "#rrggbb" or "rrggbb"
+    pub fn from_hex_str(hex: &str) -> Result<Self, String> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid hex color '{}'", hex));
+        }
+
+        let value = u32::from_str_radix(digits, 16).map_err(|_| format!("invalid hex color '{}'", hex))?;
+        return Ok(Color::from_hex(value));
+    }
+
+    // Inverse of from_hex, channels are clamped to [0, 1]. Unlike rgb, which truncates, channels are
+    // rounded to the nearest code so small float error doesn't knock a color down a level
+    pub fn to_hex(&self) -> u32 {
+        let channel = |value: f32| (util::clamp_f32(value, 0.0, 1.0) * 255.0).round() as u32;
+        return (channel(self.r) << 16) | (channel(self.g) << 8) | channel(self.b);
+    }
 }
 
 impl Add for Color {
@@ -98,11 +226,4 @@ impl PartialEq for Color {
             util::equals_f32(&self.g, &other.g) &&
             util::equals_f32(&self.b, &other.b);
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        return 
-            !util::equals_f32(&self.r, &other.r) ||
-            !util::equals_f32(&self.g, &other.g) ||
-            !util::equals_f32(&self.b, &other.b);
-    }
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::geometry::Vec4;
+use crate::image::Image;
+use crate::shape::{Cube, CubeFace};
+use crate::util;
+
+pub struct CubeMap {
+    pub left: Image,
+    pub right: Image,
+    pub front: Image,
+    pub back: Image,
+    pub up: Image,
+    pub down: Image,
+}
+
+// What a ray sees when it leaves the scene without hitting anything
+pub enum Environment {
+    Solid(Color),
+    // Latitude-longitude panorama, the centre of the image looks down +z and the top edge straight up
+    Equirectangular(Image),
+    // Six faces in the usual skybox layout as seen from the centre, the same mapping as Cube::uv_at
+    CubeMap(CubeMap),
+}
+
+impl Environment {
+    pub fn color_at(&self, direction: &Vec4) -> Color {
+        match self {
+            Environment::Solid(color) => return *color,
+            Environment::Equirectangular(image) => {
+                let d = direction.normalize();
+                let u = 0.5 + d.x().atan2(*d.z()) / (2.0 * PI);
+                let v = util::clamp_f32(*d.y(), -1.0, 1.0).acos() / PI;
+                return image.sample(u, v);
+            },
+            Environment::CubeMap(faces) => {
+                let (x, y, z) = (*direction.x(), *direction.y(), *direction.z());
+                let largest = util::max3(x.abs(), y.abs(), z.abs());
+                if largest == 0.0 {
+                    return Color::black();
+                }
+
+                let on_cube = Vec4::point(x / largest, y / largest, z / largest);
+                let (face, u, v) = Cube::uv_at(&on_cube);
+                let image = match face {
+                    CubeFace::Left => &faces.left,
+                    CubeFace::Right => &faces.right,
+                    CubeFace::Front => &faces.front,
+                    CubeFace::Back => &faces.back,
+                    CubeFace::Up => &faces.up,
+                    CubeFace::Down => &faces.down,
+                };
+
+                // Cube::uv_at counts v from the bottom of the face, images count rows from the top
+                return image.sample_clamped(u, 1.0 - v);
+            },
+        }
+    }
+}
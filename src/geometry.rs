@@ -7,19 +7,22 @@ pub struct Vec4 {
 }
 
 impl Vec4 {
-    pub fn point(x: f32, y: f32, z: f32) -> Self {
+    pub const ORIGIN: Vec4 = Vec4::point(0.0, 0.0, 0.0);
+    pub const ZERO: Vec4 = Vec4::vector(0.0, 0.0, 0.0);
+
+    pub const fn point(x: f32, y: f32, z: f32) -> Self {
         return Self {
             elements: [x, y, z, 1.0],
         };
     }
 
-    pub fn vector(x: f32, y: f32, z: f32) -> Self {
+    pub const fn vector(x: f32, y: f32, z: f32) -> Self {
         return Self {
             elements: [x, y, z, 0.0],
         };
     }
 
-    pub fn raw(x: f32, y: f32, z: f32, w: f32) -> Self {
+    pub const fn raw(x: f32, y: f32, z: f32, w: f32) -> Self {
         return Self {
             elements: [x, y, z, w],
         };
@@ -60,31 +63,92 @@ impl Vec4 {
 
     pub fn dot(&self, other: &Vec4) -> f32 {
         return 
-            &self.elements[0] * &other.elements[0] +
-            &self.elements[1] * &other.elements[1] +
-            &self.elements[2] * &other.elements[2] +
-            &self.elements[3] * &other.elements[3];      
+            self.elements[0] * other.elements[0] +
+            self.elements[1] * other.elements[1] +
+            self.elements[2] * other.elements[2] +
+            self.elements[3] * other.elements[3];      
     } 
 
     pub fn cross(&self, other: &Vec4) -> Self {
         return Vec4::vector(
-            &self.elements[1] * &other.elements[2] - &self.elements[2] * &other.elements[1], 
-            &self.elements[2] * &other.elements[0] - &self.elements[0] * &other.elements[2], 
-            &self.elements[0] * &other.elements[1] - &self.elements[1] * &other.elements[0],
+            self.elements[1] * other.elements[2] - self.elements[2] * other.elements[1], 
+            self.elements[2] * other.elements[0] - self.elements[0] * other.elements[2], 
+            self.elements[0] * other.elements[1] - self.elements[1] * other.elements[0],
         );
     }
 
+    pub fn distance(&self, other: &Vec4) -> f32 {
+        return self.distance_squared(other).sqrt();
+    }
+
+    pub fn distance_squared(&self, other: &Vec4) -> f32 {
+        let d = *self - *other;
+        return d.dot(&d);
+    }
+
+    // Component of this vector along `other`, the zero vector when `other` has no length
+    pub fn project_onto(&self, other: &Vec4) -> Vec4 {
+        let length_squared = other.dot(other);
+        if length_squared == 0.0 {
+            return Vec4::ZERO;
+        }
+
+        return *other * (self.dot(other) / length_squared);
+    }
+
+    // Angle in radians between two vectors, 0 when either has no length. The cosine is clamped so float
+    // error on (anti)parallel vectors can't push acos out of its domain
+    pub fn angle_between(&self, other: &Vec4) -> f32 {
+        let lengths = self.magnitude() * other.magnitude();
+        if lengths == 0.0 {
+            return 0.0;
+        }
+
+        return (self.dot(other) / lengths).clamp(-1.0, 1.0).acos();
+    }
+
+    // Two unit vectors perpendicular to this one and to each other, (tangent, bitangent), so that with
+    // the normalized vector they form a right handed frame. The axis helping with the cross product is
+    // picked away from the vector so the frame stays well conditioned
+    pub fn orthonormal_basis(&self) -> (Vec4, Vec4) {
+        let axis = self.normalize();
+        let helper = if axis.x().abs() < 0.9 { Vec4::vector(1.0, 0.0, 0.0) } else { Vec4::vector(0.0, 1.0, 0.0) };
+        let tangent = axis.cross(&helper).normalize();
+        let bitangent = axis.cross(&tangent);
+        return (tangent, bitangent);
+    }
+
+    // Unit direction within the cone around this vector whose half angle has cosine `cos_max`, e.g. 0
+    // for the hemisphere. u1 picks the height on the unit sphere between the axis and the rim, which
+    // keeps the directions uniform over the cone's solid angle, and u2 the turn around the axis, both
+    // as fractions in [0, 1). Random, stratified or spiral sampling all come down to their choice of u1
+    // and u2
+    pub fn cone_sample(&self, cos_max: f32, u1: f32, u2: f32) -> Vec4 {
+        let axis = self.normalize();
+        let (tangent, bitangent) = axis.orthonormal_basis();
+
+        let cos_theta = 1.0 - u1 * (1.0 - cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * std::f32::consts::PI * u2;
+
+        return axis * cos_theta + tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin());
+    }
+
     pub fn reflect(&self, normal: &Vec4) -> Vec4 {
-        return *self - *normal * 2.0 * self.dot(&normal);
+        return *self - *normal * 2.0 * self.dot(normal);
     }
 
     pub fn refract(&self, normalv: &Vec4, n_enter: f32, n_exit: f32) -> Vec4 {
         let n_ratio = n_enter / n_exit;
         let cos_theta = f32::min((-*self).dot(normalv), 1.0);
         let r_out_perp = (*self + *normalv * cos_theta) * n_ratio;
-        let r_out_parallel = *normalv * -((1.0 - r_out_perp.magnitude()).abs()).sqrt();
+        let r_out_parallel = *normalv * -((1.0 - r_out_perp.dot(&r_out_perp)).abs()).sqrt();
         return r_out_perp + r_out_parallel;
     }
+
+    pub fn lerp(&self, other: &Vec4, t: f32) -> Vec4 {
+        return *self + (*other - *self) * t;
+    }
 }
 
 impl Add for Vec4 {
@@ -146,18 +210,176 @@ impl Div<f32> for Vec4 {
 impl PartialEq for Vec4 {
     fn eq(&self, other: &Self) -> bool {
         return 
-            util::equals_f32(&self.x(), &other.x()) &&
-            util::equals_f32(&self.y(), &other.y()) &&
-            util::equals_f32(&self.z(), &other.z()) &&
-            util::equals_f32(&self.w(), &other.w());
+            util::equals_f32(self.x(), other.x()) &&
+            util::equals_f32(self.y(), other.y()) &&
+            util::equals_f32(self.z(), other.z()) &&
+            util::equals_f32(self.w(), other.w());
     }
+}
 
-    fn ne(&self, other: &Self) -> bool {
-        return 
-        !util::equals_f32(&self.x(), &other.x()) ||
-        !util::equals_f32(&self.y(), &other.y()) ||
-        !util::equals_f32(&self.z(), &other.z()) ||
-        !util::equals_f32(&self.w(), &other.w()); 
+// Three values are a position, so they become a point with w = 1
+impl From<[f32; 3]> for Vec4 {
+    fn from(xyz: [f32; 3]) -> Self {
+        return Vec4::point(xyz[0], xyz[1], xyz[2]);
+    }
+}
+
+impl From<(f32, f32, f32)> for Vec4 {
+    fn from((x, y, z): (f32, f32, f32)) -> Self {
+        return Vec4::point(x, y, z);
+    }
+}
+
+// Four values are taken as they are, w included
+impl From<[f32; 4]> for Vec4 {
+    fn from(elements: [f32; 4]) -> Self {
+        return Self {
+            elements,
+        };
+    }
+}
+
+impl From<Vec4> for [f32; 4] {
+    fn from(vec: Vec4) -> Self {
+        return vec.elements;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Quaternion {
+    elements: [f32; 4],
+}
+
+impl Quaternion {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        return Self {
+            elements: [x, y, z, w],
+        };
+    }
+
+    pub fn identity() -> Self {
+        return Quaternion::new(0.0, 0.0, 0.0, 1.0);
+    }
+
+    pub fn from_axis_angle(axis: &Vec4, radians: f32) -> Self {
+        let axis = axis.normalize();
+        let half_sin = (radians / 2.0).sin();
+        return Quaternion::new(axis.x() * half_sin, axis.y() * half_sin, axis.z() * half_sin, (radians / 2.0).cos());
+    }
+
+    // Expects the upper 3x3 of the matrix to be a pure rotation (orthonormal, determinant of 1)
+    pub fn from_rotation_matrix(m: &Matrix4x4) -> Self {
+        let trace = m.get(0, 0) + m.get(1, 1) + m.get(2, 2);
+
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new(
+                (m.get(2, 1) - m.get(1, 2)) / s,
+                (m.get(0, 2) - m.get(2, 0)) / s,
+                (m.get(1, 0) - m.get(0, 1)) / s,
+                0.25 * s,
+            )
+        } else if m.get(0, 0) > m.get(1, 1) && m.get(0, 0) > m.get(2, 2) {
+            let s = (1.0 + m.get(0, 0) - m.get(1, 1) - m.get(2, 2)).sqrt() * 2.0;
+            Quaternion::new(
+                0.25 * s,
+                (m.get(0, 1) + m.get(1, 0)) / s,
+                (m.get(0, 2) + m.get(2, 0)) / s,
+                (m.get(2, 1) - m.get(1, 2)) / s,
+            )
+        } else if m.get(1, 1) > m.get(2, 2) {
+            let s = (1.0 + m.get(1, 1) - m.get(0, 0) - m.get(2, 2)).sqrt() * 2.0;
+            Quaternion::new(
+                (m.get(0, 1) + m.get(1, 0)) / s,
+                0.25 * s,
+                (m.get(1, 2) + m.get(2, 1)) / s,
+                (m.get(0, 2) - m.get(2, 0)) / s,
+            )
+        } else {
+            let s = (1.0 + m.get(2, 2) - m.get(0, 0) - m.get(1, 1)).sqrt() * 2.0;
+            Quaternion::new(
+                (m.get(0, 2) + m.get(2, 0)) / s,
+                (m.get(1, 2) + m.get(2, 1)) / s,
+                0.25 * s,
+                (m.get(1, 0) - m.get(0, 1)) / s,
+            )
+        };
+
+        return q.normalize();
+    }
+
+    pub fn x(&self) -> &f32 {
+        return &self.elements[0];
+    }
+
+    pub fn y(&self) -> &f32 {
+        return &self.elements[1];
+    }
+
+    pub fn z(&self) -> &f32 {
+        return &self.elements[2];
+    }
+
+    pub fn w(&self) -> &f32 {
+        return &self.elements[3];
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f32 {
+        return
+            self.elements[0] * other.elements[0] +
+            self.elements[1] * other.elements[1] +
+            self.elements[2] * other.elements[2] +
+            self.elements[3] * other.elements[3];
+    }
+
+    pub fn normalize(&self) -> Self {
+        let magnitude = self.dot(self).sqrt();
+        return Quaternion::new(self.x() / magnitude, self.y() / magnitude, self.z() / magnitude, self.w() / magnitude);
+    }
+
+    // Spherical interpolation along the shortest arc, falling back to a normalized lerp when the
+    // two rotations are nearly identical
+    pub fn slerp(&self, other: &Quaternion, t: f32) -> Self {
+        let mut other = *other;
+        let mut cos_theta = self.dot(&other);
+
+        if cos_theta < 0.0 {
+            other = Quaternion::new(-other.x(), -other.y(), -other.z(), -other.w());
+            cos_theta = -cos_theta;
+        }
+
+        let (a, b) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (((1.0 - t) * theta).sin() / sin_theta, (t * theta).sin() / sin_theta)
+        };
+
+        return Quaternion::new(
+            self.x() * a + other.x() * b,
+            self.y() * a + other.y() * b,
+            self.z() * a + other.z() * b,
+            self.w() * a + other.w() * b,
+        ).normalize();
+    }
+
+    pub fn to_matrix(&self) -> Matrix4x4 {
+        let (x, y, z, w) = (*self.x(), *self.y(), *self.z(), *self.w());
+
+        return Matrix4x4::new([
+            1.0 - 2.0 * (y*y + z*z), 2.0 * (x*y - z*w), 2.0 * (x*z + y*w), 0.0,
+            2.0 * (x*y + z*w), 1.0 - 2.0 * (x*x + z*z), 2.0 * (y*z - x*w), 0.0,
+            2.0 * (x*z - y*w), 2.0 * (y*z + x*w), 1.0 - 2.0 * (x*x + y*y), 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+    }
+}
+
+impl PartialEq for Quaternion {
+    // q and -q describe the same rotation
+    fn eq(&self, other: &Self) -> bool {
+        return util::equals_f32(&self.dot(other).abs(), &1.0);
     }
 }
 
@@ -178,7 +400,7 @@ impl Matrix2x2 {
     }
 
     pub fn determinant(&self) -> f32 {
-        return &self.mat[0] * &self.mat[3] - &self.mat[1] * &self.mat[2];
+        return self.mat[0] * self.mat[3] - self.mat[1] * self.mat[2];
     }
 }
 
@@ -190,14 +412,6 @@ impl PartialEq for Matrix2x2 {
             util::equals_f32(&self.mat[2], &other.mat[2]) &&
             util::equals_f32(&self.mat[3], &other.mat[3]);
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        return 
-            !util::equals_f32(&self.mat[0], &other.mat[0]) ||
-            !util::equals_f32(&self.mat[1], &other.mat[1]) ||
-            !util::equals_f32(&self.mat[2], &other.mat[2]) ||
-            !util::equals_f32(&self.mat[3], &other.mat[3]);
-    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -239,7 +453,7 @@ impl Matrix3x3 {
 
     pub fn cofactor(&self, row: usize, col: usize) -> f32 {
         let determinant = self.minor(row, col);
-        if (row + col) % 2 != 0 {
+        if !(row + col).is_multiple_of(2) {
             return -determinant;
         }
         return determinant;
@@ -266,19 +480,17 @@ impl PartialEq for Matrix3x3 {
             util::equals_f32(&self.mat[7], &other.mat[7]) &&
             util::equals_f32(&self.mat[8], &other.mat[8]);
     }
+}
 
-    fn ne(&self, other: &Self) -> bool {
-        return 
-            !util::equals_f32(&self.mat[0], &other.mat[0]) ||
-            !util::equals_f32(&self.mat[1], &other.mat[1]) ||
-            !util::equals_f32(&self.mat[2], &other.mat[2]) ||
-            !util::equals_f32(&self.mat[3], &other.mat[3]) ||
-            !util::equals_f32(&self.mat[4], &other.mat[4]) ||
-            !util::equals_f32(&self.mat[5], &other.mat[5]) ||
-            !util::equals_f32(&self.mat[6], &other.mat[6]) ||
-            !util::equals_f32(&self.mat[7], &other.mat[7]) ||
-            !util::equals_f32(&self.mat[8], &other.mat[8]);
-    }
+// Which way the x axis runs on screen for a camera looking along its view direction with y up. The
+// crate's own convention is Left, as in The Ray Tracer Challenge, Unity and Direct3D: with y up, a camera
+// looking towards +z sees +x on its right and one looking towards -z sees it on its left. Right matches
+// OpenGL and Blender, where a camera looking towards -z sees +x on its right
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Handedness {
+    #[default]
+    Left,
+    Right,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -338,7 +550,7 @@ impl Matrix4x4 {
 
     pub fn cofactor(&self, row: usize, col: usize) -> f32 {
         let determinant = self.minor(row, col);
-        if (row + col) % 2 != 0 {
+        if !(row + col).is_multiple_of(2) {
             return -determinant;
         }
         return determinant;
@@ -356,6 +568,15 @@ impl Matrix4x4 {
         return !util::equals_f32(&self.determinant(), &0.0);
     }
 
+    // Element-wise comparison with a caller-chosen tolerance, == uses util::THRESHOLD_F32
+    pub fn approx_eq(&self, other: &Matrix4x4, epsilon: f32) -> bool {
+        return self.mat.iter().zip(other.mat.iter()).all(|(a, b)| (a - b).abs() <= epsilon);
+    }
+
+    pub fn is_identity(&self) -> bool {
+        return *self == Matrix4x4::identity();
+    }
+
     pub fn invert(&self) -> Self {
         if !self.is_invertible(){
             panic!();
@@ -451,14 +672,87 @@ impl Matrix4x4 {
         return Matrix4x4::shearing(xy, xz, yx, yz, zx, zy) * *self;
     }
 
+    // Splits an affine transform without shear into translation, rotation and scale so that
+    // compose(decompose(m)) == m
+    pub fn decompose(&self) -> (Vec4, Quaternion, Vec4) {
+        let translation = Vec4::vector(*self.get(0, 3), *self.get(1, 3), *self.get(2, 3));
+
+        let mut sx = Vec4::vector(*self.get(0, 0), *self.get(1, 0), *self.get(2, 0)).magnitude();
+        let sy = Vec4::vector(*self.get(0, 1), *self.get(1, 1), *self.get(2, 1)).magnitude();
+        let sz = Vec4::vector(*self.get(0, 2), *self.get(1, 2), *self.get(2, 2)).magnitude();
+
+        if self.submatrix(3, 3).determinant() < 0.0 {
+            sx = -sx;
+        }
+
+        let rotation = Matrix4x4::new([
+            self.get(0, 0) / sx, self.get(0, 1) / sy, self.get(0, 2) / sz, 0.0,
+            self.get(1, 0) / sx, self.get(1, 1) / sy, self.get(1, 2) / sz, 0.0,
+            self.get(2, 0) / sx, self.get(2, 1) / sy, self.get(2, 2) / sz, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+
+        return (translation, Quaternion::from_rotation_matrix(&rotation), Vec4::vector(sx, sy, sz));
+    }
+
+    pub fn compose(translation: &Vec4, rotation: &Quaternion, scale: &Vec4) -> Self {
+        return Matrix4x4::translation(*translation.x(), *translation.y(), *translation.z())
+            * rotation.to_matrix()
+            * Matrix4x4::scale(*scale.x(), *scale.y(), *scale.z());
+    }
+
+    // Interpolates translation and scale linearly and rotation spherically, which avoids the
+    // skew that comes from lerping matrix elements directly
+    pub fn interpolate(&self, other: &Matrix4x4, t: f32) -> Self {
+        let (translation1, rotation1, scale1) = self.decompose();
+        let (translation2, rotation2, scale2) = other.decompose();
+
+        return Matrix4x4::compose(
+            &translation1.lerp(&translation2, t),
+            &rotation1.slerp(&rotation2, t),
+            &scale1.lerp(&scale2, t),
+        );
+    }
+
+    // Object to world transform that places an object at `eye` with its -z axis pointing at `target` and
+    // its +y axis towards `up`, e.g. to aim a cone or spotlight mesh. The axes are kept unit length so the
+    // object is only turned and moved, never stretched. This is the inverse of view_transformation, which
+    // goes the other way, from world space into the space of a camera at `eye`
+    pub fn look_at(eye: Vec4, target: Vec4, up: Vec4) -> Matrix4x4 {
+        let forward = (target - eye).normalize();
+        let left = forward.cross(&up.normalize()).normalize();
+        let true_up = left.cross(&forward);
+
+        return Matrix4x4::new([
+            *left.x(), *true_up.x(), -*forward.x(), *eye.x(),
+            *left.y(), *true_up.y(), -*forward.y(), *eye.y(),
+            *left.z(), *true_up.z(), -*forward.z(), *eye.z(),
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+    }
+
+    // World to camera transform for a camera at `from` looking at `to`, see look_at for placing objects.
+    // Uses the crate's left-handed convention, see Handedness
     pub fn view_transformation(from: Vec4, to: Vec4, up: Vec4) -> Matrix4x4 {
+        return Matrix4x4::view_transformation_handed(from, to, up, Handedness::Left);
+    }
+
+    // view_transformation for a scene laid out in the given handedness. Right mirrors the camera's x axis,
+    // so a scene modelled right-handed shows up the way it would in a right-handed engine
+    pub fn view_transformation_handed(from: Vec4, to: Vec4, up: Vec4, handedness: Handedness) -> Matrix4x4 {
         let forward = (to - from).normalize();
         let upn = up.normalize();
         let left = forward.cross(&upn);
         let true_up = left.cross(&forward);
 
+        // Camera space +x is drawn on the left of the image, see Camera::ray_for_pixel
+        let screen_x = match handedness {
+            Handedness::Left => left,
+            Handedness::Right => -left,
+        };
+
         let orientation = Matrix4x4::new([
-            *left.x(), *left.y(), *left.z(), 0.0,
+            *screen_x.x(), *screen_x.y(), *screen_x.z(), 0.0,
             *true_up.x(), *true_up.y(), *true_up.z(), 0.0,
             -*forward.x(), -*forward.y(), -*forward.z(), 0.0,
             0.0, 0.0, 0.0, 1.0,
@@ -539,24 +833,61 @@ impl PartialEq for Matrix4x4 {
             util::equals_f32(&self.mat[14], &other.mat[14]) &&
             util::equals_f32(&self.mat[15], &other.mat[15]);
     }
+}
 
-    fn ne(&self, other: &Self) -> bool {
-        return 
-            !util::equals_f32(&self.mat[0], &other.mat[0]) ||
-            !util::equals_f32(&self.mat[1], &other.mat[1]) ||
-            !util::equals_f32(&self.mat[2], &other.mat[2]) ||
-            !util::equals_f32(&self.mat[3], &other.mat[3]) ||
-            !util::equals_f32(&self.mat[4], &other.mat[4]) ||
-            !util::equals_f32(&self.mat[5], &other.mat[5]) ||
-            !util::equals_f32(&self.mat[6], &other.mat[6]) ||
-            !util::equals_f32(&self.mat[7], &other.mat[7]) ||
-            !util::equals_f32(&self.mat[8], &other.mat[8]) ||
-            !util::equals_f32(&self.mat[9], &other.mat[9]) ||
-            !util::equals_f32(&self.mat[10], &other.mat[10]) ||
-            !util::equals_f32(&self.mat[11], &other.mat[11]) ||
-            !util::equals_f32(&self.mat[12], &other.mat[12]) ||
-            !util::equals_f32(&self.mat[13], &other.mat[13]) ||
-            !util::equals_f32(&self.mat[14], &other.mat[14]) ||
-            !util::equals_f32(&self.mat[15], &other.mat[15]);
-    }
-}
\ No newline at end of file
+// Builds a transform from steps listed in the order they happen to the object, e.g.
+// TransformBuilder::new().scale(2.0, 2.0, 2.0).rotate_y(PI).translate(0.0, 1.0, 0.0) scales first and
+// moves last, the same as translation * rotatation_y * scale written out by hand
+#[derive(Debug, Clone, Copy)]
+pub struct TransformBuilder {
+    matrix: Matrix4x4,
+}
+
+impl TransformBuilder {
+    pub fn new() -> Self {
+        return Self {
+            matrix: Matrix4x4::identity(),
+        };
+    }
+
+    // Applies `step` after everything added so far
+    pub fn then(&self, step: Matrix4x4) -> Self {
+        return Self {
+            matrix: step * self.matrix,
+        };
+    }
+
+    pub fn translate(&self, x: f32, y: f32, z: f32) -> Self {
+        return self.then(Matrix4x4::translation(x, y, z));
+    }
+
+    pub fn scale(&self, x: f32, y: f32, z: f32) -> Self {
+        return self.then(Matrix4x4::scale(x, y, z));
+    }
+
+    pub fn rotate_x(&self, radians: f32) -> Self {
+        return self.then(Matrix4x4::rotatation_x(radians));
+    }
+
+    pub fn rotate_y(&self, radians: f32) -> Self {
+        return self.then(Matrix4x4::rotatation_y(radians));
+    }
+
+    pub fn rotate_z(&self, radians: f32) -> Self {
+        return self.then(Matrix4x4::rotatation_z(radians));
+    }
+
+    pub fn shear(&self, xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Self {
+        return self.then(Matrix4x4::shearing(xy, xz, yx, yz, zx, zy));
+    }
+
+    pub fn build(&self) -> Matrix4x4 {
+        return self.matrix;
+    }
+}
+
+impl Default for TransformBuilder {
+    fn default() -> Self {
+        return TransformBuilder::new();
+    }
+}
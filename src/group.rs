@@ -0,0 +1,210 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::geometry::{Matrix4x4, Vec4};
+use crate::id;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::model::Model;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::util;
+
+#[derive(Debug)]
+pub struct LoadError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}: {}", self.path.display(), self.message);
+    }
+}
+
+// A collection of shapes that move together. The group transform is baked into every child when it
+// is added or changed, so hits are reported against the children themselves and keep their materials
+pub struct Group {
+    pub id: Uuid,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
+    pub material: Material,
+    enabled: bool,
+    pub children: Vec<Box<dyn Shape>>,
+}
+
+impl Group {
+    pub fn new(material: Material) -> Self {
+        return Group::with_id(material, id::new_id());
+    }
+
+    pub fn with_id(material: Material, id: Uuid) -> Self {
+        return Self {
+            id,
+            transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
+            material,
+            enabled: true,
+            children: Vec::new(),
+        };
+    }
+
+    pub fn add_child(&mut self, mut child: Box<dyn Shape>) {
+        child.set_transform(self.transform * *child.transform());
+        self.children.push(child);
+    }
+
+    // Loads every .obj file in the directory (not recursively) as a Model child, in file name order.
+    // Every file is attempted, and all the failures are returned together if any of them went wrong
+    pub fn from_obj_dir<P: AsRef<Path>>(path: P, material: Material) -> Result<Group, Vec<LoadError>> {
+        let path = path.as_ref();
+
+        let entries = fs::read_dir(path).map_err(|e| {
+            vec![LoadError { path: path.to_path_buf(), message: e.to_string() }]
+        })?;
+
+        let mut errors: Vec<LoadError> = Vec::new();
+        let mut files: Vec<PathBuf> = Vec::new();
+
+        for entry in entries {
+            match entry {
+                Ok(entry) => {
+                    let file = entry.path();
+                    let is_obj = file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("obj"));
+                    if is_obj && file.is_file() {
+                        files.push(file);
+                    }
+                },
+                Err(e) => errors.push(LoadError { path: path.to_path_buf(), message: e.to_string() }),
+            }
+        }
+
+        files.sort();
+
+        let mut group = Group::new(material);
+
+        for file in files {
+            let file_path = match file.to_str() {
+                Some(file_path) => file_path,
+                None => {
+                    errors.push(LoadError { path: file.clone(), message: String::from("path is not valid UTF-8") });
+                    continue;
+                },
+            };
+
+            match Model::load(group.material.clone(), file_path) {
+                Ok(model) => group.add_child(Box::new(model)),
+                Err(message) => errors.push(LoadError { path: file.clone(), message }),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        return Ok(group);
+    }
+}
+
+impl Shape for Group {
+    fn id(&self) -> &Uuid {
+        return &self.id;
+    }
+
+    // The children are copied along with the group, so it can only be copied if all of them can
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        let children = self.children.iter().map(|child| child.clone_shape()).collect::<Option<Vec<_>>>()?;
+        return Some(Box::new(Group {
+            id: id::new_id(),
+            transform: self.transform,
+            inverse_transform: self.inverse_transform,
+            material: self.material.clone(),
+            enabled: self.enabled,
+            children,
+        }));
+    }
+
+    fn transform(&self) -> &Matrix4x4 {
+        return &self.transform;
+    }
+
+    // Children already carry the old group transform, so only the change is applied to them
+    fn set_transform(&mut self, matrix: Matrix4x4) {
+        let delta = matrix * self.inverse_transform;
+
+        for child in self.children.iter_mut() {
+            let transform = delta * *child.transform();
+            child.set_transform(transform);
+        }
+
+        self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
+    }
+
+    fn material(&self) -> &Material {
+        return &self.material;
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        return &mut self.material;
+    }
+
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let world_ray = ray.transform(self.transform);
+        let mut xs: Vec<Intersection> = Vec::new();
+
+        for child in self.children.iter().filter(|child| child.enabled()) {
+            xs.append(&mut Intersection::intersect(child.as_ref(), world_ray));
+        }
+
+        return xs;
+    }
+
+    // Hits always point at a child, so a group is never asked for its own normal
+    fn local_normal_at(&self, _: &Vec4, _: Intersection) -> Vec4 {
+        return Vec4::ZERO;
+    }
+
+    fn world_normal_at(&self, _: &Vec4, _: Intersection) -> Vec4 {
+        return Vec4::ZERO;
+    }
+
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        if self.children.is_empty() {
+            return self.transform * Vec4::ORIGIN;
+        }
+
+        let scaled = util::clamp_f32(u, 0.0, 1.0) * self.children.len() as f32;
+        let index = usize::min(scaled.floor() as usize, self.children.len() - 1);
+        // Children already include the group transform, so their samples are in world space
+        return self.children[index].sample_surface(scaled - index as f32, v);
+    }
+
+    fn children(&self) -> Option<&[Box<dyn Shape>]> {
+        return Some(&self.children);
+    }
+
+    fn children_mut(&mut self) -> Option<&mut [Box<dyn Shape>]> {
+        return Some(&mut self.children);
+    }
+
+    // Children already carry the group transform
+    fn children_to_parent(&self) -> Matrix4x4 {
+        return Matrix4x4::identity();
+    }
+}
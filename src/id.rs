@@ -0,0 +1,45 @@
+use std::cell::RefCell;
+
+use uuid::Uuid;
+
+// Hands out ids in order, so scenes built the same way get the same ids on every run
+pub struct IdGenerator {
+    next: u128,
+}
+
+impl IdGenerator {
+    pub fn sequential(start: u128) -> Self {
+        return Self {
+            next: start,
+        };
+    }
+
+    pub fn next_id(&mut self) -> Uuid {
+        let id = Uuid::from_u128(self.next);
+        self.next += 1;
+        return id;
+    }
+}
+
+thread_local! {
+    static GENERATOR: RefCell<Option<IdGenerator>> = const { RefCell::new(None) };
+}
+
+// Id for a newly built shape or light: random by default, sequential on this thread after
+// use_sequential_ids. The setting is per thread so parallel tests don't hand out each other's ids
+pub fn new_id() -> Uuid {
+    return GENERATOR.with(|generator| {
+        match generator.borrow_mut().as_mut() {
+            Some(generator) => generator.next_id(),
+            None => Uuid::new_v4(),
+        }
+    });
+}
+
+pub fn use_sequential_ids(start: u128) {
+    GENERATOR.with(|generator| *generator.borrow_mut() = Some(IdGenerator::sequential(start)));
+}
+
+pub fn use_random_ids() {
+    GENERATOR.with(|generator| *generator.borrow_mut() = None);
+}
@@ -0,0 +1,158 @@
+use std::fs;
+
+use crate::color::Color;
+use crate::util;
+
+// Unquantized pixel grid used for textures and environment maps. Unlike Canvas the colors are kept
+// as floats, so values above 1.0 (HDR skies) survive
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        if pixels.len() != width * height {
+            panic!("Image of {}x{} needs {} pixels, got {}", width, height, width * height, pixels.len());
+        }
+
+        return Self {
+            width,
+            height,
+            pixels,
+        };
+    }
+
+    pub fn filled(width: usize, height: usize, color: Color) -> Self {
+        return Image::new(width, height, vec![color; width * height]);
+    }
+
+    pub fn pixel_at(&self, x: usize, y: usize) -> Color {
+        return self.pixels[x + y * self.width];
+    }
+
+    // Bilinear lookup with (0, 0) at the top left and (1, 1) at the bottom right. u wraps around so
+    // panoramas join up at the seam, v is clamped to the edges
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        return self.sample_with(u, v, true);
+    }
+
+    // Bilinear lookup that clamps both coordinates, for images that don't tile such as cube map faces
+    pub fn sample_clamped(&self, u: f32, v: f32) -> Color {
+        return self.sample_with(u, v, false);
+    }
+
+    fn sample_with(&self, u: f32, v: f32, wrap_u: bool) -> Color {
+        if self.pixels.is_empty() {
+            return Color::black();
+        }
+
+        let u = if wrap_u { u.rem_euclid(1.0) } else { util::clamp_f32(u, 0.0, 1.0) };
+        let x = u * self.width as f32 - 0.5;
+        let y = util::clamp_f32(v, 0.0, 1.0) * self.height as f32 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+
+        let column = |x: f32| {
+            if wrap_u {
+                return (x as i64).rem_euclid(self.width as i64) as usize;
+            }
+            return (x.max(0.0) as usize).min(self.width - 1);
+        };
+        let row = |y: f32| (y.max(0.0) as usize).min(self.height - 1);
+
+        let (left, right) = (column(x0), column(x0 + 1.0));
+        let (top, bottom) = (row(y0), row(y0 + 1.0));
+
+        let upper = self.pixel_at(left, top) * (1.0 - fx) + self.pixel_at(right, top) * fx;
+        let lower = self.pixel_at(left, bottom) * (1.0 - fx) + self.pixel_at(right, bottom) * fx;
+
+        return upper * (1.0 - fy) + lower * fy;
+    }
+
+    // Reads plain (P3) and binary (P6) PPM files, colors are scaled by the file's maximum value
+    pub fn load_ppm(file_path: &str) -> Result<Image, String> {
+        let bytes = fs::read(file_path).map_err(|e| format!("{}: {}", file_path, e))?;
+        return Image::parse_ppm(&bytes).map_err(|e| format!("{}: {}", file_path, e));
+    }
+
+    pub fn parse_ppm(bytes: &[u8]) -> Result<Image, String> {
+        let mut position = 0;
+        let mut header: Vec<String> = Vec::new();
+
+        // Magic number, width, height and maximum value, separated by whitespace and # comments
+        while header.len() < 4 {
+            while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+
+            if position >= bytes.len() {
+                return Err(String::from("unexpected end of header"));
+            }
+
+            if bytes[position] == b'#' {
+                while position < bytes.len() && bytes[position] != b'\n' {
+                    position += 1;
+                }
+                continue;
+            }
+
+            let start = position;
+            while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            header.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+        }
+
+        let parse = |token: &str, name: &str| -> Result<usize, String> {
+            return token.parse::<usize>().map_err(|_| format!("invalid {} '{}'", name, token));
+        };
+
+        let width = parse(&header[1], "width")?;
+        let height = parse(&header[2], "height")?;
+        let max_value = parse(&header[3], "maximum value")?;
+
+        if max_value == 0 || max_value > 255 {
+            return Err(format!("unsupported maximum value {}", max_value));
+        }
+
+        // Image::sample needs at least one pixel to look up
+        if width == 0 || height == 0 {
+            return Err(format!("image must be at least 1x1, found {}x{}", width, height));
+        }
+
+        // The sizes come straight from the file, so a bogus header must not overflow the sample count
+        let count = width.checked_mul(height).and_then(|pixels| pixels.checked_mul(3)).ok_or_else(|| String::from("image too large"))?;
+        let values: Vec<u8> = match header[0].as_str() {
+            "P3" => {
+                let text = String::from_utf8_lossy(&bytes[position..]);
+                text.lines()
+                    .map(|line| line.split('#').next().unwrap_or(""))
+                    .flat_map(|line| line.split_whitespace())
+                    .map(|token| token.parse::<u8>().map_err(|_| format!("invalid sample '{}'", token)))
+                    .collect::<Result<Vec<u8>, String>>()?
+            },
+            "P6" => {
+                // A single whitespace byte separates the header from the binary samples
+                bytes.get(position + 1..).unwrap_or(&[]).to_vec()
+            },
+            magic => return Err(format!("unsupported format '{}'", magic)),
+        };
+
+        if values.len() < count {
+            return Err(format!("expected {} samples, found {}", count, values.len()));
+        }
+
+        let scale = 1.0 / max_value as f32;
+        let pixels = values[..count]
+            .chunks(3)
+            .map(|rgb| Color::new(rgb[0] as f32 * scale, rgb[1] as f32 * scale, rgb[2] as f32 * scale))
+            .collect();
+
+        return Ok(Image::new(width, height, pixels));
+    }
+}
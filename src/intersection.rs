@@ -1,3 +1,4 @@
+use crate::color::Color;
 use crate::geometry::Vec4;
 use crate::ray::Ray;
 use crate::shape::Shape;
@@ -9,6 +10,8 @@ pub struct Intersection<'a> {
     pub t: f32,
     pub u: f32,
     pub v: f32,
+    // Index of the part that was hit inside a composite shape such as a Model
+    pub child: usize,
 }
 
 impl<'a> Intersection<'a> {
@@ -18,6 +21,7 @@ impl<'a> Intersection<'a> {
             t,
             u: 0.0,
             v: 0.0,
+            child: 0,
         }
     }
 
@@ -27,11 +31,22 @@ impl<'a> Intersection<'a> {
             t,
             u,
             v,
+            child: 0,
         };
     }
 
-    pub fn intersect(shape: &'a dyn Shape, ray: Ray) -> Vec<Intersection> {
-        let ray = ray.transform(shape.transform().invert());
+    pub fn from_child(object: &'a dyn Shape, t: f32, u: f32, v: f32, child: usize) -> Self {
+        return Self {
+            object,
+            t,
+            u,
+            v,
+            child,
+        };
+    }
+
+    pub fn intersect(shape: &'a dyn Shape, ray: Ray) -> Vec<Intersection<'a>> {
+        let ray = ray.transform(shape.inverse_transform());
         return shape.local_intersect(&ray);
     }
 
@@ -39,55 +54,32 @@ impl<'a> Intersection<'a> {
         inter.retain(|x| x.t > 0.0);
         inter.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
 
-        if inter.len() < 1 {
+        if inter.is_empty() {
             return None;
         }
 
-        return Some(inter[0]);
-    }
-
-    pub fn prepare_computations(&self, ray: &Ray, xs: Option<&Vec<Intersection>>) -> Comp {
-        let mut n1: f32 = 1.0;
-        let mut n2: f32 = 1.0;
-
-        let mut stack: Vec<&dyn Shape> = Vec::new();
-    
-        if let Some(xs) = xs {
-            for inter in xs {
-                if stack.is_empty() {
-                    n1 = 1.0;
-                } else {
-                    n1 = stack.last().unwrap().material().refraction;
-                }
+        // Coincident surfaces land within rounding error of each other, and a plain sort would pick
+        // between them pixel by pixel. Anything within THRESHOLD_F32 of the nearest hit counts as a
+        // tie that goes to the lowest shape id, so the same surface shows everywhere
+        let nearest = inter[0].t;
+        let winner = inter
+            .iter()
+            .take_while(|x| x.t - nearest < util::THRESHOLD_F32)
+            .min_by_key(|x| *x.object.id())
+            .copied();
 
-                let mut object_in_stack = false;
-                for i in 0..stack.len() {
-                    if stack[i].id() == inter.object.id() {
-                        object_in_stack = true;
-                        stack.remove(i);
-                        break;
-                    }
-                }
-
-                if !object_in_stack {
-                    stack.push(inter.object);
-                }
-
-                if stack.is_empty() {
-                    n2 = 1.0;
-                } else {
-                    n2 = stack.last().unwrap().material().refraction;
-                }
+        return winner;
+    }
 
-                if self.object.id() == inter.object.id() && self.t == inter.t {
-                    break;
-                }
-            }
-        }
+    pub fn prepare_computations(&self, ray: &Ray, xs: Option<&Vec<Intersection>>) -> Comp<'_> {
+        let (n1, n2) = match xs {
+            Some(xs) => refractive_indices(self, xs),
+            None => (1.0, 1.0),
+        };
 
-        let normalv = self.object.world_normal_at(&ray.at(self.t), xs.unwrap()[0]);
+        let normalv = self.object.world_normal_at(&ray.at(self.t), *self);
 
-        return Comp::new(
+        let mut comp = Comp::new(
             self.t,
             self.object,
             ray.at(self.t),
@@ -97,7 +89,92 @@ impl<'a> Intersection<'a> {
             n1,
             n2,
         );
+        comp.u = self.u;
+        comp.v = self.v;
+        comp.vertex_color = self.object.vertex_color(*self);
+
+        // Where the neighbouring pixel rays meet the tangent plane at the hit point
+        if let Some(differential) = ray.differential {
+            let point = comp.point;
+            let on_plane = |origin: Vec4, direction: Vec4| -> Vec4 {
+                let denominator = normalv.dot(&direction);
+                if denominator.abs() < util::THRESHOLD_F32 {
+                    return Vec4::ZERO;
+                }
+
+                let t = normalv.dot(&(point - origin)) / denominator;
+                return (origin + direction * t) - point;
+            };
+
+            comp.dpdx = on_plane(differential.x_origin, differential.x_direction);
+            comp.dpdy = on_plane(differential.y_origin, differential.y_direction);
+        }
+
+        return comp;
+    }
+}
+
+// Walks the intersections in order of t keeping a stack of the objects the ray is currently inside, and
+// returns the refractive index of the material being exited (n1) and entered (n2) at the hit.
+// xs should also hold the intersections behind the ray origin (t < 0): those seed the stack with the
+// objects the origin starts inside, e.g. a camera placed within a glass sphere.
+//
+// A ray through a mesh edge or vertex hits every triangle sharing it at the same t, which would toggle
+// the mesh in and out of the stack once per triangle. Coincident hits on different parts of the same
+// object count as one crossing. Two coincident hits on the same part, like a ray grazing a sphere or a
+// cube corner, really do enter and leave at once and are both kept
+pub fn refractive_indices(hit: &Intersection, xs: &[Intersection]) -> (f32, f32) {
+    let mut n1: f32 = 1.0;
+    let mut n2: f32 = 1.0;
+
+    let mut stack: Vec<&dyn Shape> = Vec::new();
+    let mut previous: Option<&Intersection> = None;
+
+    let mut sorted: Vec<&Intersection> = xs.iter().collect();
+    sorted.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+
+    for inter in sorted {
+        let duplicate = previous.is_some_and(|previous| {
+            previous.object.id() == inter.object.id()
+                && previous.child != inter.child
+                && (inter.t - previous.t).abs() < util::THRESHOLD_F32
+        });
+
+        if !duplicate {
+            if stack.is_empty() {
+                n1 = 1.0;
+            } else {
+                n1 = stack.last().unwrap().material().refraction;
+            }
+
+            let mut object_in_stack = false;
+            for i in 0..stack.len() {
+                if stack[i].id() == inter.object.id() {
+                    object_in_stack = true;
+                    stack.remove(i);
+                    break;
+                }
+            }
+
+            if !object_in_stack {
+                stack.push(inter.object);
+            }
+
+            if stack.is_empty() {
+                n2 = 1.0;
+            } else {
+                n2 = stack.last().unwrap().material().refraction;
+            }
+
+            previous = Some(inter);
+        }
+
+        if hit.object.id() == inter.object.id() && hit.t == inter.t {
+            break;
+        }
     }
+
+    return (n1, n2);
 }
 
 pub struct Comp<'a> {
@@ -112,9 +189,18 @@ pub struct Comp<'a> {
     pub inside: bool,
     pub over_point: Vec4,
     pub under_point: Vec4,
+    // Surface coordinates carried over from the intersection, barycentric for triangles
+    pub u: f32,
+    pub v: f32,
+    // Footprint of the pixel on the surface, zero when the ray had no differentials
+    pub dpdx: Vec4,
+    pub dpdy: Vec4,
+    // Interpolated vertex color of the hit, replacing the material color when set
+    pub vertex_color: Option<Color>,
 }
 
 impl<'a> Comp<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(t: f32, object: &'a dyn Shape, point: Vec4, eyev: Vec4, normalv: Vec4, reflectv: Vec4, n1: f32, n2: f32) -> Self {
         let mut inside = false;
         let mut normalv = normalv;
@@ -138,10 +224,68 @@ impl<'a> Comp<'a> {
             inside,
             over_point,
             under_point,
+            u: 0.0,
+            v: 0.0,
+            dpdx: Vec4::ZERO,
+            dpdy: Vec4::ZERO,
+            vertex_color: None,
         };
     }
 
+    // Hit on `object` built straight from known geometry instead of tracing a ray, for exercising
+    // World::shade_hit and Material::lighting in isolation. The ray is taken to travel along -eyev
+    // through air, t is 0 and the point doesn't need to lie on the object's surface
+    pub fn from_surface(object: &'a dyn Shape, point: Vec4, eyev: Vec4, normalv: Vec4) -> Self {
+        let reflectv = (-eyev).reflect(&normalv);
+        return Comp::new(0.0, object, point, eyev, normalv, reflectv, 1.0, 1.0);
+    }
+
+    // Base color of the surface before lighting: the vertex color on painted meshes, otherwise the
+    // material color or pattern averaged over the pixel footprint
+    pub fn surface_color(&self) -> Color {
+        if let Some(color) = self.vertex_color {
+            return color;
+        }
+
+        return self.object.material().color_at_footprint(self.object, &self.over_point, &self.dpdx, &self.dpdy);
+    }
+
+    // Direction of the ray bent through the surface by Snell's law, None under total internal reflection
+    // or when the index on the far side is 0
+    pub fn refracted_direction(&self) -> Option<Vec4> {
+        return self.refracted_direction_between(self.n1, self.n2);
+    }
+
+    // Same as refracted_direction with the indices either side of the surface replaced, e.g. to bend
+    // each wavelength by its own index
+    pub fn refracted_direction_between(&self, n1: f32, n2: f32) -> Option<Vec4> {
+        // refraction is a public field, so a zero index can still get here without going through
+        // Material::new. Nothing is transmitted rather than dividing by it
+        if n2.is_nan() || n2 <= util::THRESHOLD_F32 || !n1.is_finite() {
+            return None;
+        }
+
+        let n_ratio = n1 / n2;
+        let cos_i = self.eyev.dot(&self.normalv);
+        let sin2_t = n_ratio*n_ratio * (1.0 - cos_i*cos_i);
+
+        if sin2_t > 1.0 {
+            return None;
+        }
+
+        let cos_t = f32::sqrt(1.0 - sin2_t);
+        return Some(self.normalv * (n_ratio * cos_i - cos_t) - self.eyev * n_ratio);
+    }
+
+    // normalv has already been flipped to face the eye for inside hits, so eyev . normalv is the cosine
+    // of the incident angle on either side of the surface; when leaving the denser medium (n1 > n2)
+    // Schlick's approximation needs the cosine of the transmitted angle instead
     pub fn schlick(&self) -> f32 {
+        // Matches refracted_direction_between, which transmits nothing into a zero index
+        if self.n2.is_nan() || self.n2 <= util::THRESHOLD_F32 {
+            return 1.0;
+        }
+
         let mut cos = self.eyev.dot(&self.normalv);
 
         if self.n1 > self.n2 {
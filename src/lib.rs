@@ -1,13 +1,19 @@
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod environment;
 pub mod geometry;
+pub mod group;
+pub mod id;
+pub mod image;
 pub mod intersection;
 pub mod material;
 pub mod model;
 pub mod light;
 pub mod pattern;
+pub mod photon;
 pub mod ray;
+pub mod sdf;
 pub mod shape;
 pub mod util;
 pub mod view;
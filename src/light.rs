@@ -1,19 +1,129 @@
 use uuid::Uuid;
 use crate::color::Color;
 use crate::geometry::Vec4;
+use crate::id;
+use crate::util;
+use crate::world::World;
+
+// Ambient light from a surrounding hemisphere: surfaces facing straight up see `sky`, those facing
+// straight down see `ground` and everything in between a blend of the two
+#[derive(Debug, Clone, Copy)]
+pub struct SkyDome {
+    pub sky: Color,
+    pub ground: Color,
+}
+
+impl SkyDome {
+    pub fn new(sky: Color, ground: Color) -> Self {
+        return Self {
+            sky,
+            ground,
+        };
+    }
+
+    pub fn color_at(&self, normal: &Vec4) -> Color {
+        let up = (util::clamp_f32(*normal.normalize().y(), -1.0, 1.0) + 1.0) / 2.0;
+        return self.sky * up + self.ground * (1.0 - up);
+    }
+}
 
 pub struct Light {
     pub id: Uuid,
     pub intensity: Color,
     pub position: Vec4,
+    pub shape: Option<Uuid>,
+    // Size of a spherical light around `position`, 0 for a point light. Shadows are traced towards
+    // samples x samples points on it, so a larger or nearer light gives a wider penumbra
+    pub radius: f32,
+    pub samples: usize,
 }
 
 impl Light {
     pub fn point_light(position: Vec4, intensity: Color) -> Self {
         return Self {
-            id: Uuid::new_v4(),
+            id: id::new_id(),
             position,
             intensity,
+            shape: None,
+            radius: 0.0,
+            samples: 1,
+        };
+    }
+
+    // Point light colored like a black body at `kelvin` (see Color::from_temperature) and scaled by
+    // `intensity`, so 1.0 gives a brightest channel of 1
+    pub fn from_temperature(position: Vec4, kelvin: f32, intensity: f32) -> Self {
+        return Light::point_light(position, Color::from_temperature(kelvin) * intensity);
+    }
+
+    // A glowing ball such as a bulb or the sun, lit from its centre but casting soft shadows
+    pub fn sphere(position: Vec4, radius: f32, intensity: Color, samples: usize) -> Self {
+        let mut light = Light::point_light(position, intensity);
+        light.radius = radius;
+        light.samples = samples;
+        return light;
+    }
+
+    // Light given off by the surface of a shape in the world, which may sit inside a group or a model.
+    // Shading and shadows use samples x samples points spread over the surface, see sample_points, each
+    // with an equal share of the intensity, so only the part of the surface facing a point lights it.
+    // `position` is only used if the world doesn't contain the shape
+    pub fn from_shape(shape_id: Uuid, intensity: Color) -> Self {
+        return Self {
+            id: id::new_id(),
+            position: Vec4::ORIGIN,
+            intensity,
+            shape: Some(shape_id),
+            radius: 0.0,
+            samples: 4,
         };
     }
+
+    // Points on the half of a spherical light that faces `target`, stratified evenly over that
+    // hemisphere's area. A point light, or one with a single sample, only has its position
+    pub fn sample_points_toward(&self, target: &Vec4) -> Vec<Vec4> {
+        let to_target = *target - self.position;
+        if self.radius <= 0.0 || self.samples <= 1 || to_target.magnitude() < util::THRESHOLD_F32 {
+            return vec![self.position];
+        }
+
+        let mut points: Vec<Vec4> = Vec::with_capacity(self.samples * self.samples);
+        for i in 0..self.samples {
+            for j in 0..self.samples {
+                // The hemisphere is the cone with a right angle at its tip
+                let height = (i as f32 + 0.5) / self.samples as f32;
+                let turn = (j as f32 + 0.5) / self.samples as f32;
+
+                let direction = to_target.cone_sample(0.0, height, turn);
+                points.push(self.position + direction * self.radius);
+            }
+        }
+
+        return points;
+    }
+
+    // Stratified samples: the (u, v) square is split into samples x samples cells and each cell
+    // contributes the surface point at its center. Point lights always return their position once.
+    pub fn sample_points(&self, world: &World, samples: usize) -> Vec<Vec4> {
+        let shape = match self.shape {
+            Some(id) => world.find_shape(&id),
+            None => None,
+        };
+
+        let (shape, to_world) = match shape {
+            Some(found) if samples > 0 => found,
+            _ => return vec![self.position],
+        };
+
+        let mut points: Vec<Vec4> = Vec::new();
+        for i in 0..samples {
+            for j in 0..samples {
+                let u = (i as f32 + 0.5) / samples as f32;
+                let v = (j as f32 + 0.5) / samples as f32;
+                points.push(to_world * shape.sample_surface(u, v));
+            }
+        }
+
+        return points;
+    }
 }
@@ -4,6 +4,32 @@ use crate::light::Light;
 use crate::pattern::Pattern;
 use crate::shape::Shape;
 
+// Debug overlay for triangle meshes: hit points whose barycentric distance to an edge is below
+// `width` are drawn flat in `color`, revealing the tessellation. Shapes other than triangles and
+// meshes ignore it, see Shape::has_barycentric_hits
+#[derive(Debug, Clone, Copy)]
+pub struct Wireframe {
+    pub color: Color,
+    pub width: f32,
+}
+
+impl Wireframe {
+    pub fn new(color: Color, width: f32) -> Self {
+        return Self {
+            color,
+            width,
+        };
+    }
+
+    // u and v are the barycentric coordinates of a triangle hit, the smallest of u, v and 1 - u - v
+    // is how close the point is to the nearest edge
+    pub fn is_edge(&self, u: f32, v: f32) -> bool {
+        let edge_distance = u.min(v).min(1.0 - u - v);
+        return edge_distance < self.width;
+    }
+}
+
+#[derive(Clone)]
 pub struct Material {
     pub color: Color,
     pub ambient: f32,
@@ -11,13 +37,34 @@ pub struct Material {
     pub specular: f32,
     pub shininess: f32,
     pub reflective: f32,
+    // Blurs reflections and refractions, from a perfect mirror or clear glass at 0 to spreading over
+    // the whole hemisphere at 1
+    pub roughness: f32,
     pub transparency: f32,
     pub refraction: f32,
+    // Spread of the refractive index across the spectrum: red refracts with refraction - dispersion and
+    // blue with refraction + dispersion, splitting white light into fringes. 0 refracts every color alike
+    pub dispersion: f32,
     pub pattern: Option<Box<dyn Pattern>>,
+    // How much the pattern is tinted by `color`: 0 shows the pattern as is, 1 multiplies it by `color`
+    pub pattern_tint: f32,
+    // Only meaningful on triangles and models, other shapes don't report barycentric coordinates
+    pub wireframe: Option<Wireframe>,
+    // Light given off by the surface itself, added once per hit regardless of lights and shadows
+    pub emission: Color,
+    // Replaces the flat emission color when set, for textured panels such as stained glass
+    pub emission_pattern: Option<Box<dyn Pattern>>,
 }
 
 impl Material {
+    // Refractive indices start at 1 for a vacuum, anything lower (usually a 0 left in by mistake) would
+    // divide by zero or bend light the wrong way when refracting
+    #[allow(clippy::too_many_arguments)]
     pub fn new(color: Color, ambient: f32, diffuse: f32, specular: f32, shininess: f32, reflective: f32, transparency: f32, refraction: f32, pattern: Option<Box<dyn Pattern>>) -> Self {
+        if !(refraction >= 1.0 && refraction.is_finite()) {
+            panic!("Material refraction must be a finite index of at least 1, got {}", refraction);
+        }
+
         return Self {
             color,
             ambient,
@@ -25,25 +72,97 @@ impl Material {
             specular,
             shininess,
             reflective,
+            roughness: 0.0,
             transparency,
             refraction,
+            dispersion: 0.0,
             pattern,
+            pattern_tint: 0.0,
+            wireframe: None,
+            emission: Color::black(),
+            emission_pattern: None,
         };
     }
 
-    pub fn lighting(&self, object: &dyn Shape, light: &Light, point: &Vec4, eyev: &Vec4, normalv: &Vec4, in_shadow: bool) -> Color  {
-        let mut color = self.color;
+    // Diffuse-only surface with the default ambient and diffuse and no specular highlight
+    pub fn matte(color: Color) -> Self {
+        return Material::new(color, 0.1, 0.9, 0.0, 200.0, 0.0, 0.0, 1.0, None);
+    }
+
+    // Mostly diffuse with a tight, moderate highlight
+    pub fn plastic(color: Color) -> Self {
+        return Material::new(color, 0.1, 0.8, 0.5, 150.0, 0.0, 0.0, 1.0, None);
+    }
+
+    // Dim diffuse base with a sharp highlight and strong reflections
+    pub fn metal(color: Color) -> Self {
+        return Material::new(color, 0.1, 0.3, 0.9, 300.0, 0.7, 0.0, 1.0, None);
+    }
+
+    // Clear glass with an index of 1.5, almost all of its color comes from what it refracts and reflects
+    pub fn glass() -> Self {
+        return Material::new(Color::white(), 0.0, 0.1, 1.0, 300.0, 0.9, 0.9, 1.5, None);
+    }
+
+    // A perfect mirror, black apart from its reflections and highlights
+    pub fn mirror() -> Self {
+        return Material::new(Color::black(), 0.0, 0.0, 1.0, 300.0, 1.0, 0.0, 1.0, None);
+    }
 
+    // Diffuse with a faint, broad sheen
+    pub fn rubber(color: Color) -> Self {
+        return Material::new(color, 0.1, 0.9, 0.1, 10.0, 0.0, 0.0, 1.0, None);
+    }
+
+    pub fn color_at_object(&self, object: &dyn Shape, point: &Vec4) -> Color {
+        return self.color_at_footprint(object, point, &Vec4::ZERO, &Vec4::ZERO);
+    }
+
+    // Surface color averaged over a pixel footprint, see Pattern::color_at_footprint
+    pub fn color_at_footprint(&self, object: &dyn Shape, point: &Vec4, dpdx: &Vec4, dpdy: &Vec4) -> Color {
         if let Some(pattern) = &self.pattern {
-            color = pattern.color_at_object(object, point);
+            let tint = Color::white() * (1.0 - self.pattern_tint) + self.color * self.pattern_tint;
+            return pattern.color_at_footprint(object, point, dpdx, dpdy) * tint;
         }
 
+        return self.color;
+    }
+
+    pub fn emission_at(&self, object: &dyn Shape, point: &Vec4) -> Color {
+        if let Some(pattern) = &self.emission_pattern {
+            return pattern.color_at_object(object, point);
+        }
+
+        return self.emission;
+    }
+
+    pub fn lighting(&self, object: &dyn Shape, light: &Light, point: &Vec4, eyev: &Vec4, normalv: &Vec4, in_shadow: bool) -> Color  {
+        let color = self.color_at_object(object, point);
+        return self.lighting_with_color(color, light, point, eyev, normalv, in_shadow);
+    }
+
+    // Same as lighting with the surface color already looked up
+    pub fn lighting_with_color(&self, color: Color, light: &Light, point: &Vec4, eyev: &Vec4, normalv: &Vec4, in_shadow: bool) -> Color  {
+        let (ambient, diffuse, specular) = self.lighting_components(color, light, point, eyev, normalv, in_shadow);
+        return ambient + diffuse + specular;
+    }
+
+    // The ambient, diffuse and specular terms of lighting_with_color before they are summed, for
+    // debugging passes that look at one of them on its own
+    pub fn lighting_components(&self, color: Color, light: &Light, point: &Vec4, eyev: &Vec4, normalv: &Vec4, in_shadow: bool) -> (Color, Color, Color) {
+        return self.lighting_components_from(color, light, &light.position, point, eyev, normalv, in_shadow);
+    }
+
+    // lighting_components with the light arriving from `light_position` instead of the light's own
+    // position, e.g. from a sample on the surface of a shape light
+    #[allow(clippy::too_many_arguments)]
+    pub fn lighting_components_from(&self, color: Color, light: &Light, light_position: &Vec4, point: &Vec4, eyev: &Vec4, normalv: &Vec4, in_shadow: bool) -> (Color, Color, Color) {
         let effective_color = color * light.intensity;
-        let lightv = (light.position - *point).normalize();
+        let lightv = (*light_position - *point).normalize();
         let ambient = effective_color * self.ambient;
 
         if in_shadow {
-            return ambient;
+            return (ambient, Color::black(), Color::black());
         }
 
         let diffuse: Color;
@@ -51,37 +170,48 @@ impl Material {
 
         let light_dot_normal = lightv.dot(normalv);
         if light_dot_normal < 0.0 {
-            diffuse = Color::new(0.0, 0.0, 0.0);
-            specular = Color::new(0.0, 0.0, 0.0);
+            diffuse = Color::black();
+            specular = Color::black();
         } else {
             diffuse = effective_color * self.diffuse * light_dot_normal;
+
+            if self.specular == 0.0 {
+                return (ambient, diffuse, Color::black());
+            }
+
             let reflectv = (-lightv).reflect(normalv);
             let reflect_dot_eye = reflectv.dot(eyev);
 
             if reflect_dot_eye <= 0.0 {
-                specular = Color::new(0.0, 0.0, 0.0);
+                specular = Color::black();
             } else {
                 let factor = reflect_dot_eye.powf(self.shininess);
                 specular = light.intensity * self.specular * factor;
             }
         }
 
-        return ambient + diffuse + specular;
+        return (ambient, diffuse, specular);
     }
 }
 
 impl Default for Material {
     fn default() -> Self {
         return Self {
-            color: Color::new(1.0, 1.0, 1.0),
+            color: Color::white(),
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
             reflective: 0.0,
+            roughness: 0.0,
             transparency: 0.0,
             refraction: 1.0,
+            dispersion: 0.0,
             pattern: None,
+            pattern_tint: 0.0,
+            wireframe: None,
+            emission: Color::black(),
+            emission_pattern: None,
         }
     }
 }
\ No newline at end of file
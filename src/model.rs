@@ -1,24 +1,57 @@
-use std::fs::File;
-use std::io::{BufReader, BufRead};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::color::Color;
 use crate::geometry::{Matrix4x4, Vec4};
+use crate::id;
 use crate::intersection::Intersection;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::shape::Shape;
 use crate::util;
 
+// Barycentric (u, v) of the point projected onto the triangle p1, p1 + e1, p1 + e2, using the same
+// convention as the intersection tests: u weighs the second vertex and v the third. None when the
+// projection falls outside or the triangle is degenerate
+fn barycentric(p1: &Vec4, e1: &Vec4, e2: &Vec4, point: &Vec4) -> Option<(f32, f32)> {
+    let w = *point - *p1;
+    let (d11, d12, d22) = (e1.dot(e1), e1.dot(e2), e2.dot(e2));
+    let (dw1, dw2) = (w.dot(e1), w.dot(e2));
+
+    // Relative to the edge lengths, the denominator grows with their fourth power so small but
+    // perfectly good triangles would fall under any fixed threshold
+    let denominator = d11 * d22 - d12 * d12;
+    if denominator.abs() <= f32::EPSILON * d11 * d22 {
+        return None;
+    }
+
+    let u = (d22 * dw1 - d12 * dw2) / denominator;
+    let v = (d11 * dw2 - d12 * dw1) / denominator;
+
+    if u < 0.0 || v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    return Some((u, v));
+}
+
+#[derive(Clone)]
 pub struct Triangle {
     id: Uuid,
     transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     material: Material,
+    enabled: bool,
     p1: Vec4,
     p2: Vec4,
     p3: Vec4,
     e1: Vec4,
     e2: Vec4,
     normal: Vec4,
+    // Colors painted on p1, p2 and p3, blended across the face in place of the material color
+    colors: Option<[Color; 3]>,
 }
 
 impl Triangle {
@@ -28,17 +61,29 @@ impl Triangle {
         let normal = (e2.cross(&e1)).normalize();
 
         return Self {
-            id: Uuid::new_v4(),
+            id: id::new_id(),
             transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
             material,
+            enabled: true,
             p1,
             p2,
             p3,
             e1,
             e2,
             normal,
+            colors: None,
         };
     }
+
+    // Barycentric (u, v) of a local point if its projection onto the triangle's plane lies inside
+    pub fn contains(&self, point: &Vec4) -> Option<(f32, f32)> {
+        return barycentric(&self.p1, &self.e1, &self.e2, point);
+    }
+
+    pub fn set_vertex_colors(&mut self, c1: Color, c2: Color, c3: Color) {
+        self.colors = Some([c1, c2, c3]);
+    }
 }
 
 impl Shape for Triangle {
@@ -46,12 +91,21 @@ impl Shape for Triangle {
         return &self.id;
     }
 
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(Triangle { id: id::new_id(), ..self.clone() }));
+    }
+
     fn transform(&self) -> &Matrix4x4 {
         return &self.transform;
     }
 
     fn set_transform(&mut self, matrix: Matrix4x4) {
         self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
     }
 
     fn material(&self) -> &Material {
@@ -62,7 +116,15 @@ impl Shape for Triangle {
         return &mut self.material;
     }
 
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection> {
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
         let dir_cross_e2 = ray.direction.cross(&self.e2);
         let det = self.e1.dot(&dir_cross_e2);
 
@@ -74,7 +136,7 @@ impl Shape for Triangle {
         let p1_to_origin = ray.origin - self.p1;
         let u = p1_to_origin.dot(&dir_cross_e2) * f;
 
-        if u < 0.0 || u > 1.0 {
+        if !(0.0..=1.0).contains(&u) {
             return Vec::new();
         }
 
@@ -94,42 +156,84 @@ impl Shape for Triangle {
         return self.normal;
     }
 
+    // Along the first edge, the direction barycentric u increases in
+    fn local_tangent_at(&self, _: &Vec4, _: Intersection) -> Vec4 {
+        return self.e1;
+    }
+
+    fn has_barycentric_hits(&self) -> bool {
+        return true;
+    }
+
+    fn vertex_color(&self, hit: Intersection) -> Option<Color> {
+        return self.colors.map(|[c1, c2, c3]| c2 * hit.u + c3 * hit.v + c1 * (1.0 - hit.u - hit.v));
+    }
+
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
-        let local_point = self.transform().invert() * *world_point;
+        let local_point = self.world_to_object(world_point);
         let local_normal = self.local_normal_at(&local_point, i);
-        let world_normal = self.transform().invert().transpose() * local_normal;
-        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
-    
-        return world_normal.normalize();
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // Uniform over the triangle's area using square-root warped barycentric coordinates
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let su = u.sqrt();
+        let local_point = self.p1 * (1.0 - su) + self.p2 * (su * (1.0 - v)) + self.p3 * (su * v);
+        return self.transform * local_point;
     }
 }
 
+#[derive(Clone)]
 pub struct SmoothTriangle {
     id: Uuid,
     transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     material: Material,
+    enabled: bool,
     p1: Vec4,
     p2: Vec4,
     p3: Vec4,
     n1: Vec4,
     n2: Vec4,
     n3: Vec4,
+    e1: Vec4,
+    e2: Vec4,
+    // Colors painted on p1, p2 and p3, blended like the normals in place of the material color
+    colors: Option<[Color; 3]>,
 }
 
 impl SmoothTriangle {
     pub fn new(material: Material, p1: Vec4, p2: Vec4, p3: Vec4, n1: Vec4, n2: Vec4, n3: Vec4) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+
         return Self {
-            id: Uuid::new_v4(),
+            id: id::new_id(),
             transform: Matrix4x4::identity(),
-            material: material,
+            inverse_transform: Matrix4x4::identity(),
+            material,
+            enabled: true,
             p1,
             p2,
             p3,
             n1,
             n2,
             n3,
+            e1,
+            e2,
+            colors: None,
         };
     }
+
+    // Barycentric (u, v) of a local point if its projection onto the triangle's plane lies inside,
+    // the same coordinates a hit there would carry for normal interpolation
+    pub fn contains(&self, point: &Vec4) -> Option<(f32, f32)> {
+        return barycentric(&self.p1, &self.e1, &self.e2, point);
+    }
+
+    pub fn set_vertex_colors(&mut self, c1: Color, c2: Color, c3: Color) {
+        self.colors = Some([c1, c2, c3]);
+    }
 }
 
 impl Shape for SmoothTriangle {
@@ -137,12 +241,21 @@ impl Shape for SmoothTriangle {
         return &self.id;
     }
 
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(SmoothTriangle { id: id::new_id(), ..self.clone() }));
+    }
+
     fn transform(&self) -> &Matrix4x4 {
         return &self.transform;
     }
 
     fn set_transform(&mut self, matrix: Matrix4x4) {
         self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
     }
 
     fn material(&self) -> &Material {
@@ -153,12 +266,17 @@ impl Shape for SmoothTriangle {
         return &mut self.material;
     }
 
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection> {
-        let e1 = self.p2 - self.p1;
-        let e2 = self.p3 - self.p1;
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
 
-        let dir_cross_e2 = ray.direction.cross(&e2);
-        let det = e1.dot(&dir_cross_e2);
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let dir_cross_e2 = ray.direction.cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
 
         if det.abs() < util::THRESHOLD_F32 {
             return Vec::new();
@@ -168,18 +286,18 @@ impl Shape for SmoothTriangle {
         let p1_to_origin = ray.origin - self.p1;
         let u = p1_to_origin.dot(&dir_cross_e2) * f;
 
-        if u < 0.0 || u > 1.0 {
+        if !(0.0..=1.0).contains(&u) {
             return Vec::new();
         }
 
-        let origin_cross_e1 = p1_to_origin.cross(&e1);
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
         let v = ray.direction.dot(&origin_cross_e1) * f;
 
         if v < 0.0 || (u + v) > 1.0 {
             return Vec::new();
         }
 
-        let t = e2.dot(&origin_cross_e1) * f;
+        let t = self.e2.dot(&origin_cross_e1) * f;
 
         return vec![Intersection::from_uv(self, t, u, v)];
     }
@@ -188,72 +306,199 @@ impl Shape for SmoothTriangle {
         return self.n2 * hit.u + self.n3 * hit.v + self.n1 * (1.0 - hit.u - hit.v);
     }
 
+    // Along the first edge, the direction barycentric u increases in
+    fn local_tangent_at(&self, _: &Vec4, _: Intersection) -> Vec4 {
+        return self.e1;
+    }
+
+    fn has_barycentric_hits(&self) -> bool {
+        return true;
+    }
+
+    fn vertex_color(&self, hit: Intersection) -> Option<Color> {
+        return self.colors.map(|[c1, c2, c3]| c2 * hit.u + c3 * hit.v + c1 * (1.0 - hit.u - hit.v));
+    }
+
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
-        let local_point = self.transform().invert() * *world_point;
+        let local_point = self.world_to_object(world_point);
         let local_normal = self.local_normal_at(&local_point, i);
-        let world_normal = self.transform().invert().transpose() * local_normal;
-        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
-    
-        return world_normal.normalize();
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // Uniform over the triangle's area using square-root warped barycentric coordinates
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let su = u.sqrt();
+        let local_point = self.p1 * (1.0 - su) + self.p2 * (su * (1.0 - v)) + self.p3 * (su * v);
+        return self.transform * local_point;
     }
 }
 
+// Cloning a model, or stamping out copies with clone_shape, shares the parsed triangles between the
+// copies. They are only duplicated, with new ids, if one of the copies changes them, e.g. through
+// children_mut
+#[derive(Clone)]
 pub struct Model {
     pub id: Uuid,
-    pub transform: Matrix4x4,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     pub material: Material,
-    pub triangles: Vec<Box<dyn Shape>>,
+    enabled: bool,
+    pub triangles: Arc<Vec<Box<dyn Shape>>>,
 }
 
 impl Model {    
     pub fn new(material: Material, file_path: &str) -> Self {
-        let triangles = Self::process_obj_file(&material, file_path);
+        return Model::load(material, file_path).unwrap_or_else(|e| {
+            panic!("{}", e);
+        });
+    }
 
-        return Self {
-            id: Uuid::new_v4(),
+    pub fn with_id(material: Material, file_path: &str, id: Uuid) -> Self {
+        return Model::load_with_id(material, file_path, Some(id)).unwrap_or_else(|e| {
+            panic!("{}", e);
+        });
+    }
+
+    pub fn load(material: Material, file_path: &str) -> Result<Self, String> {
+        return Model::load_with_id(material, file_path, None);
+    }
+
+    // Picks the parser from the extension: .stl files are read as ASCII or binary STL, anything else as OBJ.
+    // The model gets a new id once its triangles are loaded unless one is given
+    fn load_with_id(material: Material, file_path: &str, id: Option<Uuid>) -> Result<Self, String> {
+        let is_stl = Path::new(file_path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("stl"));
+
+        let triangles = if is_stl {
+            Self::process_stl_file(&material, file_path)?
+        } else {
+            Self::process_obj_file(&material, file_path)?
+        };
+
+        return Ok(Self {
+            id: id.unwrap_or_else(id::new_id),
             transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
             material,
-            triangles,
-        };
+            enabled: true,
+            triangles: Arc::new(triangles),
+        });
+    }
+
+    fn parse_f32(tokens: &[String], index: usize, line_number: usize) -> Result<f32, String> {
+        let token = tokens.get(index).ok_or(format!("line {}: expected at least {} values", line_number, index))?;
+        return token.parse::<f32>().map_err(|e| format!("line {}: invalid number '{}': {}", line_number, token, e));
+    }
+
+    fn parse_index(token: &str, line_number: usize) -> Result<usize, String> {
+        let index = token.parse::<usize>().map_err(|e| format!("line {}: invalid index '{}': {}", line_number, token, e))?;
+        if index == 0 {
+            return Err(format!("line {}: indices start at 1", line_number));
+        }
+        return Ok(index - 1);
+    }
+
+    // Checks that the statement on this line has between min and max values after its keyword
+    fn expect_values(tokens: &[String], min: usize, max: usize, line_number: usize) -> Result<(), String> {
+        let count = tokens.len() - 1;
+        if count < min || count > max {
+            let expected = if min == max { format!("{}", min) } else { format!("{} to {}", min, max) };
+            return Err(format!("line {}: '{}' takes {} values, found {}", line_number, tokens[0], expected, count));
+        }
+        return Ok(());
     }
 
-    fn process_obj_file(material: &Material, file_path: &str) -> Vec<Box<dyn Shape>> {
+    fn lookup(items: &[Vec4], index: usize, kind: &str) -> Result<Vec4, String> {
+        return items.get(index).copied().ok_or(format!("{} index {} is out of range", kind, index + 1));
+    }
+
+    // A face is only vertex colored when all three of its corners have a color
+    fn face_colors(colors: &[Option<Color>], face: &[usize]) -> Option<(Color, Color, Color)> {
+        return Some((colors[face[0]]?, colors[face[1]]?, colors[face[2]]?));
+    }
+
+    fn process_obj_file(material: &Material, file_path: &str) -> Result<Vec<Box<dyn Shape>>, String> {
         let mut verts: Vec<Vec4> = Vec::new();
+        let mut vert_colors: Vec<Option<Color>> = Vec::new();
         let mut vert_normals: Vec<Vec4> = Vec::new();
         let mut face_verts: Vec<Vec<usize>> = Vec::new();
         let mut face_normals: Vec<Vec<usize>> = Vec::new();
-
-        let file = File::open(file_path).unwrap();
-        let reader = BufReader::new(file);
-
-        for line in reader.lines() {
+        // Faces inside a smoothing group are shaded with their vertex normals, 's off' or 's 0' ends the
+        // group and the faces after it come out faceted
+        let mut face_smooth: Vec<bool> = Vec::new();
+        let mut smooth = true;
+
+        // Files saved on Windows (\r\n) or classic Mac (\r) are normalized to \n before splitting into lines
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| format!("{}: {}", file_path, e))?
+            .replace("\r\n", "\n")
+            .replace('\r', "\n");
+
+        for (line_index, line) in contents.lines().enumerate() {
+            let line_number = line_index + 1;
             let tokens: Vec<_> = line
-                .unwrap()
                 .split_whitespace()
-                .map(|s| s.to_owned())
+                .map(|s| s.trim().to_owned())
                 .collect();
 
             if let Some(first) = tokens.first() {
                 if first == "v" {
+                    // Either x y z with an optional w, a weight for rational curves that has no effect on
+                    // polygons, or x y z followed by an r g b vertex color
+                    let count = tokens.len() - 1;
+                    if ![3, 4, 6].contains(&count) {
+                        return Err(format!("line {}: 'v' takes 3 or 4 values, or 6 with a color, found {}", line_number, count));
+                    }
+                    if tokens.len() == 5 {
+                        Self::parse_f32(&tokens, 4, line_number)?;
+                    }
+
                     let vertex = Vec4::point(
-                        tokens[1].parse::<f32>().unwrap(), 
-                        tokens[2].parse::<f32>().unwrap(), 
-                        tokens[3].parse::<f32>().unwrap(),
+                        Self::parse_f32(&tokens, 1, line_number)?,
+                        Self::parse_f32(&tokens, 2, line_number)?,
+                        Self::parse_f32(&tokens, 3, line_number)?,
                     );
                     verts.push(vertex);
+
+                    let color = if tokens.len() == 7 {
+                        Some(Color::new(
+                            Self::parse_f32(&tokens, 4, line_number)?,
+                            Self::parse_f32(&tokens, 5, line_number)?,
+                            Self::parse_f32(&tokens, 6, line_number)?,
+                        ))
+                    } else {
+                        None
+                    };
+                    vert_colors.push(color);
                 } else if first == "vn" {
+                    Self::expect_values(&tokens, 3, 3, line_number)?;
                     let vnormal = Vec4::vector(
-                        tokens[1].parse::<f32>().unwrap(), 
-                        tokens[2].parse::<f32>().unwrap(), 
-                        tokens[3].parse::<f32>().unwrap(),
+                        Self::parse_f32(&tokens, 1, line_number)?,
+                        Self::parse_f32(&tokens, 2, line_number)?,
+                        Self::parse_f32(&tokens, 3, line_number)?,
                     );
                     vert_normals.push(vnormal);
+                } else if first == "vp" {
+                    // Parameter space vertices only matter for free-form curves and surfaces, which
+                    // aren't supported, but they are still checked so a corrupt line doesn't pass silently
+                    Self::expect_values(&tokens, 1, 3, line_number)?;
+                    for index in 1..tokens.len() {
+                        Self::parse_f32(&tokens, index, line_number)?;
+                    }
+                } else if first == "s" {
+                    Self::expect_values(&tokens, 1, 1, line_number)?;
+                    smooth = !(tokens[1].eq_ignore_ascii_case("off") || tokens[1] == "0");
                 } else if first == "f" {
-                    let body: Vec<_> = tokens[1..]
+                    let body = tokens[1..]
                         .join("/")
                         .split("/")
-                        .map(|s| s.to_owned().parse::<usize>().unwrap() - 1)
-                        .collect();
+                        .map(|s| Self::parse_index(s.trim(), line_number))
+                        .collect::<Result<Vec<usize>, String>>()?;
+
+                    if body.len() < 9 {
+                        return Err(format!("line {}: faces must be triangles written as v/vt/vn", line_number));
+                    }
 
                     let face: Vec<usize> = Vec::from([
                         body[0],
@@ -268,71 +513,147 @@ impl Model {
                         body[8],
                     ]);
                     face_normals.push(fnormal);
+                    face_smooth.push(smooth);
                 }
             }
         }
 
         let mut triangles: Vec<Box<dyn Shape>> = Vec::new();
 
-        if face_normals.len() > 0 {
-            for i in 0..face_verts.len() {
-                let face = &face_verts[i];
-                let normal = &face_normals[i];
+        for i in 0..face_verts.len() {
+            let face = &face_verts[i];
+            let colors = Self::face_colors(&vert_colors, face);
 
-                // Not implementing Patterns right now for models
-                let material = Material::new(
-                    material.color, 
-                    material.ambient, 
-                    material.diffuse, 
-                    material.specular, 
-                    material.shininess, 
-                    material.reflective, 
-                    material.transparency, 
-                    material.refraction, 
-                    None,
+            if face_smooth[i] && !face_normals.is_empty() {
+                let normal = &face_normals[i];
+                let mut triangle = SmoothTriangle::new(
+                    material.clone(),
+                    Self::lookup(&verts, face[0], "vertex")?,
+                    Self::lookup(&verts, face[1], "vertex")?,
+                    Self::lookup(&verts, face[2], "vertex")?,
+                    Self::lookup(&vert_normals, normal[0], "normal")?,
+                    Self::lookup(&vert_normals, normal[1], "normal")?,
+                    Self::lookup(&vert_normals, normal[2], "normal")?,
                 );
+                if let Some((c1, c2, c3)) = colors {
+                    triangle.set_vertex_colors(c1, c2, c3);
+                }
 
-                let triangle = SmoothTriangle::new(
-                    material,
-                    verts[face[0]], 
-                    verts[face[1]], 
-                    verts[face[2]],
-                    vert_normals[normal[0]], 
-                    vert_normals[normal[1]], 
-                    vert_normals[normal[2]],
+                triangles.push(Box::new(triangle));
+            } else {
+                let mut triangle = Triangle::new(
+                    material.clone(),
+                    Self::lookup(&verts, face[0], "vertex")?,
+                    Self::lookup(&verts, face[1], "vertex")?,
+                    Self::lookup(&verts, face[2], "vertex")?,
                 );
+                if let Some((c1, c2, c3)) = colors {
+                    triangle.set_vertex_colors(c1, c2, c3);
+                }
 
                 triangles.push(Box::new(triangle));
-            }            
+            }
+        }
+
+        return Ok(triangles);
+    }
+
+    // STL stores every facet with its own three corners and a face normal. There are no shared vertices
+    // to smooth over, so each facet becomes a flat Triangle with its normal worked out from the corners.
+    // Zero-area facets, with repeated or collinear corners, have no normal to work out and are skipped
+    fn process_stl_file(material: &Material, file_path: &str) -> Result<Vec<Box<dyn Shape>>, String> {
+        let bytes = fs::read(file_path).map_err(|e| format!("{}: {}", file_path, e))?;
+
+        // ASCII files start with "solid", but so do the headers of some binary exporters, so the size
+        // implied by the binary triangle count decides
+        let corners = if Self::is_binary_stl(&bytes) {
+            Self::parse_binary_stl(&bytes)
         } else {
-            for i in 0..face_verts.len() {
-                let face = &face_verts[i];
-
-                // Not implementing Patterns right now for models
-                let material = Material::new(
-                    material.color, 
-                    material.ambient, 
-                    material.diffuse, 
-                    material.specular, 
-                    material.shininess, 
-                    material.reflective, 
-                    material.transparency, 
-                    material.refraction, 
-                    None,
-                );
+            Self::parse_ascii_stl(&bytes)
+        };
+        let corners = corners.map_err(|e| format!("{}: {}", file_path, e))?;
 
-                let triangle = Triangle::new(
-                    material,
-                    verts[face[0]], 
-                    verts[face[1]], 
-                    verts[face[2]],
-                );
+        let mut triangles: Vec<Box<dyn Shape>> = Vec::new();
+        for facet in corners.chunks(3) {
+            let normal = (facet[2] - facet[0]).cross(&(facet[1] - facet[0])).normalize();
+            if !(normal.x().is_finite() && normal.y().is_finite() && normal.z().is_finite()) {
+                continue;
+            }
 
-                triangles.push(Box::new(triangle));
+            triangles.push(Box::new(Triangle::new(material.clone(), facet[0], facet[1], facet[2])));
+        }
+
+        return Ok(triangles);
+    }
+
+    fn is_binary_stl(bytes: &[u8]) -> bool {
+        if bytes.len() < 84 {
+            return false;
+        }
+
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        return bytes.len() == 84 + count * 50;
+    }
+
+    // 80 byte header, little endian u32 triangle count, then 50 bytes per triangle: normal and three
+    // corners as 12 f32s followed by a u16 attribute
+    fn parse_binary_stl(bytes: &[u8]) -> Result<Vec<Vec4>, String> {
+        let read_f32 = |offset: usize| f32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+        let mut corners: Vec<Vec4> = Vec::new();
+
+        for (index, record) in (84..bytes.len()).step_by(50).enumerate() {
+            for corner in 1..4 {
+                let offset = record + corner * 12;
+                let point = Vec4::point(read_f32(offset), read_f32(offset + 4), read_f32(offset + 8));
+                if !(point.x().is_finite() && point.y().is_finite() && point.z().is_finite()) {
+                    return Err(format!("triangle {}: corner is not a finite number", index + 1));
+                }
+                corners.push(point);
+            }
+        }
+
+        return Ok(corners);
+    }
+
+    fn parse_ascii_stl(bytes: &[u8]) -> Result<Vec<Vec4>, String> {
+        let contents = std::str::from_utf8(bytes).map_err(|_| String::from("neither ASCII nor a binary STL of the right size"))?;
+        if !contents.trim_start().starts_with("solid") {
+            return Err(String::from("ASCII STL must start with 'solid'"));
+        }
+
+        let mut corners: Vec<Vec4> = Vec::new();
+        let mut facet_corners = 0;
+
+        for (line_index, line) in contents.lines().enumerate() {
+            let line_number = line_index + 1;
+            let tokens: Vec<_> = line
+                .split_whitespace()
+                .map(|s| s.trim().to_owned())
+                .collect();
+
+            match tokens.first().map(|s| s.as_str()) {
+                Some("facet") => facet_corners = 0,
+                Some("vertex") => {
+                    Self::expect_values(&tokens, 3, 3, line_number)?;
+                    corners.push(Vec4::point(
+                        Self::parse_f32(&tokens, 1, line_number)?,
+                        Self::parse_f32(&tokens, 2, line_number)?,
+                        Self::parse_f32(&tokens, 3, line_number)?,
+                    ));
+                    facet_corners += 1;
+                }
+                Some("endfacet") if facet_corners != 3 => {
+                    return Err(format!("line {}: facets must have 3 vertices, found {}", line_number, facet_corners));
+                }
+                _ => {}
             }
         }
 
-        return triangles;
+        if !corners.len().is_multiple_of(3) {
+            return Err(String::from("last facet is missing vertices"));
+        }
+
+        return Ok(corners);
     }
 }
 
@@ -341,12 +662,21 @@ impl Shape for Model {
         return &self.id;
     }
 
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(Model { id: id::new_id(), ..self.clone() }));
+    }
+
     fn transform(&self) -> &Matrix4x4 {
         return &self.transform;
     }
 
     fn set_transform(&mut self, matrix: Matrix4x4) {
         self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
     }
 
     fn material(&self) -> &Material {
@@ -357,35 +687,81 @@ impl Shape for Model {
         return &mut self.material;
     }
 
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection> {
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    // Hits are reported against the model itself so its transform, material and pattern apply to
+    // the whole mesh, with the triangle that was hit remembered in `child`
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
         let mut xs: Vec<Intersection> = Vec::new();
 
-        for tri in &self.triangles {
-            xs.append(&mut tri.local_intersect(ray));
+        for (i, tri) in self.triangles.iter().enumerate() {
+            for inter in tri.local_intersect(ray) {
+                xs.push(Intersection::from_child(self, inter.t, inter.u, inter.v, i));
+            }
         }
 
         return xs;
     }
 
-    fn local_normal_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
-        let ray = Ray::new(*local_point, Vec4::vector(0.0, 0.0, 0.0));
+    fn local_normal_at(&self, local_point: &Vec4, hit: Intersection) -> Vec4 {
+        if let Some(tri) = self.triangles.get(hit.child) {
+            return tri.local_normal_at(local_point, hit);
+        }
 
-        for tri in &self.triangles {
-            let inter = tri.local_intersect(&ray);
-            if inter.len() > 0 {
-                return tri.local_normal_at(&local_point, inter[0]);
-            }
+        return Vec4::ZERO;
+    }
+
+    fn local_tangent_at(&self, local_point: &Vec4, hit: Intersection) -> Vec4 {
+        if let Some(tri) = self.triangles.get(hit.child) {
+            return tri.local_tangent_at(local_point, hit);
         }
 
-        return Vec4::vector(0.0, 0.0, 0.0);
+        return Vec4::ZERO;
+    }
+
+    fn has_barycentric_hits(&self) -> bool {
+        return true;
+    }
+
+    fn vertex_color(&self, hit: Intersection) -> Option<Color> {
+        return self.triangles.get(hit.child)?.vertex_color(hit);
     }
 
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
-        let local_point = self.transform().invert() * *world_point;
+        let local_point = self.world_to_object(world_point);
         let local_normal = self.local_normal_at(&local_point, i);
-        let world_normal = self.transform().invert().transpose() * local_normal;
-        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
-    
-        return world_normal.normalize();
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // u picks a triangle and is then reused within it, so every triangle gets the same share of samples regardless of size
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        if self.triangles.is_empty() {
+            return self.transform * Vec4::ORIGIN;
+        }
+
+        let scaled = util::clamp_f32(u, 0.0, 1.0) * self.triangles.len() as f32;
+        let index = usize::min(scaled.floor() as usize, self.triangles.len() - 1);
+        let local_point = self.triangles[index].sample_surface(scaled - index as f32, v);
+        return self.transform * local_point;
+    }
+
+    fn children(&self) -> Option<&[Box<dyn Shape>]> {
+        return Some(&self.triangles);
+    }
+
+    fn children_mut(&mut self) -> Option<&mut [Box<dyn Shape>]> {
+        // Copies the triangles first if another copy of the model still shares them
+        if Arc::get_mut(&mut self.triangles).is_none() {
+            let copies = self.triangles.iter().map(|tri| tri.clone_shape().expect("triangles can always be copied")).collect();
+            self.triangles = Arc::new(copies);
+        }
+
+        return Arc::get_mut(&mut self.triangles).map(|triangles| triangles.as_mut_slice());
     }
 }
\ No newline at end of file
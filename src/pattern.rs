@@ -1,16 +1,84 @@
 use crate::color::Color;
 use crate::geometry::{Matrix4x4, Vec4};
 use crate::shape::Shape;
+use crate::util;
 
-pub trait Pattern {
+// Send + Sync because materials, and the patterns they own, are shared between render threads. Any
+// pattern that derives Clone gets clone_box for free, see PatternClone
+pub trait Pattern: PatternClone + Send + Sync {
     fn color_at(&self, point: &Vec4) -> Color;
     fn color_at_object(&self, shape: &dyn Shape, world_point: &Vec4) -> Color;
+
+    // Color at a point in the space of an enclosing pattern, such as a layer of a BlendedPattern, after
+    // this pattern's own transform, so the two transforms compose. Patterns without one see the point as is
+    fn color_at_nested(&self, point: &Vec4) -> Color {
+        return self.color_at(point);
+    }
+
+    // Average color over the parallelogram spanned by dpdx and dpdy around the point, patterns that
+    // don't filter fall back to point sampling
+    fn color_at_footprint(&self, shape: &dyn Shape, world_point: &Vec4, _dpdx: &Vec4, _dpdy: &Vec4) -> Color {
+        return self.color_at_object(shape, world_point);
+    }
 }
 
+// Boxed copy of a pattern, which is what lets materials holding one be cloned. Implemented for every
+// Clone pattern, so patterns don't write it themselves
+pub trait PatternClone {
+    fn clone_box(&self) -> Box<dyn Pattern>;
+}
+
+impl<T: Pattern + Clone + 'static> PatternClone for T {
+    fn clone_box(&self) -> Box<dyn Pattern> {
+        return Box::new(self.clone());
+    }
+}
+
+impl Clone for Box<dyn Pattern> {
+    fn clone(&self) -> Self {
+        return self.clone_box();
+    }
+}
+
+// Coordinates this close below an integer count as lying on it. Points on axis-aligned surfaces through
+// the origin come out as tiny positive or negative values depending on float error, and a plain floor
+// would flip between neighbouring squares along the seam. Kept well under THRESHOLD_F32 so the
+// over_point of a downward facing surface still lands below its boundary
+const BOUNDARY_EPSILON: f32 = util::THRESHOLD_F32 / 10.0;
+
+fn stable_floor(x: f32) -> f32 {
+    return (x + BOUNDARY_EPSILON).floor();
+}
+
+// Integral of the square wave (-1)^floor(x), a triangle wave between 0 and 1 with period 2
+fn square_wave_integral(x: f32) -> f32 {
+    return 1.0 - ((x.rem_euclid(2.0)) - 1.0).abs();
+}
+
+// Average of (-1)^floor(x) over a box of the given width centred on x
+fn filtered_square_wave(x: f32, width: f32) -> f32 {
+    if width < util::THRESHOLD_F32 {
+        return if stable_floor(x).rem_euclid(2.0) == 0.0 { 1.0 } else { -1.0 };
+    }
+
+    return (square_wave_integral(x + width / 2.0) - square_wave_integral(x - width / 2.0)) / width;
+}
+
+// Pattern space coordinate that a stripe pattern alternates along
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StripeAxis {
+    #[default]
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Clone)]
 pub struct StripePattern {
     pub primary_color: Color,
     pub secondary_color: Color,
     pub transform: Matrix4x4,
+    pub axis: StripeAxis,
 }
 
 impl StripePattern {
@@ -19,23 +87,37 @@ impl StripePattern {
             primary_color,
             secondary_color,
             transform,
+            axis: StripeAxis::X,
         };
     }
+
+    pub fn with_axis(primary_color: Color, secondary_color: Color, transform: Matrix4x4, axis: StripeAxis) -> Self {
+        let mut pattern = StripePattern::new(primary_color, secondary_color, transform);
+        pattern.axis = axis;
+        return pattern;
+    }
 }
 
 impl Default for StripePattern {
     fn default() -> Self {
         return Self {
-            primary_color: Color::new(1.0, 1.0, 1.0),
-            secondary_color: Color::new(0.0, 0.0, 0.0),
+            primary_color: Color::white(),
+            secondary_color: Color::black(),
             transform: Matrix4x4::identity(),
+            axis: StripeAxis::X,
         };
     }
 }
 
 impl Pattern for StripePattern {
     fn color_at(&self, point: &Vec4) -> Color {
-        if point.x().floor() as i32 % 2 == 0 {
+        let coordinate = match self.axis {
+            StripeAxis::X => *point.x(),
+            StripeAxis::Y => *point.y(),
+            StripeAxis::Z => *point.z(),
+        };
+
+        if stable_floor(coordinate) as i32 % 2 == 0 {
             return self.primary_color;
         }
 
@@ -43,13 +125,18 @@ impl Pattern for StripePattern {
     }
 
     fn color_at_object(&self, shape: &dyn Shape, world_point: &Vec4) -> Color {
-        let object_point = shape.transform().invert() * *world_point;
+        let object_point = shape.world_to_object(world_point);
         let pattern_point = self.transform * object_point;
 
         return self.color_at(&pattern_point);
     }
+
+    fn color_at_nested(&self, point: &Vec4) -> Color {
+        return self.color_at(&(self.transform * *point));
+    }
 }
 
+#[derive(Clone)]
 pub struct GradientPattern {
     pub primary_color: Color,
     pub secondary_color: Color,
@@ -69,8 +156,8 @@ impl GradientPattern {
 impl Default for GradientPattern {
     fn default() -> Self {
         return Self {
-            primary_color: Color::new(1.0, 1.0, 1.0),
-            secondary_color: Color::new(0.0, 0.0, 0.0),
+            primary_color: Color::white(),
+            secondary_color: Color::black(),
             transform: Matrix4x4::identity(),
         };
     }
@@ -85,13 +172,18 @@ impl Pattern for GradientPattern {
     }
 
     fn color_at_object(&self, shape: &dyn Shape, world_point: &Vec4) -> Color {
-        let object_point = shape.transform().invert() * *world_point;
+        let object_point = shape.world_to_object(world_point);
         let pattern_point = self.transform * object_point;
 
         return self.color_at(&pattern_point);
     }
+
+    fn color_at_nested(&self, point: &Vec4) -> Color {
+        return self.color_at(&(self.transform * *point));
+    }
 }
 
+#[derive(Clone)]
 pub struct RingPattern {
     pub primary_color: Color,
     pub secondary_color: Color,
@@ -111,8 +203,8 @@ impl RingPattern {
 impl Default for RingPattern {
     fn default() -> Self {
         return Self {
-            primary_color: Color::new(1.0, 1.0, 1.0),
-            secondary_color: Color::new(0.0, 0.0, 0.0),
+            primary_color: Color::white(),
+            secondary_color: Color::black(),
             transform: Matrix4x4::identity(),
         };
     }
@@ -120,7 +212,7 @@ impl Default for RingPattern {
 
 impl Pattern for RingPattern {
     fn color_at(&self, point: &Vec4) -> Color {
-        if (point.x() * point.x() + point.z() * point.z()).sqrt() as i32 % 2 == 0 {
+        if stable_floor((point.x() * point.x() + point.z() * point.z()).sqrt()) as i32 % 2 == 0 {
             return self.primary_color;
         }
 
@@ -128,13 +220,18 @@ impl Pattern for RingPattern {
     }
 
     fn color_at_object(&self, shape: &dyn Shape, world_point: &Vec4) -> Color {
-        let object_point = shape.transform().invert() * *world_point;
+        let object_point = shape.world_to_object(world_point);
         let pattern_point = self.transform * object_point;
 
         return self.color_at(&pattern_point);
     }
+
+    fn color_at_nested(&self, point: &Vec4) -> Color {
+        return self.color_at(&(self.transform * *point));
+    }
 }
 
+#[derive(Clone)]
 pub struct CheckeredPattern {
     pub primary_color: Color,
     pub secondary_color: Color,
@@ -154,8 +251,8 @@ impl CheckeredPattern {
 impl Default for CheckeredPattern {
     fn default() -> Self {
         return Self {
-            primary_color: Color::new(1.0, 1.0, 1.0),
-            secondary_color: Color::new(0.0, 0.0, 0.0),
+            primary_color: Color::white(),
+            secondary_color: Color::black(),
             transform: Matrix4x4::identity(),
         };
     }
@@ -163,7 +260,7 @@ impl Default for CheckeredPattern {
 
 impl Pattern for CheckeredPattern {
     fn color_at(&self, point: &Vec4) -> Color {
-        if (point.x().floor() + point.y().floor() + point.z().floor()) as i32 % 2 == 0 {
+        if (stable_floor(*point.x()) + stable_floor(*point.y()) + stable_floor(*point.z())) as i32 % 2 == 0 {
             return self.primary_color;
         }
 
@@ -171,17 +268,49 @@ impl Pattern for CheckeredPattern {
     }
 
     fn color_at_object(&self, shape: &dyn Shape, world_point: &Vec4) -> Color {
-        let object_point = shape.transform().invert() * *world_point;
+        let object_point = shape.world_to_object(world_point);
         let pattern_point = self.transform * object_point;
 
         return self.color_at(&pattern_point);
     }
+
+    fn color_at_nested(&self, point: &Vec4) -> Color {
+        return self.color_at(&(self.transform * *point));
+    }
+
+    // Box filters each axis over the footprint's bounding box, the checker is the product of the three
+    // square waves so the filtered value is too. A footprint wider than a few squares fades to the average
+    fn color_at_footprint(&self, shape: &dyn Shape, world_point: &Vec4, dpdx: &Vec4, dpdy: &Vec4) -> Color {
+        let to_pattern = self.transform * shape.inverse_transform();
+        let pattern_point = to_pattern * *world_point;
+        let dx = to_pattern * *dpdx;
+        let dy = to_pattern * *dpdy;
+
+        let fx = filtered_square_wave(*pattern_point.x(), dx.x().abs() + dy.x().abs());
+        let fy = filtered_square_wave(*pattern_point.y(), dx.y().abs() + dy.y().abs());
+        let fz = filtered_square_wave(*pattern_point.z(), dx.z().abs() + dy.z().abs());
+
+        let primary_share = (1.0 + fx * fy * fz) / 2.0;
+        return self.primary_color * primary_share + self.secondary_color * (1.0 - primary_share);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Mix,
+    Multiply,
+    Screen,
+    Overlay,
 }
 
+#[derive(Clone)]
 pub struct BlendedPattern {
     pub first_pattern: Box<dyn Pattern>,
     pub second_pattern: Box<dyn Pattern>,
     pub transform: Matrix4x4,
+    // Share of the first pattern when mixing, the second pattern gets the rest. Only used by BlendMode::Mix
+    pub weight: f32,
+    pub mode: BlendMode,
 }
 
 impl BlendedPattern {
@@ -190,21 +319,50 @@ impl BlendedPattern {
             first_pattern,
             second_pattern,
             transform,
+            weight: 0.5,
+            mode: BlendMode::Mix,
         };
     }
+
+    pub fn weighted(first_pattern: Box<dyn Pattern>, second_pattern: Box<dyn Pattern>, weight: f32, transform: Matrix4x4) -> Self {
+        let mut pattern = BlendedPattern::new(first_pattern, second_pattern, transform);
+        pattern.weight = weight;
+        return pattern;
+    }
+
+    fn overlay_channel(a: f32, b: f32) -> f32 {
+        if a < 0.5 {
+            return 2.0 * a * b;
+        }
+        return 1.0 - 2.0 * (1.0 - a) * (1.0 - b);
+    }
 }
 
 impl Pattern for BlendedPattern {
     fn color_at(&self, point: &Vec4) -> Color {
-        let mut result = self.first_pattern.color_at(point) + self.second_pattern.color_at(point);
-        result = result * 0.5;
-        return result
+        let a = self.first_pattern.color_at_nested(point);
+        let b = self.second_pattern.color_at_nested(point);
+
+        match self.mode {
+            BlendMode::Mix => return a * self.weight + b * (1.0 - self.weight),
+            BlendMode::Multiply => return a * b,
+            BlendMode::Screen => return a + b - a * b,
+            BlendMode::Overlay => return Color::new(
+                BlendedPattern::overlay_channel(*a.r(), *b.r()),
+                BlendedPattern::overlay_channel(*a.g(), *b.g()),
+                BlendedPattern::overlay_channel(*a.b(), *b.b()),
+            ),
+        }
     }
 
     fn color_at_object(&self, shape: &dyn Shape, world_point: &Vec4) -> Color {
-        let object_point = shape.transform().invert() * *world_point;
+        let object_point = shape.world_to_object(world_point);
         let pattern_point = self.transform * object_point;
 
         return self.color_at(&pattern_point);
     }
+
+    fn color_at_nested(&self, point: &Vec4) -> Color {
+        return self.color_at(&(self.transform * *point));
+    }
 }
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::geometry::Vec4;
+use crate::intersection::Intersection;
+use crate::ray::Ray;
+use crate::world::World;
+
+const MAX_PHOTON_BOUNCES: u32 = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Photon {
+    pub position: Vec4,
+    pub direction: Vec4,
+    pub power: Color,
+}
+
+// Caustic photon map: only photons that went through at least one reflective or transparent
+// surface before landing on a diffuse one are stored, direct light is left to Material::lighting
+pub struct PhotonMap {
+    pub photons: Vec<Photon>,
+    pub gather_radius: f32,
+    cells: HashMap<(i32, i32, i32), Vec<usize>>,
+}
+
+impl PhotonMap {
+    pub fn build(world: &World, photons_per_light: usize, gather_radius: f32) -> Self {
+        let mut photons: Vec<Photon> = Vec::new();
+
+        for light in &world.lights {
+            if photons_per_light == 0 {
+                break;
+            }
+
+            let origin = light.sample_points(world, 1)[0];
+            // A point light of intensity I radiates a total flux of 4 * PI * I, shared by all its photons
+            let power = light.intensity * (4.0 * PI / photons_per_light as f32);
+
+            for i in 0..photons_per_light {
+                let ray = Ray::new(origin, PhotonMap::emission_direction(i, photons_per_light));
+                PhotonMap::trace(world, ray, power, MAX_PHOTON_BOUNCES, false, &mut photons);
+            }
+        }
+
+        let mut photon_map = Self {
+            photons,
+            gather_radius,
+            cells: HashMap::new(),
+        };
+
+        for i in 0..photon_map.photons.len() {
+            let cell = photon_map.cell(&photon_map.photons[i].position);
+            photon_map.cells.entry(cell).or_default().push(i);
+        }
+
+        return photon_map;
+    }
+
+    // Evenly spreads directions over the sphere along a Fibonacci spiral
+    fn emission_direction(index: usize, count: usize) -> Vec4 {
+        let golden_angle = PI * (3.0 - 5.0_f32.sqrt());
+        let y = 1.0 - 2.0 * (index as f32 + 0.5) / count as f32;
+        let radius = (1.0 - y * y).max(0.0).sqrt();
+        let phi = golden_angle * index as f32;
+
+        return Vec4::vector(phi.cos() * radius, y, phi.sin() * radius);
+    }
+
+    fn trace(world: &World, ray: Ray, power: Color, remaining: u32, specular: bool, photons: &mut Vec<Photon>) {
+        let mut intersection = world.intersect_world(ray);
+        let xs = intersection.clone();
+
+        let hit = match Intersection::hit(&mut intersection) {
+            Some(hit) => hit,
+            None => return,
+        };
+
+        let comp = hit.prepare_computations(&ray, Some(&xs));
+        let material = comp.object.material();
+
+        if material.reflective == 0.0 && material.transparency == 0.0 {
+            if specular {
+                photons.push(Photon {
+                    position: comp.point,
+                    direction: ray.direction,
+                    power,
+                });
+            }
+            return;
+        }
+
+        if remaining == 0 {
+            return;
+        }
+
+        if material.reflective > 0.0 {
+            let reflect_ray = Ray::new(comp.over_point, comp.reflectv);
+            PhotonMap::trace(world, reflect_ray, power * material.reflective, remaining - 1, true, photons);
+        }
+
+        if material.transparency > 0.0 {
+            if let Some(direction) = comp.refracted_direction() {
+                let refract_ray = Ray::new(comp.under_point, direction);
+                let power = power * material.color * material.transparency;
+                PhotonMap::trace(world, refract_ray, power, remaining - 1, true, photons);
+            }
+        }
+    }
+
+    fn cell(&self, point: &Vec4) -> (i32, i32, i32) {
+        return (
+            (point.x() / self.gather_radius).floor() as i32,
+            (point.y() / self.gather_radius).floor() as i32,
+            (point.z() / self.gather_radius).floor() as i32,
+        );
+    }
+
+    // Density estimate of the caustic light arriving at a point: the power of the photons within
+    // gather_radius that hit the same side of the surface, divided by the area of the gather disc
+    pub fn irradiance_at(&self, point: &Vec4, normalv: &Vec4) -> Color {
+        let mut total = Color::black();
+
+        if self.photons.is_empty() || self.gather_radius <= 0.0 {
+            return total;
+        }
+
+        let (cx, cy, cz) = self.cell(point);
+
+        for x in cx - 1..=cx + 1 {
+            for y in cy - 1..=cy + 1 {
+                for z in cz - 1..=cz + 1 {
+                    let indices = match self.cells.get(&(x, y, z)) {
+                        Some(indices) => indices,
+                        None => continue,
+                    };
+
+                    for &i in indices {
+                        let photon = &self.photons[i];
+                        if photon.position.distance(point) <= self.gather_radius && photon.direction.dot(normalv) < 0.0 {
+                            total = total + photon.power;
+                        }
+                    }
+                }
+            }
+        }
+
+        return total * (1.0 / (PI * self.gather_radius * self.gather_radius));
+    }
+}
@@ -1,9 +1,34 @@
+use std::f32::consts::PI;
+
 use crate::geometry::{Matrix4x4, Vec4};
 
+// Rays through the neighbouring pixels one step right (x) and one step down (y), used to estimate
+// how much of a surface a single pixel covers
+#[derive(Copy, Clone)]
+pub struct RayDifferential {
+    pub x_origin: Vec4,
+    pub x_direction: Vec4,
+    pub y_origin: Vec4,
+    pub y_direction: Vec4,
+}
+
+impl RayDifferential {
+    pub fn transform(&self, matrix: Matrix4x4) -> Self {
+        return Self {
+            x_origin: matrix * self.x_origin,
+            x_direction: matrix * self.x_direction,
+            y_origin: matrix * self.y_origin,
+            y_direction: matrix * self.y_direction,
+        };
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct Ray {
     pub origin: Vec4,
     pub direction: Vec4,
+    // Only camera rays carry differentials, reflected, refracted and shadow rays leave it empty
+    pub differential: Option<RayDifferential>,
 }
 
 impl Ray {
@@ -11,6 +36,15 @@ impl Ray {
         return Self {
             origin,
             direction,
+            differential: None,
+        };
+    }
+
+    pub fn with_differential(origin: Vec4, direction: Vec4, differential: RayDifferential) -> Self {
+        return Self {
+            origin,
+            direction,
+            differential: Some(differential),
         };
     }
 
@@ -22,10 +56,37 @@ impl Ray {
         return self.direction.reflect(normalv);
     }
 
+    // The direction is deliberately left unnormalized: the transformed ray reaches the transformed
+    // point at the same t, so t found in object space is valid unchanged in world space. Renormalizing
+    // would rescale t under non-uniform scaling
     pub fn transform(&self, matrix: Matrix4x4) -> Self {
         return Self {
             origin: matrix * self.origin,
             direction: matrix * self.direction,
+            differential: self.differential.map(|d| d.transform(matrix)),
         };
     }
-}
\ No newline at end of file
+
+    // A copy of the ray with the origin moved to a uniform point on a disk of `origin_radius` across the
+    // direction, and the direction turned to a uniform direction within `dir_cone` radians of it, see
+    // Vec4::cone_sample. `rng` must return uniform numbers in [0, 1) and is called four times. With both
+    // at 0 the ray comes back unchanged without drawing any numbers. The direction keeps its length and
+    // the differentials, which only describe the unperturbed ray, are dropped
+    pub fn jittered<R: FnMut() -> f32>(&self, origin_radius: f32, dir_cone: f32, rng: &mut R) -> Ray {
+        if origin_radius <= 0.0 && dir_cone <= 0.0 {
+            return *self;
+        }
+
+        let length = self.direction.magnitude();
+        let (tangent, bitangent) = self.direction.orthonormal_basis();
+
+        let radius = origin_radius.max(0.0) * rng().sqrt();
+        let angle = 2.0 * PI * rng();
+        let origin = self.origin + tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin());
+
+        let (u1, u2) = (rng(), rng());
+        let direction = self.direction.cone_sample(dir_cone.max(0.0).cos(), u1, u2);
+
+        return Ray::new(origin, direction * length);
+    }
+}
@@ -0,0 +1,196 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::geometry::{Matrix4x4, Vec4};
+use crate::id;
+use crate::intersection::Intersection;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::util;
+
+// Signed distance from an object space point to the surface, negative inside. It only has to be a
+// lower bound on the true distance for the ray marching to stay on the surface
+pub type DistanceFn = dyn Fn(Vec4) -> f32 + Send + Sync;
+
+// Polynomial smooth minimum of two distances, blending them over a band of width k. k = 0 is a plain
+// union, larger values melt the shapes into each other like metaballs
+pub fn smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+
+    let h = util::clamp_f32(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
+    return b * (1.0 - h) + a * h - k * h * (1.0 - h);
+}
+
+// Implicit surface given by a distance function and found by sphere tracing: the ray moves forward by
+// the distance to the nearest surface until it is within `epsilon` of it. Gives up after `max_steps`
+// steps or once the ray is `max_distance` from its origin, both measured in object space
+#[derive(Clone)]
+pub struct SdfShape {
+    pub id: Uuid,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
+    pub material: Material,
+    enabled: bool,
+    // Shared, so copies of the shape all march the same function
+    distance: Arc<DistanceFn>,
+    pub max_steps: usize,
+    pub epsilon: f32,
+    pub max_distance: f32,
+}
+
+impl SdfShape {
+    pub fn new<F: Fn(Vec4) -> f32 + Send + Sync + 'static>(material: Material, distance: F) -> Self {
+        return SdfShape::with_id(material, distance, id::new_id());
+    }
+
+    pub fn with_id<F: Fn(Vec4) -> f32 + Send + Sync + 'static>(material: Material, distance: F, id: Uuid) -> Self {
+        return Self {
+            id,
+            transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
+            material,
+            enabled: true,
+            distance: Arc::new(distance),
+            max_steps: 256,
+            epsilon: 0.0001,
+            max_distance: 100.0,
+        };
+    }
+
+    // Two unit radius spheres centred on the points and blended together by smooth_min
+    pub fn blended_spheres(material: Material, first: Vec4, second: Vec4, k: f32) -> Self {
+        return SdfShape::new(material, move |point| {
+            let a = (point - first).magnitude() - 1.0;
+            let b = (point - second).magnitude() - 1.0;
+            return smooth_min(a, b, k);
+        });
+    }
+
+    pub fn distance_at(&self, local_point: &Vec4) -> f32 {
+        return (self.distance)(*local_point);
+    }
+
+    // Distances along the unit length direction where the ray crosses the surface, either way. Marches
+    // on the absolute distance so it carries on through the inside to find where the ray leaves again
+    fn march(&self, origin: Vec4, direction: Vec4) -> Vec<f32> {
+        let mut crossings: Vec<f32> = Vec::new();
+        let mut s = 0.0;
+        let mut on_surface = false;
+
+        for _ in 0..self.max_steps {
+            if s > self.max_distance {
+                break;
+            }
+
+            let distance = self.distance_at(&(origin + direction * s)).abs();
+            if distance < self.epsilon {
+                // Creep across the surface so the same crossing isn't counted twice
+                if !on_surface {
+                    crossings.push(s);
+                    on_surface = true;
+                }
+                s += self.epsilon;
+            } else {
+                on_surface = false;
+                s += distance;
+            }
+        }
+
+        return crossings;
+    }
+}
+
+impl Shape for SdfShape {
+    fn id(&self) -> &Uuid {
+        return &self.id;
+    }
+
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(SdfShape { id: id::new_id(), ..self.clone() }));
+    }
+
+    fn transform(&self) -> &Matrix4x4 {
+        return &self.transform;
+    }
+
+    fn set_transform(&mut self, matrix: Matrix4x4) {
+        self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
+    }
+
+    fn material(&self) -> &Material {
+        return &self.material;
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        return &mut self.material;
+    }
+
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        // Distances are only meaningful along a unit direction, t is converted back to the ray's own units
+        let length = ray.direction.magnitude();
+        if length == 0.0 {
+            return Vec::new();
+        }
+
+        return self
+            .march(ray.origin, ray.direction / length)
+            .into_iter()
+            .map(|s| Intersection::new(self, s / length))
+            .collect();
+    }
+
+    // Gradient of the distance function by central differences over epsilon
+    fn local_normal_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
+        let h = self.epsilon;
+        let difference = |offset: Vec4| self.distance_at(&(*local_point + offset)) - self.distance_at(&(*local_point - offset));
+
+        let gradient = Vec4::vector(
+            difference(Vec4::vector(h, 0.0, 0.0)),
+            difference(Vec4::vector(0.0, h, 0.0)),
+            difference(Vec4::vector(0.0, 0.0, h)),
+        );
+
+        return gradient.normalize();
+    }
+
+    fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
+        let local_point = self.world_to_object(world_point);
+        let local_normal = self.local_normal_at(&local_point, i);
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // Marches in from max_distance towards the object space origin along the direction picked by (u, v)
+    // like a sphere sample. Not uniform over the area, and the origin itself if nothing is hit
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let phi = 2.0 * PI * u;
+        let cos_theta = 1.0 - 2.0 * v;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let outward = Vec4::vector(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+
+        let start = Vec4::ORIGIN + outward * self.max_distance;
+        let local_point = match self.march(start, -outward).first() {
+            Some(s) => start - outward * *s,
+            None => Vec4::ORIGIN,
+        };
+
+        return self.transform * local_point;
+    }
+}
@@ -1,45 +1,177 @@
+use std::f32::consts::PI;
 use std::mem::swap;
 
 use uuid::Uuid;
-use crate::geometry::{Matrix4x4, Vec4};
+use crate::color::Color;
+use crate::geometry::{Matrix4x4, Quaternion, Vec4};
+use crate::id;
 use crate::intersection::Intersection;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::util;
 
-pub trait Shape {
+// Implemented by every object that can be placed in a World, including shapes defined in other crates.
+// local_intersect gets the ray already moved into object space and should return hits pointing at
+// `self` (or at a child it owns), with t measured along that ray. Shapes are shared between render
+// threads, so they must be Send + Sync
+pub trait Shape: Send + Sync {
     fn id(&self) -> &Uuid;
+    // Deep copy with its own new id, for stamping out many instances of a configured shape with
+    // different transforms. The id has to change: hits and the refraction bookkeeping tell shapes
+    // apart by it, so look copies up by their new ids. Models share their triangles between copies,
+    // see Model. Shapes that don't implement it can't be copied and return None
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return None;
+    }
     fn transform(&self) -> &Matrix4x4;
     fn set_transform(&mut self, matrix: Matrix4x4);
+
+    // The built-in shapes keep their transform private and cache the inverse whenever set_transform is
+    // called, since every intersection and normal needs it. Shapes without a cache get it recomputed
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.transform().invert();
+    }
+    // World space point in the shape's own object space, e.g. for patterns
+    fn world_to_object(&self, world_point: &Vec4) -> Vec4 {
+        return self.inverse_transform() * *world_point;
+    }
+    // Object space normal carried into world space by the inverse transpose, which keeps it
+    // perpendicular to the surface under non uniform scaling. Comes back unit length with w = 0
+    fn object_to_world_normal(&self, local_normal: &Vec4) -> Vec4 {
+        let world_normal = self.inverse_transform().transpose() * *local_normal;
+        return Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z()).normalize();
+    }
     fn material(&self) -> &Material;
     fn material_mut(&mut self) -> &mut Material;
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection>;
+    // Disabled shapes stay in the scene but are skipped when tracing, so they are neither seen nor
+    // cast shadows. Shapes that can't be switched off are always enabled and ignore set_enabled
+    fn enabled(&self) -> bool {
+        return true;
+    }
+    fn set_enabled(&mut self, _enabled: bool) {}
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
     fn local_normal_at(&self, local_point: &Vec4, hit: Intersection) -> Vec4;
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4;
+    // Object space direction across the surface at the point, e.g. of increasing u, that tangent space
+    // normal maps are lined up with. It only has to point roughly along the surface, tangent_at makes it
+    // perpendicular to the normal. Shapes without texture coordinates get an arbitrary direction
+    fn local_tangent_at(&self, local_point: &Vec4, hit: Intersection) -> Vec4 {
+        return self.local_normal_at(local_point, hit).orthonormal_basis().0;
+    }
+    // Unit tangent and bitangent (normal x tangent) at a world point, which form an orthonormal frame
+    // with world_normal_at for applying tangent space normal maps to any shape
+    fn tangent_at(&self, world_point: &Vec4, hit: Intersection) -> (Vec4, Vec4) {
+        let normal = self.world_normal_at(world_point, hit);
+        let local_point = self.world_to_object(world_point);
+        // Directions in the surface are carried by the transform itself, unlike the normal
+        let along = *self.transform() * self.local_tangent_at(&local_point, hit);
+
+        let mut tangent = along - normal * along.dot(&normal);
+        if tangent.magnitude() < util::THRESHOLD_F32 {
+            // Singular spots such as the poles of a sphere or the tip of a cone
+            tangent = normal.orthonormal_basis().0;
+        }
+
+        let tangent = tangent.normalize();
+        return (tangent, normal.cross(&tangent));
+    }
+    // Color painted onto the surface at a hit, used instead of the material color. Only meshes with
+    // per-vertex colors have one
+    fn vertex_color(&self, _hit: Intersection) -> Option<Color> {
+        return None;
+    }
+    // Whether hits carry the barycentric (u, v) of a triangle, which wireframe materials draw their
+    // edges from. Other shapes report u = v = 0
+    fn has_barycentric_hits(&self) -> bool {
+        return false;
+    }
+    // Maps (u, v) in [0, 1] x [0, 1] to a world space point on the surface, e.g. for shape lights.
+    // Shapes that can't be sampled give their object space origin, so they light like a point light
+    fn sample_surface(&self, _u: f32, _v: f32) -> Vec4 {
+        return *self.transform() * Vec4::ORIGIN;
+    }
+    // Shapes made of other shapes (groups, models) expose their parts, leaf shapes have none
+    fn children(&self) -> Option<&[Box<dyn Shape>]> {
+        return None;
+    }
+    fn children_mut(&mut self) -> Option<&mut [Box<dyn Shape>]> {
+        return None;
+    }
+    // Carries points placed by the children's transforms into the space this shape's own transform
+    // places it in. Shapes that intersect their children in object space, like Model, apply their
+    // own transform. Groups bake theirs into the children and override it with the identity
+    fn children_to_parent(&self) -> Matrix4x4 {
+        return *self.transform();
+    }
+
+    // Gives this shape and every shape inside it its own copy of `material`, e.g. to recolor a whole
+    // group or a loaded model
+    fn set_material_recursive(&mut self, material: &Material) {
+        *self.material_mut() = material.clone();
+
+        if let Some(children) = self.children_mut() {
+            for child in children {
+                child.set_material_recursive(material);
+            }
+        }
+    }
+}
+
+impl<'a> dyn Shape + 'a {
+    // Base color of the surface at a world point, the pattern if the material has one, without any lighting
+    pub fn albedo_at(&self, world_point: &Vec4) -> Color {
+        return self.material().color_at_object(self, world_point);
+    }
 }
 
+#[derive(Clone)]
 pub struct Sphere {
     pub id: Uuid,
-    pub transform: Matrix4x4,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     pub material: Material,
+    enabled: bool,
 }
 
 impl Sphere {
     pub fn new(material: Material) -> Self {
         return Self {
-            id: Uuid::new_v4(),
+            id: id::new_id(),
             transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
             material,
+            enabled: true,
         };
     }
 
+    pub fn with_id(material: Material, id: Uuid) -> Self {
+        return Self {
+            id,
+            transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
+            material,
+            enabled: true,
+        };
+    }
+
+    // Surface area and volume are for the unit shape in object space, before the transform is applied
+    pub fn surface_area(&self) -> f32 {
+        return 4.0 * PI;
+    }
+
+    pub fn volume(&self) -> f32 {
+        return 4.0 / 3.0 * PI;
+    }
+
     pub fn glass_sphere() -> Self {
-        let mut material = Material::default();
-        material.transparency = 1.0;
-        material.refraction = 1.5;
-        material.reflective = 0.8;
-        material.specular = 1.0;
-        material.shininess = 300.0;
+        let material = Material {
+            transparency: 1.0,
+            refraction: 1.5,
+            reflective: 0.8,
+            specular: 1.0,
+            shininess: 300.0,
+            ..Material::default()
+        };
 
         return Sphere::new(material);
     }
@@ -50,12 +182,21 @@ impl Shape for Sphere {
         return &self.id;
     }
 
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(Sphere { id: id::new_id(), ..self.clone() }));
+    }
+
     fn transform(&self) -> &Matrix4x4 {
         return &self.transform;
     }
 
     fn set_transform(&mut self, matrix: Matrix4x4) {
         self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
     }
 
     fn material(&self) -> &Material {
@@ -66,14 +207,31 @@ impl Shape for Sphere {
         return &mut self.material;
     }
 
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection> {
-        let sphere_to_ray = ray.origin - Vec4::point(0.0, 0.0, 0.0);
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
 
-        let a = ray.direction.dot(&ray.direction);
-        let b = 2.0 * ray.direction.dot(&sphere_to_ray);
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let sphere_to_ray = ray.origin - Vec4::ORIGIN;
+
+        // Transformed rays are usually not unit length; solving with a normalized direction keeps the
+        // discriminant on the same scale for every ray, then t is converted back to the ray's own units
+        let length = ray.direction.magnitude();
+        let direction = ray.direction / length;
+
+        let b = 2.0 * direction.dot(&sphere_to_ray);
         let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
 
-        let discriminant = b*b - 4.0 * a * c;
+        let mut discriminant = b*b - 4.0 * c;
+
+        // Tangent rays can land a rounding error below zero, treat them as touching the silhouette
+        if discriminant < 0.0 && discriminant > -util::THRESHOLD_F32 * 100.0 {
+            discriminant = 0.0;
+        }
 
         if discriminant < 0.0 {
             return Vec::new();
@@ -81,44 +239,106 @@ impl Shape for Sphere {
 
         let mut intersections: Vec<Intersection> = Vec::new();
 
-        let result1 = (-b - discriminant.sqrt()) / (2.0 * a);
+        let result1 = (-b - discriminant.sqrt()) / (2.0 * length);
         intersections.push(Intersection::new(self, result1));
 
-        let result2 = (-b + discriminant.sqrt()) / (2.0 * a);
+        let result2 = (-b + discriminant.sqrt()) / (2.0 * length);
         intersections.push(Intersection::new(self, result2));
 
         return intersections;
     }
 
     fn local_normal_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
-        let local_normal = *local_point - Vec4::point(0.0, 0.0, 0.0);
+        let local_normal = *local_point - Vec4::ORIGIN;
         return local_normal.normalize();
     }
 
+    // Around the y axis, the direction a longitude angle increases in
+    fn local_tangent_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
+        return Vec4::vector(*local_point.z(), 0.0, -*local_point.x());
+    }
+
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
-        let local_point = self.transform().invert() * *world_point;
+        let local_point = self.world_to_object(world_point);
         let local_normal = self.local_normal_at(&local_point, i);
-        let world_normal = self.transform().invert().transpose() * local_normal;
-        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
-    
-        return world_normal.normalize();
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // Uniform over the surface area: u sweeps the longitude, v the height (cos of the polar angle)
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let phi = 2.0 * PI * u;
+        let cos_theta = 1.0 - 2.0 * v;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+        let local_point = Vec4::point(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+        return self.transform * local_point;
     }
 }
 
+#[derive(Clone)]
 pub struct Plane {
     pub id: Uuid,
-    pub transform: Matrix4x4,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     pub material: Material,
+    enabled: bool,
 }
 
 impl Plane {
     pub fn new(material: Material) -> Self {
         return Self {
-            id: Uuid::new_v4(),
+            id: id::new_id(),
+            transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
+            material,
+            enabled: true,
+        };
+    }
+
+    pub fn with_id(material: Material, id: Uuid) -> Self {
+        return Self {
+            id,
             transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
             material,
+            enabled: true,
         };
     }
+
+    // The plane never ends and encloses nothing
+    pub fn surface_area(&self) -> f32 {
+        return f32::INFINITY;
+    }
+
+    pub fn volume(&self) -> f32 {
+        return 0.0;
+    }
+
+    // Plane through the origin facing `normal`, e.g. (0, 0, 1) for a wall facing +z. The transform is
+    // the shortest rotation taking the local +y normal onto it and can be combined with a translation
+    // afterwards as usual
+    pub fn from_normal(material: Material, normal: Vec4) -> Self {
+        let target = Vec4::vector(*normal.x(), *normal.y(), *normal.z());
+        if target.magnitude() < util::THRESHOLD_F32 {
+            panic!("Plane normal must not be zero");
+        }
+
+        let target = target.normalize();
+        let up = Vec4::vector(0.0, 1.0, 0.0);
+        let cos = util::clamp_f32(up.dot(&target), -1.0, 1.0);
+        let axis = up.cross(&target);
+
+        // Facing straight down there is no unique axis, any horizontal one flips the plane over
+        let rotation = if axis.magnitude() < util::THRESHOLD_F32 {
+            if cos > 0.0 { Matrix4x4::identity() } else { Matrix4x4::rotatation_x(PI) }
+        } else {
+            Quaternion::from_axis_angle(&axis, cos.acos()).to_matrix()
+        };
+
+        let mut plane = Plane::new(material);
+        plane.set_transform(rotation);
+        return plane;
+    }
 }
 
 impl Shape for Plane {
@@ -126,12 +346,21 @@ impl Shape for Plane {
         return &self.id;
     }
 
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(Plane { id: id::new_id(), ..self.clone() }));
+    }
+
     fn transform(&self) -> &Matrix4x4 {
         return &self.transform;
     }
 
     fn set_transform(&mut self, matrix: Matrix4x4) {
         self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
     }
 
     fn material(&self) -> &Material {
@@ -142,48 +371,94 @@ impl Shape for Plane {
         return &mut self.material;
     }
 
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection> {
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
         if ray.direction.y().abs() < util::THRESHOLD_F32 {
             return Vec::new();
         }
 
         let t = -ray.origin.y() / ray.direction.y();
 
-        let mut result: Vec<Intersection> = Vec::new();
-        result.push(Intersection::new(self, t));
-
-        return result;
+        return vec![Intersection::new(self, t)];
     }
 
     fn local_normal_at(&self, _: &Vec4, _: Intersection) -> Vec4 {
         return Vec4::vector(0.0, 1.0, 0.0);
     }
 
+    fn local_tangent_at(&self, _: &Vec4, _: Intersection) -> Vec4 {
+        return Vec4::vector(1.0, 0.0, 0.0);
+    }
+
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
-        let local_point = self.transform().invert() * *world_point;
+        let local_point = self.world_to_object(world_point);
         let local_normal = self.local_normal_at(&local_point, i);
-        let world_normal = self.transform().invert().transpose() * local_normal;
-        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
-    
-        return world_normal.normalize();
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // Planes are infinite, so samples are spread uniformly over the 2x2 square around the object space origin
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let local_point = Vec4::point(2.0 * u - 1.0, 0.0, 2.0 * v - 1.0);
+        return self.transform * local_point;
     }
 }  
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+#[derive(Clone)]
 pub struct Cube {
     pub id: Uuid,
-    pub transform: Matrix4x4,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     pub material: Material,
+    enabled: bool,
 }
 
 impl Cube {
     pub fn new(material: Material) -> Self {
         return Self {
-            id: Uuid::new_v4(),
+            id: id::new_id(),
+            transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
+            material,
+            enabled: true,
+        };
+    }
+
+    pub fn with_id(material: Material, id: Uuid) -> Self {
+        return Self {
+            id,
             transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
             material,
+            enabled: true,
         };
     }
 
+    // Object space values for the cube from -1 to 1, before the transform is applied
+    pub fn surface_area(&self) -> f32 {
+        return 24.0;
+    }
+
+    pub fn volume(&self) -> f32 {
+        return 8.0;
+    }
+
     fn check_axis(origin: &f32, direction: &f32) -> (f32, f32) {
         let mut tmin: f32;
         let mut tmax: f32;
@@ -205,6 +480,46 @@ impl Cube {
 
         return (tmin, tmax);
     }
+
+    pub fn face_from_point(local_point: &Vec4) -> CubeFace {
+        let maxc = util::max3(local_point.x().abs(), local_point.y().abs(), local_point.z().abs());
+
+        if maxc == local_point.x().abs() {
+            if *local_point.x() >= 0.0 {
+                return CubeFace::Right;
+            }
+            return CubeFace::Left;
+        } else if maxc == local_point.y().abs() {
+            if *local_point.y() >= 0.0 {
+                return CubeFace::Up;
+            }
+            return CubeFace::Down;
+        }
+
+        if *local_point.z() >= 0.0 {
+            return CubeFace::Front;
+        }
+        return CubeFace::Back;
+    }
+
+    // Maps an object space point on the cube to the face it lies on and (u, v) coordinates in [0, 1]
+    // on that face, laid out as an unfolded cube map seen from outside. Points on an edge map to the
+    // edge of the face, and points slightly off the surface are clamped onto it
+    pub fn uv_at(local_point: &Vec4) -> (CubeFace, f32, f32) {
+        let (x, y, z) = (*local_point.x(), *local_point.y(), *local_point.z());
+        let face = Cube::face_from_point(local_point);
+
+        let (u, v) = match face {
+            CubeFace::Front => (x + 1.0, y + 1.0),
+            CubeFace::Back => (1.0 - x, y + 1.0),
+            CubeFace::Left => (z + 1.0, y + 1.0),
+            CubeFace::Right => (1.0 - z, y + 1.0),
+            CubeFace::Up => (x + 1.0, 1.0 - z),
+            CubeFace::Down => (x + 1.0, z + 1.0),
+        };
+
+        return (face, util::clamp_f32(u / 2.0, 0.0, 1.0), util::clamp_f32(v / 2.0, 0.0, 1.0));
+    }
 }
 
 impl Shape for Cube {
@@ -212,12 +527,21 @@ impl Shape for Cube {
         return &self.id;
     }
 
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(Cube { id: id::new_id(), ..self.clone() }));
+    }
+
     fn transform(&self) -> &Matrix4x4 {
         return &self.transform;
     }
 
     fn set_transform(&mut self, matrix: Matrix4x4) {
         self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
     }
 
     fn material(&self) -> &Material {
@@ -228,13 +552,21 @@ impl Shape for Cube {
         return &mut self.material;
     }
 
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection> {
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
         let (xtmin, xtmax) = Cube::check_axis(ray.origin.x(), ray.direction.x());
         let (ytmin, ytmax) = Cube::check_axis(ray.origin.y(), ray.direction.y());
         let (ztmin, ztmax) = Cube::check_axis(ray.origin.z(), ray.direction.z());
 
-        let tmin = util::max_f32(&vec![xtmin, ytmin, ztmin]).unwrap();
-        let tmax = util::min_f32(&vec![xtmax, ytmax, ztmax]).unwrap();
+        let tmin = util::max3(xtmin, ytmin, ztmin);
+        let tmax = util::min3(xtmax, ytmax, ztmax);
 
         if tmin > tmax {
             return Vec::new();
@@ -244,31 +576,55 @@ impl Shape for Cube {
     }
 
     fn local_normal_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
-        let maxc = util::max_f32(&vec![local_point.x().abs(), local_point.y().abs(), local_point.z().abs()]).unwrap();
-
-        if maxc == local_point.x().abs() {
-            return Vec4::vector(*local_point.x(), 0.0, 0.0);
-        } else if maxc == local_point.y().abs() {
-            return Vec4::vector(0.0, *local_point.y(), 0.0);
+        match Cube::face_from_point(local_point) {
+            CubeFace::Left | CubeFace::Right => return Vec4::vector(*local_point.x(), 0.0, 0.0),
+            CubeFace::Up | CubeFace::Down => return Vec4::vector(0.0, *local_point.y(), 0.0),
+            CubeFace::Front | CubeFace::Back => return Vec4::vector(0.0, 0.0, *local_point.z()),
         }
+    }
 
-        return Vec4::vector(0.0, 0.0, *local_point.z());
+    // The direction u of uv_at increases in on each face
+    fn local_tangent_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
+        match Cube::face_from_point(local_point) {
+            CubeFace::Front | CubeFace::Up | CubeFace::Down => return Vec4::vector(1.0, 0.0, 0.0),
+            CubeFace::Back => return Vec4::vector(-1.0, 0.0, 0.0),
+            CubeFace::Left => return Vec4::vector(0.0, 0.0, 1.0),
+            CubeFace::Right => return Vec4::vector(0.0, 0.0, -1.0),
+        }
     }
 
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
-        let local_point = self.transform().invert() * *world_point;
+        let local_point = self.world_to_object(world_point);
         let local_normal = self.local_normal_at(&local_point, i);
-        let world_normal = self.transform().invert().transpose() * local_normal;
-        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
-    
-        return world_normal.normalize();
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // u picks one of the six equally sized faces and the remainder of u along with v places the point on it
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let scaled = util::clamp_f32(u, 0.0, 1.0) * 6.0;
+        let face = f32::min(scaled.floor(), 5.0);
+        let a = 2.0 * (scaled - face) - 1.0;
+        let b = 2.0 * v - 1.0;
+
+        let local_point = match face as u32 {
+            0 => Vec4::point(1.0, a, b),
+            1 => Vec4::point(-1.0, a, b),
+            2 => Vec4::point(a, 1.0, b),
+            3 => Vec4::point(a, -1.0, b),
+            4 => Vec4::point(a, b, 1.0),
+            _ => Vec4::point(a, b, -1.0),
+        };
+        return self.transform * local_point;
     }
 }
 
+#[derive(Clone)]
 pub struct Cylinder {
     pub id: Uuid,
-    pub transform: Matrix4x4,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
     pub material: Material,
+    enabled: bool,
     pub minimum: f32,
     pub maximum: f32,
     pub closed: bool,
@@ -276,23 +632,52 @@ pub struct Cylinder {
 
 impl Cylinder {
     pub fn new(material: Material, minimum: f32, maximum: f32, closed: bool) -> Self {
+        return Cylinder::with_id(material, minimum, maximum, closed, id::new_id());
+    }
+
+    pub fn with_id(material: Material, minimum: f32, maximum: f32, closed: bool, id: Uuid) -> Self {
         return Self {
-            id: Uuid::new_v4(),
+            id,
             transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
             material,
+            enabled: true,
             minimum,
             maximum,
             closed,
         };
     }
 
+    // Open cylinder extending forever along y; the y-range checks in local_intersect hold for infinite bounds
+    pub fn infinite(material: Material) -> Self {
+        return Cylinder::new(material, f32::NEG_INFINITY, f32::INFINITY, false);
+    }
+
+    pub fn bounded(material: Material, minimum: f32, maximum: f32, closed: bool) -> Self {
+        return Cylinder::new(material, minimum, maximum, closed);
+    }
+
+    // Object space values, infinite when either end is unbounded. Caps only count towards the area
+    // when the cylinder is closed, the volume is the space between the ends either way
+    pub fn surface_area(&self) -> f32 {
+        let height = self.maximum - self.minimum;
+        let caps = if self.closed { 2.0 * PI } else { 0.0 };
+        return 2.0 * PI * height + caps;
+    }
+
+    pub fn volume(&self) -> f32 {
+        return PI * (self.maximum - self.minimum);
+    }
+
+    // The radius is always 1 in object space. Points on the rim land a rounding error either side of it,
+    // and the barrel doesn't count its own ends, so the cap takes the rim with a little slack
     fn check_cap(ray: Ray, t: f32) -> bool {
         let x = ray.origin.x() + t * ray.direction.x();
         let z = ray.origin.z() + t * ray.direction.z();
-        return (x*x + z*z) <= 1.0;
+        return (x*x + z*z) <= 1.0 + util::THRESHOLD_F32;
     }
 
-    pub fn intersect_caps(&self, ray: Ray) -> Vec<Intersection> {
+    pub fn intersect_caps(&self, ray: Ray) -> Vec<Intersection<'_>> {
         if !self.closed || util::equals_f32(ray.direction.y(), &0.0) {
             return Vec::new();
         }
@@ -317,12 +702,21 @@ impl Shape for Cylinder {
         return &self.id;
     }
 
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(Cylinder { id: id::new_id(), ..self.clone() }));
+    }
+
     fn transform(&self) -> &Matrix4x4 {
         return &self.transform;
     }
 
     fn set_transform(&mut self, matrix: Matrix4x4) {
         self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
     }
 
     fn material(&self) -> &Material {
@@ -333,11 +727,20 @@ impl Shape for Cylinder {
         return &mut self.material;
     }
 
-    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection> {
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
         let a = ray.direction.x().powi(2) + ray.direction.z().powi(2);
 
+        // Rays parallel to the axis never cross the barrel but can still pass through both caps
         if util::equals_f32(&a, &0.0) {
-            return Vec::new();
+            return self.intersect_caps(*ray);
         }
 
         let b = 2.0 * ray.origin.x() * ray.direction.x() + 2.0 * ray.origin.z() * ray.direction.z();
@@ -377,19 +780,244 @@ impl Shape for Cylinder {
 
         if dist < 1.0 && *local_point.y() >= self.maximum - util::THRESHOLD_F32 {
             return Vec4::vector(0.0, 1.0, 0.0);
-        } else if dist < 1.0 && *local_point.y() >= self.maximum - util::THRESHOLD_F32 {
+        } else if dist < 1.0 && *local_point.y() <= self.minimum + util::THRESHOLD_F32 {
             return Vec4::vector(0.0, -1.0, 0.0);
         } else {
             return Vec4::vector(*local_point.x(), 0.0, *local_point.z());
         }
     }
 
+    // Around the axis, which lies in the caps as well as the side
+    fn local_tangent_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
+        return Vec4::vector(*local_point.z(), 0.0, -*local_point.x());
+    }
+
     fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
-        let local_point = self.transform().invert() * *world_point;
+        let local_point = self.world_to_object(world_point);
         let local_normal = self.local_normal_at(&local_point, i);
-        let world_normal = self.transform().invert().transpose() * local_normal;
-        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
-    
-        return world_normal.normalize();
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // Uniform over the side wall; unbounded cylinders are sampled between y = -1 and y = 1
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let minimum = if self.minimum.is_finite() { self.minimum } else { -1.0 };
+        let maximum = if self.maximum.is_finite() { self.maximum } else { 1.0 };
+        let phi = 2.0 * PI * u;
+
+        let local_point = Vec4::point(phi.cos(), minimum + (maximum - minimum) * v, phi.sin());
+        return self.transform * local_point;
+    }
+}
+
+#[derive(Clone)]
+pub struct Cone {
+    pub id: Uuid,
+    transform: Matrix4x4,
+    inverse_transform: Matrix4x4,
+    pub material: Material,
+    enabled: bool,
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool,
+}
+
+impl Cone {
+    pub fn new(material: Material, minimum: f32, maximum: f32, closed: bool) -> Self {
+        return Cone::with_id(material, minimum, maximum, closed, id::new_id());
+    }
+
+    pub fn with_id(material: Material, minimum: f32, maximum: f32, closed: bool, id: Uuid) -> Self {
+        return Self {
+            id,
+            transform: Matrix4x4::identity(),
+            inverse_transform: Matrix4x4::identity(),
+            material,
+            enabled: true,
+            minimum,
+            maximum,
+            closed,
+        };
+    }
+
+    // Double napped cone meeting at the origin and extending forever along y
+    pub fn infinite(material: Material) -> Self {
+        return Cone::new(material, f32::NEG_INFINITY, f32::INFINITY, false);
+    }
+
+    pub fn bounded(material: Material, minimum: f32, maximum: f32, closed: bool) -> Self {
+        return Cone::new(material, minimum, maximum, closed);
+    }
+
+    // Object space values, infinite when either end is unbounded. The radius at height y is |y|, so the
+    // wall's slant is sqrt(2) times its height and both halves count when the range spans the apex
+    pub fn surface_area(&self) -> f32 {
+        let half_square = |y: f32| y * y.abs() / 2.0;
+        let wall = 2.0_f32.sqrt() * 2.0 * PI * (half_square(self.maximum) - half_square(self.minimum));
+        let caps = if self.closed { PI * (self.minimum * self.minimum + self.maximum * self.maximum) } else { 0.0 };
+        return wall + caps;
+    }
+
+    pub fn volume(&self) -> f32 {
+        return PI * (self.maximum.powi(3) - self.minimum.powi(3)) / 3.0;
+    }
+
+    fn check_cap(ray: Ray, t: f32, radius: f32) -> bool {
+        let x = ray.origin.x() + t * ray.direction.x();
+        let z = ray.origin.z() + t * ray.direction.z();
+        return (x*x + z*z) <= radius*radius;
+    }
+
+    pub fn intersect_caps(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        if !self.closed || util::equals_f32(ray.direction.y(), &0.0) {
+            return Vec::new();
+        }
+
+        let mut xs: Vec<Intersection> = Vec::new();
+        let t = (self.minimum - ray.origin.y()) / ray.direction.y();
+        if Cone::check_cap(ray, t, self.minimum.abs()) {
+            xs.push(Intersection::new(self, t));
+        }
+
+        let t = (self.maximum - ray.origin.y()) / ray.direction.y();
+        if Cone::check_cap(ray, t, self.maximum.abs()) {
+            xs.push(Intersection::new(self, t));
+        }
+
+        return xs;
+    }
+
+    fn in_bounds(&self, ray: &Ray, t: f32) -> bool {
+        let y = ray.origin.y() + t * ray.direction.y();
+        return self.minimum < y && y < self.maximum;
+    }
+}
+
+impl Shape for Cone {
+    fn id(&self) -> &Uuid {
+        return &self.id;
     }
-}
\ No newline at end of file
+
+    fn clone_shape(&self) -> Option<Box<dyn Shape>> {
+        return Some(Box::new(Cone { id: id::new_id(), ..self.clone() }));
+    }
+
+    fn transform(&self) -> &Matrix4x4 {
+        return &self.transform;
+    }
+
+    fn set_transform(&mut self, matrix: Matrix4x4) {
+        self.transform = matrix;
+        self.inverse_transform = matrix.invert();
+    }
+
+    fn inverse_transform(&self) -> Matrix4x4 {
+        return self.inverse_transform;
+    }
+
+    fn material(&self) -> &Material {
+        return &self.material;
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        return &mut self.material;
+    }
+
+    fn enabled(&self) -> bool {
+        return self.enabled;
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let a = ray.direction.x().powi(2) - ray.direction.y().powi(2) + ray.direction.z().powi(2);
+        let b = 2.0 * ray.origin.x() * ray.direction.x() - 2.0 * ray.origin.y() * ray.direction.y() + 2.0 * ray.origin.z() * ray.direction.z();
+        let c = ray.origin.x().powi(2) - ray.origin.y().powi(2) + ray.origin.z().powi(2);
+
+        let mut xs: Vec<Intersection> = Vec::new();
+
+        // Ray parallel to one of the cone's halves only crosses the other half once
+        if util::equals_f32(&a, &0.0) {
+            if !util::equals_f32(&b, &0.0) {
+                let t = -c / (2.0 * b);
+                if self.in_bounds(ray, t) {
+                    xs.push(Intersection::new(self, t));
+                }
+            }
+
+            xs.append(&mut self.intersect_caps(*ray));
+            return xs;
+        }
+
+        let mut disc = b*b - 4.0 * a * c;
+
+        // Grazing rays can land a rounding error below zero
+        if disc < 0.0 && disc > -util::THRESHOLD_F32 * 100.0 {
+            disc = 0.0;
+        }
+
+        if disc < 0.0 {
+            return xs;
+        }
+
+        let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
+        let mut t1 = (-b + disc.sqrt()) / (2.0 * a);
+
+        if t0 > t1 {
+            swap(&mut t0, &mut t1);
+        }
+
+        if self.in_bounds(ray, t0) {
+            xs.push(Intersection::new(self, t0));
+        }
+
+        if self.in_bounds(ray, t1) {
+            xs.push(Intersection::new(self, t1));
+        }
+
+        xs.append(&mut self.intersect_caps(*ray));
+
+        return xs;
+    }
+
+    fn local_normal_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
+        let dist = local_point.x().powi(2) + local_point.z().powi(2);
+
+        if dist < self.maximum.powi(2) && *local_point.y() >= self.maximum - util::THRESHOLD_F32 {
+            return Vec4::vector(0.0, 1.0, 0.0);
+        } else if dist < self.minimum.powi(2) && *local_point.y() <= self.minimum + util::THRESHOLD_F32 {
+            return Vec4::vector(0.0, -1.0, 0.0);
+        }
+
+        let mut y = dist.sqrt();
+        if *local_point.y() > 0.0 {
+            y = -y;
+        }
+
+        return Vec4::vector(*local_point.x(), y, *local_point.z());
+    }
+
+    // Around the axis, which lies in the caps as well as the side
+    fn local_tangent_at(&self, local_point: &Vec4, _: Intersection) -> Vec4 {
+        return Vec4::vector(*local_point.z(), 0.0, -*local_point.x());
+    }
+
+    fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
+        let local_point = self.world_to_object(world_point);
+        let local_normal = self.local_normal_at(&local_point, i);
+        return self.object_to_world_normal(&local_normal);
+    }
+
+    // Over the side wall with y spread evenly between the bounds (so not uniform by area);
+    // unbounded cones are sampled between y = -1 and y = 1
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let minimum = if self.minimum.is_finite() { self.minimum } else { -1.0 };
+        let maximum = if self.maximum.is_finite() { self.maximum } else { 1.0 };
+        let phi = 2.0 * PI * u;
+        let y = minimum + (maximum - minimum) * v;
+
+        let local_point = Vec4::point(phi.cos() * y.abs(), y, phi.sin() * y.abs());
+        return self.transform * local_point;
+    }
+}
@@ -5,7 +5,11 @@ pub fn equals_f32(num1: &f32, num2: &f32) -> bool {
     return (num1 - num2).abs() < THRESHOLD_F32;
 }
 
+// NaN clamps to `low` and infinities to the nearest bound, so broken values can't leak through
 pub fn clamp_f32(num: f32, low: f32, high: f32) -> f32 {
+    if num.is_nan() {
+        return low;
+    }
     if num < low {
         return low;
     }
@@ -15,30 +19,41 @@ pub fn clamp_f32(num: f32, low: f32, high: f32) -> f32 {
     return num;
 }
 
-pub fn max_f32(arr: &Vec<f32>) -> Option<f32> {
-    if arr.len() <= 0 {
+// Largest of three values without allocating. NaN arguments are ignored like f32::max, so the
+// result is only NaN when all three are
+pub fn max3(a: f32, b: f32, c: f32) -> f32 {
+    return a.max(b).max(c);
+}
+
+// Smallest of three values, NaN arguments are ignored like f32::min
+pub fn min3(a: f32, b: f32, c: f32) -> f32 {
+    return a.min(b).min(c);
+}
+
+pub fn max_f32(arr: &[f32]) -> Option<f32> {
+    if arr.is_empty() {
         return None;
     }
 
     let mut max = arr[0];
-    for i in 1..arr.len() {
-        if arr[i] > max {
-            max = arr[i];
+    for &x in &arr[1..] {
+        if x > max {
+            max = x;
         }
     }
 
     return Some(max);
 }
 
-pub fn min_f32(arr: &Vec<f32>) -> Option<f32> {
-    if arr.len() <= 0 {
+pub fn min_f32(arr: &[f32]) -> Option<f32> {
+    if arr.is_empty() {
         return None;
     }
 
     let mut min = arr[0];
-    for i in 1..arr.len() {
-        if arr[i] < min {
-            min = arr[i];
+    for &x in &arr[1..] {
+        if x < min {
+            min = x;
         }
     }
 
@@ -1,5 +1,8 @@
 use minifb::{Key, Window, WindowOptions};
+use crate::camera::Camera;
 use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::world::World;
 
 pub struct View {
     pub canvas: Canvas,
@@ -10,8 +13,8 @@ impl View {
     pub fn new(canvas: Canvas) -> Self {
         let mut window = Window::new(
             "",
-            canvas.width as usize,
-            canvas.height as usize,
+            canvas.width,
+            canvas.height,
             WindowOptions::default(),
         ).unwrap_or_else(|e| {
             panic!("{}", e);
@@ -30,7 +33,26 @@ impl View {
 
         while self.window.is_open() && !self.window.is_key_down(Key::Escape) {
             self.window
-                .update_with_buffer(&self.canvas.buffer, self.canvas.width as usize, self.canvas.height as usize)
+                .update_with_buffer(&self.canvas.buffer, self.canvas.width, self.canvas.height)
+                .unwrap();
+        }
+    }
+
+    // Keeps refining the render with Camera::render_accumulate and shows every frame, so the image
+    // cleans up for as long as the window stays open. The camera should match the canvas size
+    pub fn run_progressive(&mut self, camera: &Camera, world: &World) {
+        let mut accumulated: Vec<Color> = Vec::new();
+        let mut frame = 0;
+
+        while self.window.is_open() && !self.window.is_key_down(Key::Escape) {
+            camera.render_accumulate(world, &mut accumulated, frame);
+            for (i, color) in accumulated.iter().enumerate() {
+                self.canvas.set_color(i % camera.width(), i / camera.width(), color);
+            }
+            frame += 1;
+
+            self.window
+                .update_with_buffer(&self.canvas.buffer, self.canvas.width, self.canvas.height)
                 .unwrap();
         }
     }
@@ -1,14 +1,102 @@
+use std::f32::consts::PI;
+
+use uuid::Uuid;
+
 use crate::color::Color;
+use crate::environment::Environment;
 use crate::geometry::{Matrix4x4, Vec4};
 use crate::intersection::{Comp, Intersection};
 use crate::material::Material;
-use crate::light::Light;
+use crate::light::{Light, SkyDome};
+use crate::photon::PhotonMap;
 use crate::ray::Ray;
 use crate::shape::{Shape, Sphere};
+use crate::util;
+
+// How far short of a point on a shape light its shadow rays stop, so the light's own surface at the
+// sample doesn't count as blocking it
+const LIGHT_SURFACE_MARGIN: f32 = 0.001;
+
+// What the first surface along a ray looks like before any lighting is applied
+pub struct SurfaceSample {
+    // Distance along the ray, equal to the world-space distance when the direction is normalized
+    pub depth: f32,
+    // World-space normal, facing back towards the ray
+    pub normal: Vec4,
+    pub albedo: Color,
+}
+
+// How many more times a ray may be reflected and refracted, each budget is spent independently
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounces {
+    pub reflections: u32,
+    pub refractions: u32,
+    // Rough reflections spread into several rays only this many times along a path, deeper ones
+    // reflect sharply so the ray count doesn't multiply at every bounce
+    pub glossy: u32,
+}
+
+impl Bounces {
+    pub fn new(reflections: u32, refractions: u32) -> Self {
+        return Self {
+            reflections,
+            refractions,
+            glossy: 1,
+        };
+    }
+
+    pub fn uniform(remaining: u32) -> Self {
+        return Bounces::new(remaining, remaining);
+    }
+}
+
+impl Default for Bounces {
+    fn default() -> Self {
+        return Bounces::uniform(5);
+    }
+}
 
 pub struct World {
     pub objects: Vec<Box<dyn Shape>>,
     pub lights: Vec<Light>,
+    // Reflection and refraction stop recursing once the accumulated attenuation falls below this
+    pub reflection_cutoff: f32,
+    // Budgets used by the camera when rendering this world
+    pub max_bounces: Bounces,
+    // Rays averaged for a reflection off a rough material
+    pub glossy_samples: usize,
+    // Seen by rays that escape the scene, black when unset
+    pub environment: Option<Environment>,
+    // Seen instead of the environment by refracted rays that escape, i.e. what shows through glass
+    // when there is nothing behind it. Falls back to the environment when unset
+    pub refraction_background: Option<Environment>,
+    pub photon_map: Option<PhotonMap>,
+    // Exponential distance fog, hits are blended towards fog_color by how far the ray travelled and
+    // rays that miss everything see the fog color. A density of 0 disables it
+    pub fog_color: Color,
+    pub fog_density: f32,
+    // Replaces each light's flat ambient term with one tinted by which way the surface faces
+    pub sky_dome: Option<SkyDome>,
+}
+
+// Depth-first walk over the leaf shapes of a world, see World::iter_shapes
+pub struct Shapes<'a> {
+    stack: Vec<&'a dyn Shape>,
+}
+
+impl<'a> Iterator for Shapes<'a> {
+    type Item = &'a dyn Shape;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(shape) = self.stack.pop() {
+            match shape.children() {
+                Some(children) => self.stack.extend(children.iter().rev().map(|child| child.as_ref())),
+                None => return Some(shape),
+            }
+        }
+
+        return None;
+    }
 }
 
 impl World {
@@ -16,13 +104,67 @@ impl World {
         return Self {
             objects: Vec::new(),
             lights: Vec::new(),
+            reflection_cutoff: 1.0 / 255.0,
+            max_bounces: Bounces::default(),
+            glossy_samples: 16,
+            environment: None,
+            refraction_background: None,
+            photon_map: None,
+            fog_color: Color::black(),
+            fog_density: 0.0,
+            sky_dome: None,
         };
     }
 
-    pub fn intersect_world(&self, ray: Ray) -> Vec<Intersection> {
+    // Two concentric spheres lit from the upper left, the standard scene from The Ray Tracer Challenge
+    pub fn demo() -> Self {
+        let mut world = World::new();
+        world.add_light(Light::point_light(Vec4::point(-10.0, 10.0, -10.0), Color::white()));
+
+        let material = Material::new(Color::new(0.8, 1.0, 0.6), 0.1, 0.7, 0.2, 200.0, 0.0, 0.0, 1.0, None);
+        let sphere1 = Sphere::new(material);
+
+        let material = Material::new(Color::new(0.8, 1.0, 0.6), 0.1, 0.7, 0.2, 200.0, 0.0, 0.0, 1.0, None);
+        let mut sphere2 = Sphere::new(material);
+        sphere2.set_transform(Matrix4x4::scale(0.5, 0.5, 0.5));
+
+        world.add_object(Box::new(sphere1));
+        world.add_object(Box::new(sphere2));
+
+        return world;
+    }
+
+    // Three overlapping glass spheres from The Ray Tracer Challenge: A of index 1.5 scaled by 2 at the
+    // origin, B of index 2.0 at z = -0.25 and C of index 2.5 at z = 0.25. A ray from (0, 0, -4) along +z
+    // hits A, B, C, B, C, A at t = 2, 2.75, 3.25, 4.75, 5.25, 6 and sees n1/n2 of
+    // 1.0/1.5, 1.5/2.0, 2.0/2.5, 2.5/2.5, 2.5/1.5, 1.5/1.0
+    pub fn glass_stack_scene() -> Self {
+        let mut world = World::new();
+        world.add_light(Light::point_light(Vec4::point(-10.0, 10.0, -10.0), Color::white()));
+
+        let mut a = Sphere::glass_sphere();
+        a.set_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+        a.material.refraction = 1.5;
+
+        let mut b = Sphere::glass_sphere();
+        b.set_transform(Matrix4x4::translation(0.0, 0.0, -0.25));
+        b.material.refraction = 2.0;
+
+        let mut c = Sphere::glass_sphere();
+        c.set_transform(Matrix4x4::translation(0.0, 0.0, 0.25));
+        c.material.refraction = 2.5;
+
+        world.add_object(Box::new(a));
+        world.add_object(Box::new(b));
+        world.add_object(Box::new(c));
+
+        return world;
+    }
+
+    pub fn intersect_world(&self, ray: Ray) -> Vec<Intersection<'_>> {
         let mut xs: Vec<Intersection> = Vec::new();
 
-        for shape in &self.objects {
+        for shape in self.objects.iter().filter(|shape| shape.enabled()) {
             let inter = Intersection::intersect(&**shape, ray);
             xs.extend(inter);
         }
@@ -32,6 +174,36 @@ impl World {
         return xs;
     }
 
+    // Every leaf shape in insertion order, descending into groups and models. Leaves are yielded as
+    // stored, so a model's triangles come with their model space coordinates
+    pub fn iter_shapes(&self) -> Shapes<'_> {
+        return Shapes {
+            stack: self.objects.iter().rev().map(|object| object.as_ref()).collect(),
+        };
+    }
+
+    // The shape with the id, searching inside groups and models as well as the top level objects,
+    // along with the transform that carries points placed by the shape's own transform into world
+    // space. It is the identity for top level objects and the children of top level groups, but
+    // moves the triangles of a model to where the model is drawn, see Shape::children_to_parent
+    pub fn find_shape(&self, id: &Uuid) -> Option<(&dyn Shape, Matrix4x4)> {
+        let mut stack: Vec<(&dyn Shape, Matrix4x4)> = self.objects
+            .iter()
+            .map(|object| (object.as_ref(), Matrix4x4::identity()))
+            .collect();
+        while let Some((shape, to_world)) = stack.pop() {
+            if shape.id() == id {
+                return Some((shape, to_world));
+            }
+            if let Some(children) = shape.children() {
+                let children_to_world = to_world * shape.children_to_parent();
+                stack.extend(children.iter().map(|child| (child.as_ref(), children_to_world)));
+            }
+        }
+
+        return None;
+    }
+
     pub fn add_object(&mut self, shape: Box<dyn Shape>) {
         self.objects.push(shape);
     }
@@ -40,92 +212,323 @@ impl World {
         self.lights.push(light);
     }
 
+    pub fn add_lights(&mut self, lights: Vec<Light>) {
+        self.lights.extend(lights);
+    }
+
+    // Combined color of every light, a rough measure of how brightly the scene is lit
+    pub fn total_lights_intensity(&self) -> Color {
+        return self.lights.iter().fold(Color::black(), |total, light| total + light.intensity);
+    }
+
+    // Shoots photons from every light so glass and mirrors focus light onto diffuse surfaces.
+    // Needs to be rebuilt whenever objects or lights change.
+    pub fn build_caustics(&mut self, photons_per_light: usize, gather_radius: f32) {
+        let photon_map = PhotonMap::build(self, photons_per_light, gather_radius);
+        self.photon_map = Some(photon_map);
+    }
+
+    // True when any light is blocked from the point, see is_shadowed_from
     pub fn is_shadowed(&self, point: &Vec4) -> bool {
-        for light in &self.lights {
-            let v = light.position - *point;
-            let distance = v.magnitude();
-            let direction = v.normalize();
+        return self.lights.iter().any(|light| self.is_shadowed_from(point, light));
+    }
 
-            let ray = Ray::new(*point, direction);
-            let mut inter = self.intersect_world(ray);
+    // Casts a ray from the point towards the light and reports whether something lies between them.
+    // Occluders beyond the light don't count. The light doesn't have to be one of the world's lights.
+    // A shape light only shadows the point when every sample on its surface is hidden
+    pub fn is_shadowed_from(&self, point: &Vec4, light: &Light) -> bool {
+        if self.is_shape_light(light) {
+            return self.intensity_at(point, light) == 0.0;
+        }
 
-            if let Some(hit) = Intersection::hit(&mut inter) {
-                if hit.t < distance {
-                    return true;
-                }
-            }
+        return self.is_occluded(point, &light.position, 0.0);
+    }
+
+    // Share of the light that reaches the point, between 0 in full shadow and 1 fully lit. Point
+    // lights are all or nothing, spherical and shape lights are partly hidden inside the penumbra
+    pub fn intensity_at(&self, point: &Vec4, light: &Light) -> f32 {
+        let (samples, margin) = self.light_samples(light, point);
+        let visible = samples.iter().filter(|sample| !self.is_occluded(point, sample, margin)).count();
+        return visible as f32 / samples.len() as f32;
+    }
+
+    fn is_shape_light(&self, light: &Light) -> bool {
+        return light.shape.is_some_and(|id| self.find_shape(&id).is_some());
+    }
+
+    // Points on the light that shadow rays from the point are traced towards, and how far short of
+    // them the rays stop. Samples on a shape light lie on its surface, which must not hide them itself
+    fn light_samples(&self, light: &Light, point: &Vec4) -> (Vec<Vec4>, f32) {
+        if self.is_shape_light(light) {
+            return (light.sample_points(self, light.samples), LIGHT_SURFACE_MARGIN);
         }
 
-        return false;
+        return (light.sample_points_toward(point), 0.0);
+    }
+
+    fn is_occluded(&self, point: &Vec4, target: &Vec4, margin: f32) -> bool {
+        let v = *target - *point;
+        let distance = v.magnitude();
+        let direction = v.normalize();
+
+        // direction is unit length, so hit t values are world distances however the occluder is scaled
+        let ray = Ray::new(*point, direction);
+        return self.any_occluder(ray, distance - margin);
+    }
+
+    // Whether any enabled shape is hit in front of the ray closer than max_t. Stops at the first such
+    // shape without gathering or sorting the rest, which is all a shadow ray needs to know
+    pub fn any_occluder(&self, ray: Ray, max_t: f32) -> bool {
+        return self
+            .objects
+            .iter()
+            .filter(|shape| shape.enabled())
+            .any(|shape| Intersection::intersect(&**shape, ray).iter().any(|x| x.t > 0.0 && x.t < max_t));
+    }
+
+    pub fn first_hit(&self, ray: Ray) -> Option<SurfaceSample> {
+        let mut intersection = self.intersect_world(ray);
+        let hit = Intersection::hit(&mut intersection)?;
+        let comp = hit.prepare_computations(&ray, None);
+
+        return Some(SurfaceSample {
+            depth: comp.t,
+            normal: comp.normalv,
+            albedo: comp.surface_color(),
+        });
     }
 
+    // Shorthand for color_at_bounces with the same budget for reflections and refractions
     pub fn color_at(&self, ray: Ray, remaining: u32) -> Color {
+        return self.color_at_bounces(ray, Bounces::uniform(remaining));
+    }
+
+    pub fn color_at_bounces(&self, ray: Ray, bounces: Bounces) -> Color {
+        return self.color_at_throughput(ray, bounces, 1.0, None);
+    }
+
+    // `escape` is seen by the ray if it misses everything, the world's environment when None
+    fn color_at_throughput(&self, ray: Ray, bounces: Bounces, throughput: f32, escape: Option<&Environment>) -> Color {
         let mut intersection = self.intersect_world(ray);
         let xs = intersection.clone();
         
         if let Some(hit) = Intersection::hit(&mut intersection) {
             let comp = hit.prepare_computations(&ray, Some(&xs));
-            return self.shade_hit(&comp, remaining);
+            let color = self.shade_hit_throughput(&comp, bounces, throughput);
+            return self.apply_fog(color, comp.t * ray.direction.magnitude());
+        }
+
+        if self.fog_density > 0.0 {
+            return self.fog_color;
+        }
+
+        if let Some(environment) = escape.or(self.environment.as_ref()) {
+            return environment.color_at(&ray.direction);
         }
 
-        return Color::new(0.0, 0.0, 0.0);
+        return Color::black();
+    }
+
+    // Fraction of the surface color left after travelling `distance` through the fog is e^(-density * distance)
+    fn apply_fog(&self, color: Color, distance: f32) -> Color {
+        if self.fog_density <= 0.0 {
+            return color;
+        }
+
+        let transmittance = (-self.fog_density * distance).exp();
+        return color * transmittance + self.fog_color * (1.0 - transmittance);
     }
 
     pub fn reflected_color(&self, comp: &Comp, remaining: u32) -> Color {
-        if comp.object.material().reflective == 0.0 || remaining == 0 {
-            return Color::new(0.0, 0.0, 0.0);
+        return self.reflected_color_throughput(comp, Bounces::uniform(remaining), 1.0);
+    }
+
+    fn reflected_color_throughput(&self, comp: &Comp, bounces: Bounces, throughput: f32) -> Color {
+        if comp.object.material().reflective == 0.0 || bounces.reflections == 0 {
+            return Color::black();
+        }
+
+        let throughput = throughput * comp.object.material().reflective;
+        if throughput < self.reflection_cutoff {
+            return Color::black();
         }
 
-        let reflect_ray = Ray::new(comp.over_point, comp.reflectv);
-        let color = self.color_at(reflect_ray, remaining - 1);
+        let material = comp.object.material();
+        let next = Bounces { reflections: bounces.reflections - 1, ..bounces };
 
-        return color * comp.object.material().reflective;
+        if material.roughness <= 0.0 || bounces.glossy == 0 || self.glossy_samples <= 1 {
+            let reflect_ray = Ray::new(comp.over_point, comp.reflectv);
+            let color = self.color_at_throughput(reflect_ray, next, throughput, None);
+
+            return color * material.reflective;
+        }
+
+        let next = Bounces { glossy: next.glossy - 1, ..next };
+        let half_angle = util::clamp_f32(material.roughness, 0.0, 1.0) * PI / 2.0;
+        let directions = World::glossy_directions(&comp.reflectv, &comp.normalv, &comp.point, half_angle, self.glossy_samples);
+
+        let mut color = Color::black();
+        for direction in &directions {
+            let reflect_ray = Ray::new(comp.over_point, *direction);
+            color = color + self.color_at_throughput(reflect_ray, next, throughput, None);
+        }
+
+        return color * (material.reflective / directions.len() as f32);
+    }
+
+    // Directions spread evenly over a cone around `axis` along a Fibonacci spiral. The spiral is turned
+    // by a different amount at every point, so neighbouring pixels sample different directions and the
+    // pattern shows up as noise instead of banding. Directions that end up on the wrong side of the
+    // surface, against `side`, are mirrored back.
+    fn glossy_directions(axis: &Vec4, side: &Vec4, point: &Vec4, half_angle: f32, count: usize) -> Vec<Vec4> {
+        let p = *point;
+        let hash = (p.x() * 12.9898 + p.y() * 78.233 + p.z() * 37.719).sin() * 43758.547;
+        let rotation = (hash - hash.floor()) * 2.0 * PI;
+
+        let golden_angle = PI * (3.0 - 5.0_f32.sqrt());
+        let cos_max = half_angle.cos();
+        let mut directions: Vec<Vec4> = Vec::with_capacity(count);
+
+        for i in 0..count {
+            // Evenly spaced heights, turned by the golden angle each step so the directions spiral out
+            let height = (i as f32 + 0.5) / count as f32;
+            let turn = (golden_angle * i as f32 + rotation) / (2.0 * PI);
+
+            let mut direction = axis.cone_sample(cos_max, height, turn);
+            let below = direction.dot(side);
+            if below < 0.0 {
+                direction = direction - *side * (2.0 * below);
+            }
+
+            directions.push(direction);
+        }
+
+        return directions;
     }
 
     pub fn refracted_color(&self, comp: &Comp, remaining: u32) -> Color {
-        if comp.object.material().transparency == 0.0 || remaining == 0 {
-            return Color::new(0.0, 0.0, 0.0);
+        return self.refracted_color_throughput(comp, Bounces::uniform(remaining), 1.0);
+    }
+
+    fn refracted_color_throughput(&self, comp: &Comp, bounces: Bounces, throughput: f32) -> Color {
+        if comp.object.material().transparency == 0.0 || bounces.refractions == 0 {
+            return Color::black();
+        }
+
+        let throughput = throughput * comp.object.material().transparency;
+        if throughput < self.reflection_cutoff {
+            return Color::black();
+        }
+
+        let material = comp.object.material();
+        if material.dispersion <= 0.0 {
+            return match comp.refracted_direction() {
+                Some(direction) => self.refracted_along(comp, direction, bounces, throughput),
+                // Total Internal Reflection
+                None => Color::black(),
+            };
         }
 
-        let n_ratio = comp.n1 / comp.n2;
-        let cos_i = comp.eyev.dot(&comp.normalv);
-        let sin2_t = n_ratio*n_ratio * (1.0 - cos_i*cos_i);
+        // Each channel is traced along its own direction and only that channel of the result is kept.
+        // The surface belongs to the material being left when inside it and to the one entered otherwise
+        let channel = |offset: f32| {
+            let (n1, n2) = if comp.inside { (comp.n1 + offset, comp.n2) } else { (comp.n1, comp.n2 + offset) };
+            return match comp.refracted_direction_between(n1, n2) {
+                Some(direction) => self.refracted_along(comp, direction, bounces, throughput),
+                None => Color::black(),
+            };
+        };
+
+        let red = channel(-material.dispersion);
+        let green = channel(0.0);
+        let blue = channel(material.dispersion);
+
+        return Color::new(*red.r(), *green.g(), *blue.b());
+    }
+
+    fn refracted_along(&self, comp: &Comp, direction: Vec4, bounces: Bounces, throughput: f32) -> Color {
+        let material = comp.object.material();
+        let next = Bounces { refractions: bounces.refractions - 1, ..bounces };
+
+        if material.roughness <= 0.0 || bounces.glossy == 0 || self.glossy_samples <= 1 {
+            let refract_ray = Ray::new(comp.under_point, direction);
+            let color = self.color_at_throughput(refract_ray, next, throughput, self.refraction_background.as_ref()) * material.transparency;
 
-        // Total Internal Reflection
-        if sin2_t > 1.0 {
-            return Color::new(0.0, 0.0, 0.0);
+            return color;
         }
 
-        let cos_t = f32::sqrt(1.0 - sin2_t);
-        let direction = comp.normalv * (n_ratio * cos_i - cos_t) - comp.eyev * n_ratio;
-        let refract_ray = Ray::new(comp.under_point, direction);
-        let color = self.color_at(refract_ray, remaining - 1) * comp.object.material().transparency;
+        // Frosted glass: the same cone as a rough reflection, kept on the far side of the surface
+        let next = Bounces { glossy: next.glossy - 1, ..next };
+        let half_angle = util::clamp_f32(material.roughness, 0.0, 1.0) * PI / 2.0;
+        let directions = World::glossy_directions(&direction, &-comp.normalv, &comp.point, half_angle, self.glossy_samples);
+
+        let mut color = Color::black();
+        for direction in &directions {
+            let refract_ray = Ray::new(comp.under_point, *direction);
+            color = color + self.color_at_throughput(refract_ray, next, throughput, self.refraction_background.as_ref());
+        }
 
-        return color;
+        return color * (material.transparency / directions.len() as f32);
+    }
+
+    // Ambient term and the shadowed diffuse and specular light from one light. Each light is shadowed
+    // on its own, partially inside a spherical light's penumbra. Shape lights shine from every sample on
+    // their surface, each lighting the point from its own direction as long as it can see the point
+    fn light_contribution(&self, comp: &Comp, light: &Light, surface_color: Color, receives_shadows: bool) -> (Color, Color) {
+        let material = comp.object.material();
+
+        if !self.is_shape_light(light) {
+            let visible = if receives_shadows { self.intensity_at(&comp.over_point, light) } else { 1.0 };
+            let (ambient, diffuse, specular) = material.lighting_components(surface_color, light, &comp.over_point, &comp.eyev, &comp.normalv, false);
+            return (ambient, (diffuse + specular) * visible);
+        }
+
+        let (samples, margin) = self.light_samples(light, &comp.over_point);
+        let mut ambient = Color::black();
+        let mut direct = Color::black();
+        for sample in &samples {
+            let in_shadow = receives_shadows && self.is_occluded(&comp.over_point, sample, margin);
+            let (sample_ambient, diffuse, specular) = material.lighting_components_from(surface_color, light, sample, &comp.over_point, &comp.eyev, &comp.normalv, in_shadow);
+            ambient = sample_ambient;
+            direct = direct + diffuse + specular;
+        }
+
+        return (ambient, direct * (1.0 / samples.len() as f32));
     }
 
     pub fn shade_hit(&self, comp: &Comp, remaining: u32) -> Color {
-        let shadowed: bool;
+        return self.shade_hit_throughput(comp, Bounces::uniform(remaining), 1.0);
+    }
 
-        if comp.object.material().transparency >= 1.0 {
-            shadowed = false;
-        } else {
-            shadowed = self.is_shadowed(&comp.over_point);
+    fn shade_hit_throughput(&self, comp: &Comp, bounces: Bounces, throughput: f32) -> Color {
+        if let Some(wireframe) = comp.object.material().wireframe {
+            if comp.object.has_barycentric_hits() && wireframe.is_edge(comp.u, comp.v) {
+                return wireframe.color;
+            }
         }
 
-        let mut color = Color::new(0.0, 0.0, 0.0);
+        let receives_shadows = comp.object.material().transparency < 1.0;
+
+        let mut color = comp.object.material().emission_at(comp.object, &comp.over_point);
+        let surface_color = comp.surface_color();
+
+        if let Some(sky_dome) = &self.sky_dome {
+            color = color + surface_color * sky_dome.color_at(&comp.normalv) * comp.object.material().ambient;
+        }
 
         for light in &self.lights {
-            let c = comp
-                .object
-                .material()
-                .lighting(comp.object, light, &comp.over_point, &comp.eyev, &comp.normalv, shadowed);
+            let (ambient, direct) = self.light_contribution(comp, light, surface_color, receives_shadows);
+            let ambient = if self.sky_dome.is_some() { Color::black() } else { ambient };
+            color = color + ambient + direct;
+        }
 
-            color = color + c;
+        if let Some(photon_map) = &self.photon_map {
+            let caustic = photon_map.irradiance_at(&comp.over_point, &comp.normalv);
+            color = color + caustic * surface_color * comp.object.material().diffuse;
         }
 
-        let reflected = self.reflected_color(comp, remaining);
-        let refracted = self.refracted_color(comp, remaining);
+        let reflected = self.reflected_color_throughput(comp, bounces, throughput);
+        let refracted = self.refracted_color_throughput(comp, bounces, throughput);
 
         let material = comp.object.material();
         if material.reflective > 0.0 && material.transparency > 0.0 {
@@ -139,25 +542,6 @@ impl World {
 
 impl Default for World {
     fn default() -> Self {
-        let mut objects: Vec<Box<dyn Shape>> = Vec::new();
-        let mut lights: Vec<Light> = Vec::new();
-
-        let light = Light::point_light(Vec4::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        lights.push(light);
-
-        let material = Material::new(Color::new(0.8, 1.0, 0.6), 0.1, 0.7, 0.2, 200.0, 0.0, 0.0, 1.0, None);
-        let sphere1 = Sphere::new(material);
-
-        let material = Material::new(Color::new(0.8, 1.0, 0.6), 0.1, 0.7, 0.2, 200.0, 0.0, 0.0, 1.0, None);
-        let mut sphere2 = Sphere::new(material);
-        sphere2.set_transform(Matrix4x4::scale(0.5, 0.5, 0.5));
-
-        objects.push(Box::new(sphere1));
-        objects.push(Box::new(sphere2));
-
-        return Self {
-            objects,
-            lights,
-        };
+        return World::new();
     }
 }
\ No newline at end of file
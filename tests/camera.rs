@@ -0,0 +1,296 @@
+use std::f32::consts::PI;
+
+use tracer::camera::Camera;
+use tracer::color::Color;
+use tracer::environment::Environment;
+use tracer::geometry::{Handedness, Matrix4x4, Vec4};
+use tracer::intersection::Intersection;
+use tracer::material::Material;
+use tracer::ray::Ray;
+use tracer::shape::{Plane, Shape, Sphere};
+use tracer::util;
+use tracer::world::World;
+
+#[test]
+fn pixel_size_for_horizontal_canvas() {
+    let camera = Camera::new(200.0, 125.0, PI / 2.0);
+
+    assert!(util::equals_f32(&camera.pixel_size, &0.01));
+}
+
+#[test]
+fn pixel_size_for_vertical_canvas() {
+    let camera = Camera::new(125.0, 200.0, PI / 2.0);
+
+    assert!(util::equals_f32(&camera.pixel_size, &0.01));
+}
+
+#[test]
+fn with_aspect_derives_height() {
+    let camera = Camera::with_aspect(320.0, 16.0 / 9.0, PI / 3.0);
+
+    assert_eq!(camera.hsize, 320.0);
+    assert_eq!(camera.vsize, 180.0);
+}
+
+#[test]
+fn from_horizontal_gives_whole_pixel_sizes() {
+    let camera = Camera::from_horizontal(1920.0, PI / 3.0, 16.0 / 9.0);
+    assert_eq!((camera.width(), camera.height()), (1920, 1080));
+
+    // 854 / (16 / 9) is 480.375, which rounds down
+    let camera = Camera::from_horizontal(854.0, PI / 3.0, 16.0 / 9.0);
+    assert_eq!((camera.width(), camera.height()), (854, 480));
+    assert_eq!(camera.field_of_view, PI / 3.0);
+}
+
+#[test]
+fn fractional_sizes_round_to_the_nearest_pixel() {
+    // Truncating would give a 10x5 canvas while the pixel size was worked out for 10.6 pixels
+    let camera = Camera::new(10.6, 5.4, PI / 2.0);
+    assert_eq!((camera.hsize, camera.vsize), (11.0, 5.0));
+    assert!(util::equals_f32(&camera.pixel_size, &(2.0 / 11.0)));
+
+    let image = camera.render(&World::new());
+    assert_eq!((image.width, image.height), (11, 5));
+    assert_eq!(camera.render_to_colors(&World::new()).len(), 55);
+}
+
+#[test]
+#[should_panic(expected = "Camera size must be at least 1x1 pixels")]
+fn zero_width_panics() {
+    Camera::new(0.0, 100.0, PI / 2.0);
+}
+
+#[test]
+#[should_panic(expected = "Camera size must be at least 1x1 pixels")]
+fn negative_height_panics() {
+    Camera::new(100.0, -5.0, PI / 2.0);
+}
+
+#[test]
+#[should_panic(expected = "Camera field of view must be between 0 and PI radians")]
+fn flat_field_of_view_panics() {
+    Camera::new(100.0, 100.0, 0.0);
+}
+
+#[test]
+#[should_panic(expected = "Camera aspect ratio must be positive")]
+fn zero_aspect_panics() {
+    Camera::with_aspect(100.0, 0.0, PI / 2.0);
+}
+
+#[test]
+fn camera_rays_carry_pixel_differentials() {
+    let camera = Camera::new(201.0, 101.0, PI / 2.0);
+
+    let ray = camera.ray_for_pixel(100.0, 50.0);
+    let differential = ray.differential.unwrap();
+
+    assert_eq!(differential.x_direction, camera.ray_for_pixel(101.0, 50.0).direction);
+    assert_eq!(differential.y_direction, camera.ray_for_pixel(100.0, 51.0).direction);
+}
+
+#[test]
+fn footprint_grows_with_distance() {
+    let camera = Camera::new(201.0, 101.0, PI / 2.0);
+    let ray = camera.ray_for_pixel(100.0, 50.0);
+
+    let footprint_at = |distance: f32| -> (Vec4, Vec4) {
+        let mut plane = Plane::new(Material::default());
+        plane.set_transform(Matrix4x4::translation(0.0, 0.0, -distance) * Matrix4x4::rotatation_x(PI / 2.0));
+        let xs = Intersection::intersect(&plane, ray);
+        let comp = xs[0].prepare_computations(&ray, Some(&xs));
+        return (comp.dpdx, comp.dpdy);
+    };
+
+    let (near_x, near_y) = footprint_at(1.0);
+    let (far_x, _) = footprint_at(10.0);
+
+    assert!((near_x.magnitude() - camera.pixel_size).abs() < 0.0001);
+    assert!((near_y.magnitude() - camera.pixel_size).abs() < 0.0001);
+    assert!((far_x.magnitude() - camera.pixel_size * 10.0).abs() < 0.001);
+}
+
+#[test]
+fn secondary_rays_have_no_footprint() {
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let sphere = Sphere::new(Material::default());
+
+    let xs = Intersection::intersect(&sphere, ray);
+    let comp = xs[0].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(comp.dpdx, Vec4::ZERO);
+    assert_eq!(comp.dpdy, Vec4::ZERO);
+}
+
+#[test]
+fn view_transform_updates_cached_inverse() {
+    let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+    assert!(camera.inverse_transform().is_identity());
+
+    camera.set_view_transform(Vec4::point(1.0, 3.0, 2.0), Vec4::point(4.0, -2.0, 8.0), Vec4::vector(1.0, 1.0, 0.0));
+    assert!((*camera.transform() * *camera.inverse_transform()).is_identity());
+
+    let ray = camera.ray_for_pixel(5.0, 5.0);
+    assert_eq!(ray.origin, Vec4::point(1.0, 3.0, 2.0));
+
+    camera.set_transform(Matrix4x4::translation(0.0, -2.0, 5.0));
+    assert_eq!(*camera.inverse_transform(), Matrix4x4::translation(0.0, 2.0, -5.0));
+    assert_eq!(camera.ray_for_pixel(5.0, 5.0).origin, Vec4::point(0.0, 2.0, -5.0));
+}
+
+#[test]
+fn stereo_eyes_are_offset_along_the_camera_axis() {
+    let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+    // Looking down +x, so the camera's left is +z in world space
+    camera.set_view_transform(Vec4::point(0.0, 1.0, 0.0), Vec4::point(10.0, 1.0, 0.0), Vec4::vector(0.0, 1.0, 0.0));
+
+    let (left, right) = camera.stereo_pair(0.064);
+
+    assert_eq!(left.ray_for_pixel(5.0, 5.0).origin, Vec4::point(0.0, 1.0, 0.032));
+    assert_eq!(right.ray_for_pixel(5.0, 5.0).origin, Vec4::point(0.0, 1.0, -0.032));
+
+    // The eyes look the same way rather than converging
+    assert_eq!(left.ray_for_pixel(5.0, 5.0).direction, camera.ray_for_pixel(5.0, 5.0).direction);
+    assert_eq!(right.ray_for_pixel(5.0, 5.0).direction, camera.ray_for_pixel(5.0, 5.0).direction);
+}
+
+#[test]
+fn left_eye_is_on_the_image_left() {
+    let camera = Camera::new(101.0, 101.0, PI / 2.0);
+    let (left, _) = camera.stereo_pair(1.0);
+
+    // The leftmost column of the image looks towards the left eye's side
+    let leftmost = camera.ray_for_pixel(0.0, 50.0).direction;
+    let offset = left.ray_for_pixel(50.0, 50.0).origin - camera.ray_for_pixel(50.0, 50.0).origin;
+    assert!(leftmost.dot(&offset) > 0.0);
+}
+
+#[test]
+fn dithered_render_breaks_up_a_flat_level() {
+    let mut world = World::new();
+    world.environment = Some(Environment::Solid(Color::new(0.0, 0.0, 60.5 / 255.0)));
+
+    let mut camera = Camera::new(9.0, 9.0, PI / 2.0);
+    let blues = |camera: &Camera| -> Vec<u32> {
+        let image = camera.render(&world);
+        let mut levels = Vec::new();
+        for y in 0..8 {
+            for x in 0..8 {
+                levels.push(*image.color_at(x, y) & 0xff);
+            }
+        }
+        levels
+    };
+
+    assert!(blues(&camera).iter().all(|level| *level == 60));
+
+    camera.dither = Some(3);
+    let levels = blues(&camera);
+    assert_eq!(levels.iter().filter(|level| **level == 61).count(), 32);
+    assert_eq!(blues(&camera), levels);
+
+    // Both stereo eyes keep the setting
+    let (left, right) = camera.stereo_pair(0.064);
+    assert_eq!((left.dither, right.dither), (Some(3), Some(3)));
+}
+
+#[test]
+fn orbit_at_zero_matches_looking_down_z() {
+    let target = Vec4::point(1.0, 2.0, 3.0);
+    let mut orbit = Camera::new(11.0, 11.0, PI / 2.0);
+    orbit.orbit(target, 5.0, 0.0, 0.0);
+
+    let mut expected = Camera::new(11.0, 11.0, PI / 2.0);
+    expected.set_view_transform(Vec4::point(1.0, 2.0, -2.0), target, Vec4::vector(0.0, 1.0, 0.0));
+
+    assert!(orbit.transform().approx_eq(expected.transform(), 0.0001));
+}
+
+#[test]
+fn orbit_turns_around_the_target() {
+    let target = Vec4::point(0.0, 1.0, 0.0);
+    let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+
+    let positions = [
+        (PI / 2.0, 0.0, Vec4::point(4.0, 1.0, 0.0)),
+        (PI, 0.0, Vec4::point(0.0, 1.0, 4.0)),
+        (0.0, PI / 6.0, Vec4::point(0.0, 3.0, -(12.0_f32).sqrt())),
+    ];
+
+    for (azimuth, elevation, expected) in positions {
+        camera.orbit(target, 4.0, azimuth, elevation);
+        let ray = camera.ray_for_pixel(5.0, 5.0);
+
+        assert_eq!(ray.origin, expected);
+        // The centre of the image always looks at the target
+        assert_eq!(ray.direction, (target - expected).normalize());
+    }
+}
+
+#[test]
+fn orbit_straight_down_keeps_a_valid_up_vector() {
+    let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+    camera.orbit(Vec4::ORIGIN, 3.0, 0.0, PI / 2.0);
+
+    let ray = camera.ray_for_pixel(5.0, 5.0);
+    assert_eq!(ray.origin, Vec4::point(0.0, 3.0, 0.0));
+    assert_eq!(ray.direction, Vec4::vector(0.0, -1.0, 0.0));
+
+    // Continuing the turntable from above, the top of the image points away from the start side
+    let top = camera.ray_for_pixel(5.0, 0.0).direction;
+    assert!(*top.z() > 0.0 && top.x().abs() < 0.0001);
+}
+
+// Directions of the rays through the middle of the right and top edges of an 11x11 image
+fn edge_directions(camera: &Camera) -> (Vec4, Vec4) {
+    return (camera.ray_for_pixel(10.0, 5.0).direction, camera.ray_for_pixel(5.0, 0.0).direction);
+}
+
+#[test]
+fn default_camera_is_left_handed() {
+    let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+
+    // Looking towards +z with y up, +x is on the right
+    camera.set_view_transform(Vec4::point(0.0, 0.0, -5.0), Vec4::ORIGIN, Vec4::vector(0.0, 1.0, 0.0));
+    let (right, top) = edge_directions(&camera);
+    assert!(*right.x() > 0.0);
+    assert!(*top.y() > 0.0);
+
+    // Looking towards +x, +z is on the left
+    camera.set_view_transform(Vec4::point(-5.0, 0.0, 0.0), Vec4::ORIGIN, Vec4::vector(0.0, 1.0, 0.0));
+    let (right, _) = edge_directions(&camera);
+    assert!(*right.z() < 0.0);
+
+    // Looking straight down with +z at the top of the image, +x is on the right
+    camera.set_view_transform(Vec4::point(0.0, 5.0, 0.0), Vec4::ORIGIN, Vec4::vector(0.0, 0.0, 1.0));
+    let (right, top) = edge_directions(&camera);
+    assert!(*right.x() > 0.0);
+    assert!(*top.z() > 0.0);
+}
+
+#[test]
+fn right_handed_camera_mirrors_the_x_axis() {
+    let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+
+    // Looking towards -z with y up, +x is on the right like OpenGL, and on the left by default
+    let (from, up) = (Vec4::point(0.0, 0.0, 5.0), Vec4::vector(0.0, 1.0, 0.0));
+    camera.set_view_transform_handed(from, Vec4::ORIGIN, up, Handedness::Right);
+    let (right, top) = edge_directions(&camera);
+    assert!(*right.x() > 0.0);
+    assert!(*top.y() > 0.0);
+
+    camera.set_view_transform(from, Vec4::ORIGIN, up);
+    assert!(*edge_directions(&camera).0.x() < 0.0);
+
+    // Looking towards +x, +z is on the right
+    camera.set_view_transform_handed(Vec4::point(-5.0, 0.0, 0.0), Vec4::ORIGIN, up, Handedness::Right);
+    assert!(*edge_directions(&camera).0.z() > 0.0);
+
+    // Only the camera's x axis flips, the centre ray and the vertical are unchanged
+    let left = Matrix4x4::view_transformation(from, Vec4::point(1.0, 2.0, 0.0), up);
+    let right = Matrix4x4::view_transformation_handed(from, Vec4::point(1.0, 2.0, 0.0), up, Handedness::Right);
+    assert_eq!(right, Matrix4x4::scale(-1.0, 1.0, 1.0) * left);
+    assert_eq!(Matrix4x4::view_transformation_handed(from, Vec4::ORIGIN, up, Handedness::default()), Matrix4x4::view_transformation(from, Vec4::ORIGIN, up));
+}
@@ -0,0 +1,373 @@
+use tracer::canvas::Canvas;
+use tracer::color::Color;
+
+#[test]
+fn anaglyph_takes_red_from_left_and_cyan_from_right() {
+    let mut left = Canvas::new(2, 1);
+    let mut right = Canvas::new(2, 1);
+
+    left.set_color(0, 0, &Color::new(1.0, 0.5, 0.25));
+    right.set_color(0, 0, &Color::new(0.25, 0.75, 1.0));
+    left.set_color(1, 0, &Color::white());
+
+    let image = Canvas::anaglyph(&left, &right).unwrap();
+
+    assert_eq!(*image.color_at(0, 0), Color::new(1.0, 0.75, 1.0).rgb());
+    assert_eq!(*image.color_at(1, 0), Color::new(1.0, 0.0, 0.0).rgb());
+}
+
+#[test]
+fn anaglyph_rejects_mismatched_sizes() {
+    let left = Canvas::new(4, 3);
+    let right = Canvas::new(3, 4);
+
+    let error = Canvas::anaglyph(&left, &right).err().unwrap();
+    assert!(error.contains("4x3") && error.contains("3x4"));
+}
+
+fn filled(width: usize, height: usize, color: Color) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    for x in 0..width {
+        for y in 0..height {
+            canvas.set_color(x, y, &color);
+        }
+    }
+    return canvas;
+}
+
+#[test]
+fn blit_copies_at_an_offset() {
+    let mut canvas = Canvas::new(4, 3);
+    canvas.blit(&filled(2, 2, Color::RED), 1, 1);
+
+    let red = Color::RED.rgb();
+    let expected = [
+        0, 0, 0, 0,
+        0, red, red, 0,
+        0, red, red, 0,
+    ];
+    assert_eq!(canvas.buffer, expected);
+}
+
+#[test]
+fn blit_clips_to_the_destination() {
+    let mut canvas = Canvas::new(3, 3);
+    canvas.blit(&filled(4, 4, Color::BLUE), 2, 1);
+
+    let blue = Color::BLUE.rgb();
+    let expected = [
+        0, 0, 0,
+        0, 0, blue,
+        0, 0, blue,
+    ];
+    assert_eq!(canvas.buffer, expected);
+
+    // Entirely outside does nothing
+    let mut canvas = Canvas::new(3, 3);
+    canvas.blit(&filled(2, 2, Color::BLUE), 3, 0);
+    canvas.blit(&filled(2, 2, Color::BLUE), 0, 3);
+    assert!(canvas.buffer.iter().all(|pixel| *pixel == 0));
+}
+
+#[test]
+fn blit_builds_a_side_by_side_montage() {
+    let left = filled(2, 1, Color::RED);
+    let right = filled(2, 1, Color::GREEN);
+
+    let mut montage = Canvas::new(4, 1);
+    montage.blit(&left, 0, 0);
+    montage.blit(&right, 2, 0);
+
+    assert_eq!(*montage.color_at(1, 0), Color::RED.rgb());
+    assert_eq!(*montage.color_at(2, 0), Color::GREEN.rgb());
+}
+
+fn green_levels(canvas: &Canvas) -> Vec<u32> {
+    return canvas.buffer.iter().map(|pixel| (pixel >> 8) & 0xff).collect();
+}
+
+#[test]
+fn dither_spreads_a_level_between_steps_over_the_block() {
+    let color = Color::new(0.0, 100.5 / 255.0, 0.0);
+
+    let mut plain = Canvas::new(8, 8);
+    let mut dithered = Canvas::with_dither(8, 8, 0);
+    for x in 0..8 {
+        for y in 0..8 {
+            plain.set_color(x, y, &color);
+            dithered.set_color(x, y, &color);
+        }
+    }
+
+    assert!(green_levels(&plain).iter().all(|level| *level == 100));
+
+    let levels = green_levels(&dithered);
+    assert_eq!(levels.iter().filter(|level| **level == 100).count(), 32);
+    assert_eq!(levels.iter().filter(|level| **level == 101).count(), 32);
+}
+
+#[test]
+fn dither_is_reproducible_for_a_seed() {
+    let render = |seed: u64| {
+        let mut canvas = Canvas::with_dither(16, 16, seed);
+        for x in 0..16 {
+            for y in 0..16 {
+                canvas.set_color(x, y, &Color::new(x as f32 / 255.0 * 0.3, 0.5, y as f32 / 255.0 * 0.7));
+            }
+        }
+        canvas.buffer
+    };
+
+    assert_eq!(render(7), render(7));
+    assert_ne!(render(7), render(8));
+}
+
+#[test]
+fn zero_sized_canvas_has_one_pixel() {
+    for (width, height) in [(0, 0), (0, 5), (5, 0)] {
+        let mut canvas = Canvas::new(width, height);
+        assert_eq!((canvas.width, canvas.height), (width.max(1), height.max(1)));
+
+        canvas.set_color(0, 0, &Color::white());
+        assert_eq!(*canvas.color_at(0, 0), Color::white().rgb());
+        assert_eq!(*canvas.color_at(5, 5), 0);
+    }
+}
+
+#[test]
+fn single_pixel_canvas_ignores_out_of_bounds_pixels() {
+    let mut canvas = Canvas::new(1, 1);
+    canvas.set_color(1, 0, &Color::white());
+    canvas.set_color(0, 1, &Color::white());
+    canvas.set_color(usize::MAX, usize::MAX, &Color::white());
+    assert_eq!(*canvas.color_at(0, 0), 0);
+    assert_eq!(*canvas.color_at(1, 0), 0);
+    assert_eq!(*canvas.color_at(usize::MAX, 0), 0);
+
+    canvas.set_color(0, 0, &Color::new(1.0, 0.0, 0.0));
+    assert_eq!(*canvas.color_at(0, 0), Color::new(1.0, 0.0, 0.0).rgb());
+}
+
+#[test]
+fn pixels_iterates_row_by_row_with_coordinates() {
+    let mut canvas = Canvas::new(3, 2);
+    canvas.set_color(2, 0, &Color::new(1.0, 0.0, 0.0));
+    canvas.set_color(0, 1, &Color::new(0.0, 1.0, 1.0));
+
+    let pixels: Vec<(usize, usize, Color)> = canvas.pixels().collect();
+    let coordinates: Vec<(usize, usize)> = pixels.iter().map(|(x, y, _)| (*x, *y)).collect();
+    assert_eq!(coordinates, vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+
+    assert_eq!(pixels[2].2, Color::new(1.0, 0.0, 0.0));
+    assert_eq!(pixels[3].2, Color::new(0.0, 1.0, 1.0));
+    assert_eq!(pixels[0].2, Color::black());
+
+    let lit = (&canvas).into_iter().filter(|(_, _, color)| *color != Color::black()).count();
+    assert_eq!(lit, 2);
+}
+
+#[test]
+fn pixels_round_trip_through_set_color() {
+    let mut canvas = Canvas::new(4, 4);
+    for (x, y, _) in canvas.pixels().collect::<Vec<_>>() {
+        canvas.set_color(x, y, &Color::new(x as f32 / 3.0, y as f32 / 3.0, 0.5));
+    }
+
+    for (x, y, color) in &canvas {
+        assert_eq!(color.rgb(), *canvas.color_at(x, y));
+        assert!((*color.r() - x as f32 / 3.0).abs() < 1.0 / 255.0);
+    }
+}
+
+#[test]
+fn auto_exposure_brings_the_bright_end_to_0_9() {
+    // 90 pixels of dim gray and 10 brighter ones, the 95th percentile falls among the bright ones
+    let mut canvas = Canvas::new(10, 10);
+    for (x, y, _) in canvas.pixels().collect::<Vec<_>>() {
+        let level = if y == 9 { 0.6 } else { 0.2 };
+        canvas.set_color(x, y, &Color::new(level, level, level));
+    }
+
+    let exposure = canvas.auto_exposure();
+    let bright = Color::from_rgb(Color::new(0.6, 0.6, 0.6).rgb()).luminance();
+    assert!((exposure - 0.9 / bright).abs() < 0.0001, "{}", exposure);
+    assert!(exposure > 1.0);
+
+    // A single very bright pixel is ignored as an outlier
+    canvas.set_color(0, 0, &Color::white());
+    assert!((canvas.auto_exposure() - exposure).abs() < 0.0001);
+}
+
+#[test]
+fn auto_exposure_darkens_an_overexposed_image() {
+    let canvas = filled(4, 4, Color::white());
+    assert!((canvas.auto_exposure() - 0.9).abs() < 0.0001);
+
+    assert_eq!(Canvas::new(4, 4).auto_exposure(), 1.0);
+}
+
+#[test]
+fn auto_exposure_pulls_down_colors_brighter_than_1() {
+    let mut canvas = Canvas::with_hdr(4, 4);
+    for (x, y, _) in canvas.pixels().collect::<Vec<_>>() {
+        canvas.set_color(x, y, &Color::new(3.0, 3.0, 3.0));
+    }
+
+    let exposure = canvas.auto_exposure();
+    assert!((exposure - 0.3).abs() < 0.0001, "{}", exposure);
+}
+
+#[test]
+fn auto_exposure_skips_broken_pixels() {
+    let mut canvas = Canvas::with_hdr(4, 4);
+    for (x, y, _) in canvas.pixels().collect::<Vec<_>>() {
+        canvas.set_color(x, y, &Color::new(3.0, 3.0, 3.0));
+    }
+    canvas.set_color(1, 2, &Color::new(f32::NAN, 3.0, 3.0));
+    canvas.set_color(3, 0, &Color::new(f32::INFINITY, 3.0, 3.0));
+
+    let exposure = canvas.auto_exposure();
+    assert!((exposure - 0.3).abs() < 0.0001, "{}", exposure);
+
+    let mut broken = Canvas::with_hdr(1, 1);
+    broken.set_color(0, 0, &Color::new(f32::NAN, f32::NAN, f32::NAN));
+    assert_eq!(broken.auto_exposure(), 1.0);
+}
+
+fn temp_path(name: &str, extension: &str) -> String {
+    let path = std::env::temp_dir().join(format!("{}-{}.{}", name, std::process::id(), extension));
+    return path.to_string_lossy().into_owned();
+}
+
+fn read_f32(bytes: &[u8], at: usize) -> f32 {
+    return f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+}
+
+fn read_i32(bytes: &[u8], at: usize) -> i32 {
+    return i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+}
+
+#[test]
+fn hdr_canvas_keeps_colors_above_one() {
+    let bright = Color::new(4.0, 0.5, 12.5);
+
+    let mut canvas = Canvas::with_hdr(3, 2);
+    canvas.set_color(1, 1, &bright);
+    assert_eq!(canvas.hdr_color_at(1, 1), bright);
+    assert_eq!(*canvas.color_at(1, 1), bright.rgb());
+
+    // Plain canvases only have the 8 bit colors to give back
+    let mut plain = Canvas::new(3, 2);
+    plain.set_color(1, 1, &bright);
+    assert!(plain.hdr.is_none());
+    assert_eq!(plain.hdr_color_at(1, 1), Color::from_rgb(bright.rgb()));
+
+    // Blitting carries the float colors across
+    let mut target = Canvas::with_hdr(4, 4);
+    target.blit(&canvas, 1, 1);
+    assert_eq!(target.hdr_color_at(2, 2), bright);
+}
+
+#[test]
+fn save_hdr_writes_rgbe_pixels() {
+    let mut canvas = Canvas::with_hdr(2, 1);
+    canvas.set_color(0, 0, &Color::new(3.0, 1.5, 0.75));
+    canvas.set_color(1, 0, &Color::new(-1.0, f32::NAN, 0.0));
+    let path = temp_path("canvas", "hdr");
+
+    canvas.save_hdr(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+    assert_eq!(&bytes[..header.len()], header);
+
+    // 3 = 0.75 * 2^2, so the exponent byte is 128 + 2 and the mantissas are out of 256
+    let pixels = &bytes[header.len()..];
+    assert_eq!(pixels, &[192, 96, 48, 130, 0, 0, 0, 0]);
+}
+
+#[test]
+fn save_exr_writes_float_scanlines() {
+    let mut canvas = Canvas::with_hdr(2, 2);
+    canvas.set_color(0, 0, &Color::new(3.0, 1.5, 0.75));
+    canvas.set_color(1, 1, &Color::new(0.25, 40.0, 2.0));
+    let path = temp_path("canvas", "exr");
+
+    canvas.save_exr(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(&bytes[..4], &[0x76, 0x2f, 0x31, 0x01]);
+
+    // Walk the header attributes, name, type, size and value, up to the empty name that ends them
+    let mut at = 8;
+    let mut names = Vec::new();
+    while bytes[at] != 0 {
+        let name_end = at + bytes[at..].iter().position(|b| *b == 0).unwrap();
+        let kind_end = name_end + 1 + bytes[name_end + 1..].iter().position(|b| *b == 0).unwrap();
+        names.push(String::from_utf8(bytes[at..name_end].to_vec()).unwrap());
+        at = kind_end + 5 + read_i32(&bytes, kind_end + 1) as usize;
+    }
+    for required in ["channels", "compression", "dataWindow", "displayWindow", "lineOrder", "pixelAspectRatio", "screenWindowCenter", "screenWindowWidth"] {
+        assert!(names.iter().any(|name| name == required), "{}", required);
+    }
+
+    // Each scanline is its y, its size and then the row's blue, green and red values
+    let table = at + 1;
+    let line = |y: usize| u64::from_le_bytes(bytes[table + y * 8..table + y * 8 + 8].try_into().unwrap()) as usize;
+    assert_eq!(read_i32(&bytes, line(0)), 0);
+    assert_eq!(read_i32(&bytes, line(0) + 4), 24);
+    assert_eq!(read_i32(&bytes, line(1)), 1);
+
+    let value = |y: usize, channel: usize, x: usize| read_f32(&bytes, line(y) + 8 + (channel * 2 + x) * 4);
+    assert_eq!((value(0, 2, 0), value(0, 1, 0), value(0, 0, 0)), (3.0, 1.5, 0.75));
+    assert_eq!((value(1, 2, 1), value(1, 1, 1), value(1, 0, 1)), (0.25, 40.0, 2.0));
+    assert_eq!(value(0, 1, 1), 0.0);
+    assert_eq!(line(1) + 8 + 24, bytes.len());
+}
+
+#[test]
+fn diff_of_identical_canvases_is_zero() {
+    let canvas = filled(4, 3, Color::new(0.2, 0.4, 0.6));
+
+    assert_eq!(canvas.diff(&filled(4, 3, Color::new(0.2, 0.4, 0.6))).unwrap(), (0.0, 0));
+    assert!(canvas.diff_image(&canvas).unwrap().buffer.iter().all(|pixel| *pixel == 0));
+}
+
+#[test]
+fn diff_reports_the_worst_channel_and_changed_pixels() {
+    let reference = filled(4, 3, Color::black());
+    let mut render = filled(4, 3, Color::black());
+    render.set_color(1, 1, &Color::new(0.0, 1.0, 0.0));
+    render.set_color(3, 2, &Color::new(0.2, 0.0, 0.4));
+
+    let (max_error, differing) = render.diff(&reference).unwrap();
+    assert!((max_error - 1.0).abs() < 0.0001);
+    assert_eq!(differing, 2);
+
+    // The biggest difference is red, a smaller one further down the ramp and matches stay black
+    let heatmap = render.diff_image(&reference).unwrap();
+    assert_eq!(*heatmap.color_at(1, 1), Color::RED.rgb());
+    assert_eq!(*heatmap.color_at(3, 2), Color::heat_ramp(0.4).rgb());
+    assert_eq!(*heatmap.color_at(0, 0), 0);
+}
+
+#[test]
+fn diff_compares_float_colors_when_both_have_them() {
+    let mut a = Canvas::with_hdr(2, 1);
+    let mut b = Canvas::with_hdr(2, 1);
+    a.set_color(0, 0, &Color::new(3.0, 1.0, 1.0));
+    b.set_color(0, 0, &Color::new(5.0, 1.0, 1.0));
+
+    // Both clip to white in 8 bits but differ by 2 in red
+    assert_eq!(a.buffer, b.buffer);
+    assert_eq!(a.diff(&b).unwrap(), (2.0, 1));
+}
+
+#[test]
+fn diff_rejects_mismatched_sizes() {
+    let error = Canvas::new(4, 3).diff(&Canvas::new(3, 4)).err().unwrap();
+    assert!(error.contains("4x3") && error.contains("3x4"), "{}", error);
+    assert!(Canvas::new(4, 3).diff_image(&Canvas::new(4, 4)).is_err());
+}
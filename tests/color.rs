@@ -0,0 +1,150 @@
+use tracer::color::Color;
+
+#[test]
+fn named_colors() {
+    assert_eq!(Color::black(), Color::new(0.0, 0.0, 0.0));
+    assert_eq!(Color::white(), Color::new(1.0, 1.0, 1.0));
+    assert_eq!(Color::BLACK, Color::black());
+    assert_eq!(Color::WHITE, Color::white());
+    assert_eq!(Color::RED + Color::GREEN + Color::BLUE, Color::WHITE);
+}
+
+#[test]
+fn constants_are_usable_in_const_context() {
+    const HIGHLIGHT: Color = Color::new(1.0, 0.9, 0.5);
+
+    assert_eq!(HIGHLIGHT.rgb(), 0xffe67f);
+}
+
+#[test]
+fn hex_colors_are_divided_by_255() {
+    assert_eq!(Color::from_hex(0x000000), Color::black());
+    assert_eq!(Color::from_hex(0xffffff), Color::white());
+    assert_eq!(Color::from_hex(0xff0000), Color::RED);
+    assert_eq!(Color::from_hex(0x808080), Color::new(0.50196, 0.50196, 0.50196));
+}
+
+#[test]
+fn hex_colors_are_written_out_unchanged() {
+    // The output writes channels without gamma, so a picked code comes back out as the same pixel
+    for value in 0..=255u32 {
+        let hex = (value << 16) | ((255 - value) << 8) | (value / 2);
+        assert_eq!(Color::from_hex(hex).rgb(), hex, "{:06x}", hex);
+    }
+}
+
+#[test]
+fn hex_strings_are_parsed_with_or_without_hash() {
+    assert_eq!(Color::from_hex_str("#ff8800"), Ok(Color::from_hex(0xff8800)));
+    assert_eq!(Color::from_hex_str("FF8800"), Ok(Color::from_hex(0xff8800)));
+    assert!(Color::from_hex_str("#ff880").is_err());
+    assert!(Color::from_hex_str("#gg8800").is_err());
+    assert!(Color::from_hex_str("+ff8800").is_err());
+}
+
+#[test]
+fn hex_round_trips_every_channel_value() {
+    for value in 0..=255u32 {
+        let hex = (value << 16) | ((255 - value) << 8) | (value / 2);
+        assert_eq!(Color::from_hex(hex).to_hex(), hex, "{:06x}", hex);
+    }
+}
+
+#[test]
+fn to_hex_clamps_out_of_range_channels() {
+    assert_eq!(Color::new(2.0, -1.0, 0.5).to_hex(), 0xff0080);
+}
+
+#[test]
+fn dithered_quantization_rounds_up_past_the_threshold() {
+    let color = Color::new(100.25 / 255.0, 100.75 / 255.0, 1.0);
+
+    assert_eq!(color.rgb_dithered(0.0), (100 << 16) | (100 << 8) | 255);
+    assert_eq!(color.rgb_dithered(0.5), (100 << 16) | (101 << 8) | 255);
+    assert_eq!(color.rgb_dithered(0.99), (101 << 16) | (101 << 8) | 255);
+}
+
+#[test]
+fn broken_channels_quantize_predictably() {
+    let broken = Color::new(f32::NAN, f32::INFINITY, f32::NEG_INFINITY);
+
+    assert!(!broken.is_finite());
+    assert!(Color::new(0.5, 2.0, -1.0).is_finite());
+
+    assert_eq!(broken.rgb(), 0x00ff00);
+    assert_eq!(broken.rgb_dithered(0.5), 0x00ff00);
+    assert_eq!(broken.to_hex(), 0x00ff00);
+}
+
+// CIE xy chromaticity of a linear sRGB color
+fn chromaticity(color: Color) -> (f32, f32) {
+    let (r, g, b) = (*color.r(), *color.g(), *color.b());
+    let x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+    return (x / (x + y + z), y / (x + y + z));
+}
+
+#[test]
+fn temperatures_land_on_the_planckian_locus() {
+    // Reference chromaticities of a black body at each temperature
+    let references = [(2856.0, 0.4476, 0.4074), (3200.0, 0.4234, 0.3990), (5000.0, 0.3451, 0.3516), (6500.0, 0.3135, 0.3236)];
+
+    for (kelvin, x, y) in references {
+        let (actual_x, actual_y) = chromaticity(Color::from_temperature(kelvin));
+        assert!((actual_x - x).abs() < 0.002 && (actual_y - y).abs() < 0.002, "{}K gave ({}, {})", kelvin, actual_x, actual_y);
+    }
+}
+
+#[test]
+fn temperatures_run_from_warm_to_cool() {
+    let warm = Color::from_temperature(3200.0);
+    assert_eq!(*warm.r(), 1.0);
+    assert!(warm.r() > warm.g() && warm.g() > warm.b());
+
+    let daylight = Color::from_temperature(6500.0);
+    assert!(*daylight.r() > 0.9 && *daylight.g() > 0.9 && *daylight.b() > 0.9);
+
+    let sky = Color::from_temperature(12000.0);
+    assert_eq!(*sky.b(), 1.0);
+    assert!(sky.r() < sky.g());
+
+    // Outside the fitted range the ends are held
+    assert_eq!(Color::from_temperature(500.0), Color::from_temperature(1667.0));
+    assert_eq!(Color::from_temperature(40000.0), Color::from_temperature(25000.0));
+}
+
+#[test]
+fn luminance_uses_rec_709_weights() {
+    assert_eq!(Color::black().luminance(), 0.0);
+    assert!((Color::white().luminance() - 1.0).abs() < 0.00001);
+    assert!((Color::RED.luminance() - 0.2126).abs() < 0.00001);
+    assert!((Color::GREEN.luminance() - 0.7152).abs() < 0.00001);
+    assert!((Color::BLUE.luminance() - 0.0722).abs() < 0.00001);
+    assert!((Color::new(0.5, 0.25, 1.0).luminance() - 0.3573).abs() < 0.00001);
+    assert!((Color::new(2.0, 2.0, 2.0).luminance() - 2.0).abs() < 0.00001);
+}
+
+#[test]
+fn grayscale_keeps_luminance() {
+    let color = Color::new(0.9, 0.4, 0.1);
+    let gray = color.grayscale();
+
+    assert_eq!(gray, Color::new(0.48464, 0.48464, 0.48464));
+    assert!((gray.luminance() - color.luminance()).abs() < 0.00001);
+    assert_eq!(Color::white().grayscale(), Color::white());
+}
+
+#[test]
+fn heat_ramp_runs_from_black_to_red() {
+    assert_eq!(Color::heat_ramp(0.0), Color::BLACK);
+    assert_eq!(Color::heat_ramp(0.25), Color::BLUE);
+    assert_eq!(Color::heat_ramp(0.5), Color::GREEN);
+    assert_eq!(Color::heat_ramp(0.75), Color::new(1.0, 1.0, 0.0));
+    assert_eq!(Color::heat_ramp(1.0), Color::RED);
+    assert_eq!(Color::heat_ramp(0.125), Color::new(0.0, 0.0, 0.5));
+
+    assert_eq!(Color::heat_ramp(-1.0), Color::BLACK);
+    assert_eq!(Color::heat_ramp(3.0), Color::RED);
+    assert_eq!(Color::heat_ramp(f32::NAN), Color::BLACK);
+}
@@ -0,0 +1,188 @@
+use std::f32::consts::PI;
+
+use tracer::camera::Camera;
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::intersection::{self, Intersection};
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::ray::Ray;
+use tracer::shape::{Shape, Sphere};
+use tracer::util;
+use tracer::world::World;
+use uuid::Uuid;
+
+// A flat disk of radius 1 in the xz plane, written the way a downstream crate would add its own shape
+struct Disk {
+    id: Uuid,
+    transform: Matrix4x4,
+    material: Material,
+}
+
+impl Disk {
+    fn new(material: Material) -> Self {
+        return Self {
+            id: Uuid::new_v4(),
+            transform: Matrix4x4::identity(),
+            material,
+        };
+    }
+}
+
+impl Shape for Disk {
+    fn id(&self) -> &Uuid {
+        return &self.id;
+    }
+
+    fn transform(&self) -> &Matrix4x4 {
+        return &self.transform;
+    }
+
+    fn set_transform(&mut self, matrix: Matrix4x4) {
+        self.transform = matrix;
+    }
+
+    fn material(&self) -> &Material {
+        return &self.material;
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        return &mut self.material;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if ray.direction.y().abs() < util::THRESHOLD_F32 {
+            return Vec::new();
+        }
+
+        let t = -ray.origin.y() / ray.direction.y();
+        let point = ray.at(t);
+        if point.x() * point.x() + point.z() * point.z() > 1.0 {
+            return Vec::new();
+        }
+
+        return vec![Intersection::new(self, t)];
+    }
+
+    fn local_normal_at(&self, _: &Vec4, _: Intersection) -> Vec4 {
+        return Vec4::vector(0.0, 1.0, 0.0);
+    }
+
+    fn world_normal_at(&self, world_point: &Vec4, i: Intersection) -> Vec4 {
+        let local_point = self.transform().invert() * *world_point;
+        let local_normal = self.local_normal_at(&local_point, i);
+        let world_normal = self.transform().invert().transpose() * local_normal;
+        let world_normal = Vec4::vector(*world_normal.x(), *world_normal.y(), *world_normal.z());
+
+        return world_normal.normalize();
+    }
+
+    fn sample_surface(&self, u: f32, v: f32) -> Vec4 {
+        let radius = u.sqrt();
+        let angle = 2.0 * PI * v;
+        return self.transform * Vec4::point(radius * angle.cos(), 0.0, radius * angle.sin());
+    }
+}
+
+fn disk_world() -> World {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::white()));
+
+    let mut disk = Disk::new(Material::matte(Color::new(0.2, 0.4, 0.8)));
+    disk.set_transform(Matrix4x4::scale(2.0, 1.0, 2.0));
+    world.add_object(Box::new(disk));
+
+    return world;
+}
+
+#[test]
+fn custom_shape_is_intersected_through_its_transform() {
+    let world = disk_world();
+
+    let ray = Ray::new(Vec4::point(1.5, 3.0, 0.0), Vec4::vector(0.0, -1.0, 0.0));
+    let xs = world.intersect_world(ray);
+    assert_eq!(xs.len(), 1);
+    assert!(util::equals_f32(&xs[0].t, &3.0));
+
+    let outside = Ray::new(Vec4::point(2.5, 3.0, 0.0), Vec4::vector(0.0, -1.0, 0.0));
+    assert!(world.intersect_world(outside).is_empty());
+}
+
+#[test]
+fn custom_shape_without_a_cache_gets_its_inverse_computed() {
+    let mut disk = Disk::new(Material::default());
+    disk.set_transform(Matrix4x4::scale(2.0, 1.0, 2.0));
+
+    assert_eq!(disk.inverse_transform(), Matrix4x4::scale(0.5, 1.0, 0.5));
+}
+
+#[test]
+fn custom_shape_is_shaded_end_to_end() {
+    let world = disk_world();
+
+    let ray = Ray::new(Vec4::point(0.0, 3.0, 0.0), Vec4::vector(0.0, -1.0, 0.0));
+    let xs = world.intersect_world(ray);
+    let comp = xs[0].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(comp.point, Vec4::point(0.0, 0.0, 0.0));
+    assert_eq!(comp.normalv, Vec4::vector(0.0, 1.0, 0.0));
+    assert!(world.color_at(ray, 5) != Color::black());
+}
+
+#[test]
+fn custom_shape_takes_part_in_refraction() {
+    let mut world = disk_world();
+    world.add_object(Box::new(Sphere::glass_sphere()));
+
+    let ray = Ray::new(Vec4::point(0.5, 3.0, 0.0), Vec4::vector(0.0, -1.0, 0.0));
+    let xs = world.intersect_world(ray);
+    let disk_hit = xs.iter().find(|x| x.object.id() == world.objects[0].id()).unwrap();
+
+    // The disk cuts through the middle of the glass sphere, so the ray reaches it from inside the glass
+    assert_eq!(intersection::refractive_indices(disk_hit, &xs), (1.5, 1.0));
+}
+
+#[test]
+fn world_with_custom_shape_renders_across_threads() {
+    let world = disk_world();
+    let mut camera = Camera::new(20.0, 20.0, PI / 3.0);
+    camera.set_view_transform(Vec4::point(0.0, 5.0, -5.0), Vec4::ORIGIN, Vec4::vector(0.0, 1.0, 0.0));
+
+    let rows: Vec<Vec<Color>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..2)
+            .map(|half| {
+                let world = &world;
+                let camera = &camera;
+                scope.spawn(move || {
+                    let mut colors = Vec::new();
+                    for y in half * 10..(half + 1) * 10 {
+                        for x in 0..20 {
+                            colors.push(world.color_at(camera.ray_for_pixel(x as f32, y as f32), 5));
+                        }
+                    }
+                    colors
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    let serial = camera.render_to_colors(&world);
+    let threaded: Vec<Color> = rows.into_iter().flatten().collect();
+
+    assert_eq!(threaded, serial);
+}
+
+#[test]
+fn custom_shape_that_does_not_copy_itself_is_left_out_of_copies() {
+    let disk = Disk::new(Material::default());
+    assert!(disk.clone_shape().is_none());
+
+    // A group can only be copied along with all of its children
+    let mut group = tracer::group::Group::new(Material::default());
+    group.add_child(Box::new(Sphere::new(Material::default())));
+    assert!(group.clone_shape().is_some());
+    group.add_child(Box::new(disk));
+    assert!(group.clone_shape().is_none());
+}
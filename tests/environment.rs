@@ -0,0 +1,135 @@
+use tracer::color::Color;
+use tracer::environment::{CubeMap, Environment};
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::image::Image;
+use tracer::material::Material;
+use tracer::ray::Ray;
+use tracer::shape::{Plane, Shape};
+use tracer::world::World;
+
+fn quadrant_panorama() -> Image {
+    let columns = [Color::RED, Color::RED, Color::GREEN, Color::GREEN, Color::BLUE, Color::BLUE, Color::WHITE, Color::WHITE];
+    let pixels = (0..4).flat_map(|_| columns).collect();
+    return Image::new(8, 4, pixels);
+}
+
+#[test]
+fn missed_rays_see_the_environment() {
+    let mut world = World::new();
+    let ray = Ray::new(Vec4::ORIGIN, Vec4::vector(0.0, 0.0, 1.0));
+
+    assert_eq!(world.color_at(ray, 5), Color::black());
+
+    world.environment = Some(Environment::Solid(Color::new(0.2, 0.4, 0.9)));
+    assert_eq!(world.color_at(ray, 5), Color::new(0.2, 0.4, 0.9));
+}
+
+#[test]
+fn reflections_pick_up_the_environment() {
+    let mut world = World::new();
+    world.environment = Some(Environment::Solid(Color::new(0.2, 0.4, 0.9)));
+
+    let material = Material { ambient: 0.0, diffuse: 0.0, specular: 0.0, reflective: 1.0, ..Material::default() };
+    let mut mirror = Plane::new(material);
+    mirror.set_transform(Matrix4x4::translation(0.0, -1.0, 0.0));
+    world.add_object(Box::new(mirror));
+
+    let ray = Ray::new(Vec4::ORIGIN, Vec4::vector(0.0, -1.0, 1.0).normalize());
+    assert_eq!(world.color_at(ray, 5), Color::new(0.2, 0.4, 0.9));
+}
+
+#[test]
+fn equirectangular_maps_longitude_to_columns() {
+    let environment = Environment::Equirectangular(quadrant_panorama());
+
+    assert_eq!(environment.color_at(&Vec4::vector(-1.0, 0.0, -1.0)), Color::RED);
+    assert_eq!(environment.color_at(&Vec4::vector(-1.0, 0.0, 1.0)), Color::GREEN);
+    assert_eq!(environment.color_at(&Vec4::vector(1.0, 0.0, 1.0)), Color::BLUE);
+    assert_eq!(environment.color_at(&Vec4::vector(1.0, 0.0, -1.0)), Color::WHITE);
+}
+
+#[test]
+fn equirectangular_maps_latitude_to_rows() {
+    let pixels = vec![Color::BLUE, Color::BLUE, Color::GREEN, Color::GREEN];
+    let environment = Environment::Equirectangular(Image::new(1, 4, pixels));
+
+    assert_eq!(environment.color_at(&Vec4::vector(0.0, 1.0, 0.0)), Color::BLUE);
+    assert_eq!(environment.color_at(&Vec4::vector(0.0, -1.0, 0.0)), Color::GREEN);
+    assert_eq!(environment.color_at(&Vec4::vector(0.0, 0.0, 1.0)), Color::new(0.0, 0.5, 0.5));
+}
+
+#[test]
+fn cube_map_picks_the_face_along_the_direction() {
+    let environment = Environment::CubeMap(CubeMap {
+        left: Image::filled(2, 2, Color::RED),
+        right: Image::filled(2, 2, Color::GREEN),
+        front: Image::filled(2, 2, Color::BLUE),
+        back: Image::filled(2, 2, Color::WHITE),
+        up: Image::filled(2, 2, Color::new(1.0, 1.0, 0.0)),
+        down: Image::filled(2, 2, Color::new(0.0, 1.0, 1.0)),
+    });
+
+    assert_eq!(environment.color_at(&Vec4::vector(-2.0, 0.5, 0.3)), Color::RED);
+    assert_eq!(environment.color_at(&Vec4::vector(2.0, -0.5, 0.3)), Color::GREEN);
+    assert_eq!(environment.color_at(&Vec4::vector(0.1, 0.2, 5.0)), Color::BLUE);
+    assert_eq!(environment.color_at(&Vec4::vector(0.1, 0.2, -5.0)), Color::WHITE);
+    assert_eq!(environment.color_at(&Vec4::vector(0.1, 3.0, 0.5)), Color::new(1.0, 1.0, 0.0));
+    assert_eq!(environment.color_at(&Vec4::vector(0.1, -3.0, 0.5)), Color::new(0.0, 1.0, 1.0));
+}
+
+#[test]
+fn cube_map_face_is_upright_from_the_centre() {
+    // Top row red, bottom row blue
+    let front = Image::new(1, 2, vec![Color::RED, Color::BLUE]);
+    let environment = Environment::CubeMap(CubeMap {
+        left: Image::filled(1, 1, Color::black()),
+        right: Image::filled(1, 1, Color::black()),
+        front,
+        back: Image::filled(1, 1, Color::black()),
+        up: Image::filled(1, 1, Color::black()),
+        down: Image::filled(1, 1, Color::black()),
+    });
+
+    assert_eq!(environment.color_at(&Vec4::vector(0.0, 0.9, 1.0)), Color::RED);
+    assert_eq!(environment.color_at(&Vec4::vector(0.0, -0.9, 1.0)), Color::BLUE);
+}
+
+#[test]
+fn parse_plain_ppm() {
+    let ppm = b"P3\n# a comment\n2 1\n255\n255 0 0  0 0 255\n";
+    let image = Image::parse_ppm(ppm).unwrap();
+
+    assert_eq!((image.width, image.height), (2, 1));
+    assert_eq!(image.pixel_at(0, 0), Color::RED);
+    assert_eq!(image.pixel_at(1, 0), Color::BLUE);
+}
+
+#[test]
+fn parse_binary_ppm() {
+    let mut ppm = b"P6 1 2 255\n".to_vec();
+    ppm.extend_from_slice(&[0, 255, 0, 255, 255, 255]);
+    let image = Image::parse_ppm(&ppm).unwrap();
+
+    assert_eq!(image.pixel_at(0, 0), Color::GREEN);
+    assert_eq!(image.pixel_at(0, 1), Color::WHITE);
+}
+
+#[test]
+fn parse_ppm_rejects_sizes_that_overflow() {
+    let header = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+    assert_eq!(Image::parse_ppm(header.as_bytes()).err(), Some(String::from("image too large")));
+
+    // width * height fits but the sample count does not
+    let header = format!("P6 {} 1 255\n", usize::MAX / 2);
+    assert_eq!(Image::parse_ppm(header.as_bytes()).err(), Some(String::from("image too large")));
+}
+
+#[test]
+fn parse_ppm_errors() {
+    assert!(Image::parse_ppm(b"P5 1 1 255\n0").is_err());
+    assert!(Image::parse_ppm(b"P3 2 2 255\n0 0 0").is_err());
+    assert!(Image::parse_ppm(b"P3 2").is_err());
+    assert!(Image::parse_ppm(b"P3 0 1 255\n").is_err());
+    assert!(Image::parse_ppm(b"P6 1 0 255\n").is_err());
+    assert!(Image::load_ppm("does_not_exist.ppm").is_err());
+}
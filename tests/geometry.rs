@@ -0,0 +1,362 @@
+use std::f32::consts::PI;
+
+use tracer::geometry::{Matrix4x4, Quaternion, TransformBuilder, Vec4};
+use tracer::util;
+
+#[test]
+fn lerp_between_points() {
+    let a = Vec4::point(0.0, 0.0, 0.0);
+    let b = Vec4::point(2.0, -4.0, 6.0);
+
+    assert_eq!(a.lerp(&b, 0.0), a);
+    assert_eq!(a.lerp(&b, 0.5), Vec4::point(1.0, -2.0, 3.0));
+    assert_eq!(a.lerp(&b, 1.0), b);
+}
+
+#[test]
+fn quaternion_round_trips_through_a_matrix() {
+    let rotation = Matrix4x4::rotatation_x(0.3) * Matrix4x4::rotatation_y(-1.2) * Matrix4x4::rotatation_z(2.5);
+    let q = Quaternion::from_rotation_matrix(&rotation);
+
+    assert_eq!(q.to_matrix(), rotation);
+}
+
+#[test]
+fn quaternion_from_axis_angle_matches_rotation() {
+    let q = Quaternion::from_axis_angle(&Vec4::vector(0.0, 1.0, 0.0), PI / 3.0);
+
+    assert_eq!(q.to_matrix(), Matrix4x4::rotatation_y(PI / 3.0));
+}
+
+#[test]
+fn decompose_and_compose_round_trip() {
+    let m = Matrix4x4::translation(1.0, -2.0, 3.0) * Matrix4x4::rotatation_z(0.7) * Matrix4x4::scale(2.0, 0.5, 3.0);
+    let (translation, rotation, scale) = m.decompose();
+
+    assert_eq!(translation, Vec4::vector(1.0, -2.0, 3.0));
+    assert_eq!(rotation, Quaternion::from_axis_angle(&Vec4::vector(0.0, 0.0, 1.0), 0.7));
+    assert_eq!(scale, Vec4::vector(2.0, 0.5, 3.0));
+    assert_eq!(Matrix4x4::compose(&translation, &rotation, &scale), m);
+}
+
+#[test]
+fn interpolate_endpoints() {
+    let a = Matrix4x4::translation(1.0, 0.0, 0.0) * Matrix4x4::rotatation_x(0.4);
+    let b = Matrix4x4::translation(0.0, 5.0, 0.0) * Matrix4x4::rotatation_y(1.1) * Matrix4x4::scale(2.0, 2.0, 2.0);
+
+    assert_eq!(a.interpolate(&b, 0.0), a);
+    assert_eq!(a.interpolate(&b, 1.0), b);
+}
+
+#[test]
+fn interpolate_rotation_halfway() {
+    let a = Matrix4x4::identity();
+    let b = Matrix4x4::rotatation_y(PI / 2.0);
+
+    assert_eq!(a.interpolate(&b, 0.5), Matrix4x4::rotatation_y(PI / 4.0));
+}
+
+#[test]
+fn interpolate_keeps_rotation_rigid() {
+    let a = Matrix4x4::translation(0.0, 0.0, 0.0);
+    let b = Matrix4x4::translation(4.0, 0.0, 0.0) * Matrix4x4::rotatation_z(PI / 2.0) * Matrix4x4::scale(3.0, 3.0, 3.0);
+
+    let halfway = a.interpolate(&b, 0.5);
+    let expected = Matrix4x4::translation(2.0, 0.0, 0.0) * Matrix4x4::rotatation_z(PI / 4.0) * Matrix4x4::scale(2.0, 2.0, 2.0);
+
+    assert_eq!(halfway, expected);
+}
+
+#[test]
+fn origin_and_zero_constants() {
+    assert_eq!(Vec4::ORIGIN, Vec4::point(0.0, 0.0, 0.0));
+    assert_eq!(Vec4::ZERO, Vec4::vector(0.0, 0.0, 0.0));
+    assert_eq!(Vec4::ORIGIN + Vec4::vector(1.0, 2.0, 3.0), Vec4::point(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn identity_matrix_is_identity() {
+    assert!(Matrix4x4::identity().is_identity());
+    assert!(!Matrix4x4::translation(0.0, 1.0, 0.0).is_identity());
+
+    let m = Matrix4x4::rotatation_x(PI / 3.0) * Matrix4x4::scale(2.0, 3.0, 4.0);
+    assert!((m * m.invert()).is_identity());
+}
+
+#[test]
+fn approx_eq_uses_the_given_tolerance() {
+    let a = Matrix4x4::translation(1.0, 2.0, 3.0);
+    let b = Matrix4x4::translation(1.0, 2.001, 3.0);
+
+    assert!(a != b);
+    assert!(a.approx_eq(&b, 0.01));
+    assert!(!a.approx_eq(&b, 0.0001));
+    assert!(a.approx_eq(&a, 0.0));
+}
+
+#[test]
+fn double_inverse_round_trips_within_tolerance() {
+    let m = Matrix4x4::translation(10.0, -5.0, 2.0)
+        * Matrix4x4::rotatation_y(0.7)
+        * Matrix4x4::shearing(0.3, 0.0, 0.1, 0.0, 0.0, 0.2)
+        * Matrix4x4::scale(0.5, 8.0, 3.0);
+
+    assert!(m.invert().invert().approx_eq(&m, 0.001));
+}
+
+#[test]
+fn distance_between_points() {
+    let a = Vec4::point(1.0, 2.0, 3.0);
+    let b = Vec4::point(4.0, 6.0, 15.0);
+
+    assert_eq!(a.distance_squared(&b), 169.0);
+    assert_eq!(a.distance(&b), 13.0);
+    assert_eq!(b.distance(&a), 13.0);
+    assert_eq!(a.distance(&a), 0.0);
+}
+
+#[test]
+fn project_vector_onto_another() {
+    let v = Vec4::vector(3.0, 4.0, 0.0);
+
+    assert_eq!(v.project_onto(&Vec4::vector(1.0, 0.0, 0.0)), Vec4::vector(3.0, 0.0, 0.0));
+    assert_eq!(v.project_onto(&Vec4::vector(0.0, 5.0, 0.0)), Vec4::vector(0.0, 4.0, 0.0));
+    assert_eq!(v.project_onto(&Vec4::vector(1.0, 1.0, 0.0)), Vec4::vector(3.5, 3.5, 0.0));
+    assert_eq!(v.project_onto(&Vec4::vector(0.0, 0.0, 2.0)), Vec4::ZERO);
+    assert_eq!(v.project_onto(&Vec4::ZERO), Vec4::ZERO);
+}
+
+#[test]
+fn angle_between_vectors() {
+    let x = Vec4::vector(1.0, 0.0, 0.0);
+
+    assert_eq!(x.angle_between(&x), 0.0);
+    assert!(util::equals_f32(&x.angle_between(&Vec4::vector(0.0, 3.0, 0.0)), &(PI / 2.0)));
+    assert!(util::equals_f32(&x.angle_between(&Vec4::vector(1.0, 1.0, 0.0)), &(PI / 4.0)));
+    assert!(util::equals_f32(&x.angle_between(&Vec4::vector(-2.0, 0.0, 0.0)), &PI));
+    assert_eq!(x.angle_between(&Vec4::ZERO), 0.0);
+}
+
+#[test]
+fn angle_between_nearly_parallel_vectors_is_never_nan() {
+    for i in 1..100 {
+        let v = Vec4::vector(0.1 * i as f32, 0.3, 0.7).normalize();
+
+        let same = v.angle_between(&(v * 3.0));
+        let opposite = v.angle_between(&(-v));
+        // acos is steep near +-1, so a rounding error in the cosine still shows up as a small angle
+        assert!(!same.is_nan() && same < 0.001, "{:?}", v);
+        assert!(!opposite.is_nan() && PI - opposite < 0.001, "{:?}", v);
+    }
+}
+
+#[test]
+fn orthonormal_basis_is_a_right_handed_frame() {
+    let vectors = [
+        Vec4::vector(0.0, 0.0, 1.0),
+        Vec4::vector(1.0, 0.0, 0.0),
+        Vec4::vector(-3.0, 0.1, 0.0),
+        Vec4::vector(1.0, 2.0, -0.5),
+    ];
+
+    for v in vectors {
+        let (tangent, bitangent) = v.orthonormal_basis();
+        let axis = v.normalize();
+
+        assert!(util::equals_f32(&tangent.magnitude(), &1.0), "{:?}", v);
+        assert!(util::equals_f32(&bitangent.magnitude(), &1.0), "{:?}", v);
+        assert!(tangent.dot(&axis).abs() < util::THRESHOLD_F32, "{:?}", v);
+        assert!(bitangent.dot(&axis).abs() < util::THRESHOLD_F32, "{:?}", v);
+        assert!(tangent.dot(&bitangent).abs() < util::THRESHOLD_F32, "{:?}", v);
+        assert_eq!(axis.cross(&tangent), bitangent);
+    }
+}
+
+#[test]
+fn cone_samples_stay_inside_the_cone() {
+    let axis = Vec4::vector(1.0, 2.0, -0.5);
+    let cos_max = 0.8;
+
+    assert_eq!(axis.cone_sample(cos_max, 0.0, 0.3), axis.normalize());
+    for i in 0..10 {
+        let u2 = i as f32 / 10.0;
+        let rim = axis.cone_sample(cos_max, 1.0, u2);
+        assert!(util::equals_f32(&rim.dot(&axis.normalize()), &cos_max), "{:?}", rim);
+
+        let inside = axis.cone_sample(cos_max, 0.5, u2);
+        assert!(util::equals_f32(&inside.magnitude(), &1.0));
+        assert!(inside.dot(&axis.normalize()) > cos_max);
+    }
+
+    // A cosine of 0 covers the hemisphere
+    let edge = axis.cone_sample(0.0, 1.0, 0.25);
+    assert!(edge.dot(&axis).abs() < util::THRESHOLD_F32);
+}
+
+#[test]
+fn reflect_vector_approaching_at_45_degrees() {
+    let v = Vec4::vector(1.0, -1.0, 0.0);
+    let n = Vec4::vector(0.0, 1.0, 0.0);
+
+    assert_eq!(v.reflect(&n), Vec4::vector(1.0, 1.0, 0.0));
+}
+
+#[test]
+fn reflect_vector_off_slanted_surface() {
+    let half = 2.0_f32.sqrt() / 2.0;
+    let v = Vec4::vector(0.0, -1.0, 0.0);
+    let n = Vec4::vector(half, half, 0.0);
+
+    assert_eq!(v.reflect(&n), Vec4::vector(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn reflection_keeps_length_and_mirrors_the_angle() {
+    let n = Vec4::vector(1.0, 2.0, -0.5).normalize();
+
+    for i in 0..20 {
+        let angle = i as f32 * 0.3;
+        let v = Vec4::vector(angle.cos(), -2.0, angle.sin());
+        let r = v.reflect(&n);
+
+        assert!(util::equals_f32(&r.magnitude(), &v.magnitude()));
+        assert!(util::equals_f32(&r.dot(&n), &-v.dot(&n)));
+        assert!(util::equals_f32(&(-v).angle_between(&n), &r.angle_between(&n)));
+    }
+}
+
+#[test]
+fn refract_through_matching_media_is_unbent() {
+    let n = Vec4::vector(0.0, 1.0, 0.0);
+    let v = Vec4::vector(1.0, -1.0, 0.0).normalize();
+
+    assert_eq!(v.refract(&n, 1.5, 1.5), v);
+    assert_eq!(Vec4::vector(0.0, -1.0, 0.0).refract(&n, 1.0, 1.5), Vec4::vector(0.0, -1.0, 0.0));
+}
+
+#[test]
+fn refract_follows_snells_law() {
+    let n = Vec4::vector(0.0, 1.0, 0.0);
+
+    for i in 1..10 {
+        let incident = i as f32 * 0.15;
+        let v = Vec4::vector(incident.sin(), -incident.cos(), 0.0);
+        let r = v.refract(&n, 1.0, 1.5);
+
+        let transmitted = r.angle_between(&-n);
+        assert!(util::equals_f32(&r.magnitude(), &1.0));
+        assert!(util::equals_f32(&(incident.sin() * 1.0), &(transmitted.sin() * 1.5)), "incident {}", incident);
+        assert!(*r.x() > 0.0 && *r.y() < 0.0);
+    }
+}
+
+#[test]
+fn transform_builder_applies_steps_in_the_order_given() {
+    let built = TransformBuilder::new()
+        .scale(2.0, 3.0, 4.0)
+        .rotate_x(PI / 3.0)
+        .rotate_y(PI / 4.0)
+        .rotate_z(PI / 5.0)
+        .shear(0.5, 0.0, 0.0, 0.25, 0.0, 0.0)
+        .translate(1.0, -2.0, 3.0)
+        .build();
+
+    let manual = Matrix4x4::translation(1.0, -2.0, 3.0)
+        * Matrix4x4::shearing(0.5, 0.0, 0.0, 0.25, 0.0, 0.0)
+        * Matrix4x4::rotatation_z(PI / 5.0)
+        * Matrix4x4::rotatation_y(PI / 4.0)
+        * Matrix4x4::rotatation_x(PI / 3.0)
+        * Matrix4x4::scale(2.0, 3.0, 4.0);
+
+    assert!(built.approx_eq(&manual, 0.0001));
+}
+
+#[test]
+fn transform_builder_moves_a_point_step_by_step() {
+    let point = Vec4::point(1.0, 0.0, 1.0);
+    let transform = TransformBuilder::new()
+        .rotate_x(PI / 2.0)
+        .scale(5.0, 5.0, 5.0)
+        .translate(10.0, 5.0, 7.0)
+        .build();
+
+    assert_eq!(transform * point, Vec4::point(15.0, 0.0, 7.0));
+    assert_eq!(TransformBuilder::default().build(), Matrix4x4::identity());
+    assert_eq!(TransformBuilder::new().then(Matrix4x4::translation(1.0, 2.0, 3.0)).build(), Matrix4x4::translation(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn look_at_is_the_inverse_of_the_view_transformation() {
+    // With up square to the line of sight the view transformation is a pure rotation and translation
+    let eye = Vec4::point(1.0, 3.0, 2.0);
+    let target = Vec4::point(4.0, 3.0, 6.0);
+    let up = Vec4::vector(0.0, 1.0, 0.0);
+
+    let look_at = Matrix4x4::look_at(eye, target, up);
+    let view = Matrix4x4::view_transformation(eye, target, up);
+    assert!((look_at * view).approx_eq(&Matrix4x4::identity(), 0.0001));
+
+    // The local origin sits at the eye and -z runs straight at the target
+    assert_eq!(look_at * Vec4::ORIGIN, eye);
+    assert_eq!(look_at * Vec4::vector(0.0, 1.0, 0.0), up);
+    let distance = eye.distance(&target);
+    let reached = look_at * Vec4::point(0.0, 0.0, -distance);
+    assert!(reached.distance(&target) < 0.0001);
+}
+
+#[test]
+fn look_at_aims_a_cone_at_a_point() {
+    use tracer::intersection::Intersection;
+    use tracer::material::Material;
+    use tracer::ray::Ray;
+    use tracer::shape::{Cone, Shape};
+
+    // A closed cone with its tip at the origin opening along +y, turned so it opens along -z before
+    // being aimed
+    let mut cone = Cone::new(Material::default(), 0.0, 1.0, true);
+    let eye = Vec4::point(2.0, 2.0, 2.0);
+    let target = Vec4::point(-3.0, 0.0, 5.0);
+    cone.set_transform(TransformBuilder::new()
+        .rotate_x(-PI / 2.0)
+        .then(Matrix4x4::look_at(eye, target, Vec4::vector(0.0, 1.0, 0.0)))
+        .build());
+
+    // The centre of the open end lies one unit from the tip towards the target
+    let toward = (target - eye).normalize();
+    let cap_centre = *cone.transform() * Vec4::point(0.0, 1.0, 0.0);
+    assert!(cap_centre.distance(&(eye + toward)) < 0.0001);
+
+    // A ray back from the target along the axis first meets the cone at its cap
+    let ray = Ray::new(target, -toward);
+    let xs = Intersection::intersect(&cone, ray);
+    let nearest = xs.iter().map(|x| x.t).fold(f32::INFINITY, f32::min);
+    assert!((nearest - (eye.distance(&target) - 1.0)).abs() < 0.001);
+
+    // and a ray crossing the line of sight beyond the cap misses it
+    let side = Ray::new(eye + toward * 2.0 + Vec4::vector(0.0, 5.0, 0.0), Vec4::vector(0.0, -1.0, 0.0));
+    assert!(Intersection::intersect(&cone, side).is_empty());
+}
+
+#[test]
+fn three_values_convert_to_a_point() {
+    assert_eq!(Vec4::from([1.0, -2.0, 3.5]), Vec4::point(1.0, -2.0, 3.5));
+    assert_eq!(Vec4::from((1.0, -2.0, 3.5)), Vec4::point(1.0, -2.0, 3.5));
+
+    let vertices = [[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]];
+    let points: Vec<Vec4> = vertices.into_iter().map(Vec4::from).collect();
+    assert_eq!(*points[1].w(), 1.0);
+}
+
+#[test]
+fn four_values_keep_their_w() {
+    let vector: Vec4 = [1.0, 2.0, 3.0, 0.0].into();
+    assert_eq!(vector, Vec4::vector(1.0, 2.0, 3.0));
+
+    let raw = Vec4::from([1.0, 2.0, 3.0, 0.5]);
+    assert_eq!(*raw.w(), 0.5);
+
+    let elements: [f32; 4] = Vec4::point(4.0, 5.0, 6.0).into();
+    assert_eq!(elements, [4.0, 5.0, 6.0, 1.0]);
+    let elements: [f32; 4] = raw.into();
+    assert_eq!(elements, [1.0, 2.0, 3.0, 0.5]);
+}
@@ -0,0 +1,150 @@
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::group::Group;
+use tracer::intersection::Intersection;
+use tracer::material::Material;
+use tracer::model::Model;
+use tracer::pattern::StripePattern;
+use tracer::ray::Ray;
+use tracer::shape::{Plane, Shape, Sphere};
+use tracer::world::World;
+use uuid::Uuid;
+
+#[test]
+fn loads_every_obj_file_in_a_directory() {
+    let group = Group::from_obj_dir("obj_files/scene", Material::default()).unwrap();
+
+    // floor.obj and wall.OBJ, notes.txt is ignored
+    assert_eq!(group.children.len(), 2);
+
+    let down = Ray::new(Vec4::point(0.5, 1.0, -0.25), Vec4::vector(0.0, -1.0, 0.0));
+    let xs = Intersection::intersect(&group, down);
+    assert_eq!(xs.len(), 1);
+    assert!((xs[0].t - 1.0).abs() < 0.0001);
+
+    let forward = Ray::new(Vec4::point(0.0, 0.5, 0.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = Intersection::intersect(&group, forward);
+    assert_eq!(xs.len(), 1);
+    assert!((xs[0].t - 5.0).abs() < 0.0001);
+}
+
+#[test]
+fn children_take_the_group_material() {
+    let material = Material { diffuse: 0.25, ..Material::default() };
+
+    let group = Group::from_obj_dir("obj_files/scene", material).unwrap();
+
+    assert!(group.children.iter().all(|child| child.material().diffuse == 0.25));
+}
+
+#[test]
+fn reports_every_broken_file() {
+    let errors = match Group::from_obj_dir("obj_files/broken", Material::default()) {
+        Ok(_) => panic!("expected the broken directory to fail"),
+        Err(errors) => errors,
+    };
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].path.ends_with("bad_number.obj"));
+    assert!(errors[0].message.contains("line 1"));
+    assert!(errors[1].path.ends_with("missing_vertex.obj"));
+    assert!(errors[1].to_string().contains("missing_vertex.obj"));
+}
+
+#[test]
+fn missing_directory_is_an_error() {
+    let errors = Group::from_obj_dir("obj_files/does_not_exist", Material::default()).err().unwrap();
+
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn group_transform_moves_its_children() {
+    let mut group = Group::new(Material::default());
+    group.set_transform(Matrix4x4::translation(0.0, 0.0, 5.0));
+    group.add_child(Box::new(Sphere::new(Material::default())));
+    group.set_transform(Matrix4x4::translation(0.0, 0.0, 10.0));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = Intersection::intersect(&group, ray);
+
+    assert_eq!(xs.len(), 2);
+    assert!((xs[0].t - 9.0).abs() < 0.0001 && (xs[1].t - 11.0).abs() < 0.0001);
+    assert!(xs.iter().all(|x| x.object.id() == group.children[0].id()));
+}
+
+#[test]
+fn world_iterates_leaf_shapes_depth_first() {
+    let sphere = Sphere::new(Material::default());
+    let inner = Sphere::new(Material::default());
+    let plane = Plane::new(Material::default());
+    let model = Model::new(Material::default(), "obj_files/crlf_triangle.obj");
+
+    let expected = vec![
+        *sphere.id(),
+        *inner.id(),
+        *model.triangles[0].id(),
+        *model.triangles[1].id(),
+        *plane.id(),
+    ];
+
+    let mut group = Group::new(Material::default());
+    group.add_child(Box::new(inner));
+    group.add_child(Box::new(model));
+
+    let mut world = World::new();
+    world.add_object(Box::new(sphere));
+    world.add_object(Box::new(group));
+    world.add_object(Box::new(Group::new(Material::default())));
+    world.add_object(Box::new(plane));
+
+    let ids: Vec<Uuid> = world.iter_shapes().map(|shape| *shape.id()).collect();
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn leaf_shapes_have_no_children() {
+    assert!(Sphere::new(Material::default()).children().is_none());
+    assert_eq!(Group::new(Material::default()).children().unwrap().len(), 0);
+}
+
+#[test]
+fn animating_a_group_keeps_children_in_step() {
+    let mut group = Group::new(Material::default());
+    group.add_child(Box::new(Sphere::new(Material::default())));
+
+    for frame in 1..=3 {
+        let matrix = Matrix4x4::translation(0.0, frame as f32, 0.0) * Matrix4x4::rotatation_y(frame as f32 * 0.3);
+        group.set_transform(matrix);
+
+        assert!(group.inverse_transform().approx_eq(&matrix.invert(), 0.0001));
+        assert!(group.children[0].transform().approx_eq(&matrix, 0.0001), "frame {}", frame);
+    }
+}
+
+#[test]
+fn material_is_set_on_every_leaf() {
+    let mut inner = Group::new(Material::default());
+    inner.add_child(Box::new(Model::new(Material::default(), "obj_files/crlf_triangle.obj")));
+    inner.add_child(Box::new(Sphere::new(Material::default())));
+
+    let mut group = Group::new(Material::default());
+    group.add_child(Box::new(inner));
+    group.add_child(Box::new(Plane::new(Material::default())));
+
+    let mut material = Material::matte(Color::new(1.0, 0.0, 0.0));
+    material.pattern = Some(Box::new(StripePattern::default()));
+    group.set_material_recursive(&material);
+
+    assert_eq!(group.material.color, Color::new(1.0, 0.0, 0.0));
+
+    let mut world = World::new();
+    world.add_object(Box::new(group));
+    assert_eq!(world.iter_shapes().count(), 4);
+
+    for shape in world.iter_shapes() {
+        assert_eq!(shape.material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(shape.material().specular, 0.0);
+        assert!(shape.material().pattern.is_some());
+    }
+}
@@ -0,0 +1,58 @@
+use tracer::color::Color;
+use tracer::geometry::Vec4;
+use tracer::group::Group;
+use tracer::id::{self, IdGenerator};
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::model::Model;
+use tracer::sdf::SdfShape;
+use tracer::shape::{Cone, Cube, Cylinder, Plane, Shape, Sphere};
+use uuid::Uuid;
+
+#[test]
+fn shapes_can_be_built_with_a_given_id() {
+    let id = Uuid::from_u128(42);
+
+    assert_eq!(*Sphere::with_id(Material::default(), id).id(), id);
+    assert_eq!(*Plane::with_id(Material::default(), id).id(), id);
+    assert_eq!(*Cube::with_id(Material::default(), id).id(), id);
+    assert_eq!(*Cylinder::with_id(Material::default(), -1.0, 1.0, true, id).id(), id);
+    assert_eq!(*Cone::with_id(Material::default(), -1.0, 0.0, false, id).id(), id);
+    assert_eq!(*Group::with_id(Material::default(), id).id(), id);
+    assert_eq!(*Model::with_id(Material::default(), "obj_files/crlf_triangle.obj", id).id(), id);
+    assert_eq!(*SdfShape::with_id(Material::default(), |point: Vec4| point.magnitude() - 1.0, id).id(), id);
+}
+
+#[test]
+fn generator_counts_up_from_its_start() {
+    let mut generator = IdGenerator::sequential(10);
+
+    assert_eq!(generator.next_id(), Uuid::from_u128(10));
+    assert_eq!(generator.next_id(), Uuid::from_u128(11));
+}
+
+#[test]
+fn sequential_ids_make_scenes_reproducible() {
+    let build = || -> Vec<Uuid> {
+        id::use_sequential_ids(1);
+        let sphere = Sphere::new(Material::default());
+        let plane = Plane::new(Material::default());
+        let light = Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::white());
+        id::use_random_ids();
+
+        return vec![*sphere.id(), *plane.id(), light.id];
+    };
+
+    let first = build();
+    assert_eq!(first, vec![Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3)]);
+    assert_eq!(build(), first);
+}
+
+#[test]
+fn ids_are_random_by_default() {
+    let a = Sphere::new(Material::default());
+    let b = Sphere::new(Material::default());
+
+    assert_ne!(a.id(), b.id());
+    assert_eq!(a.id().get_version_num(), 4);
+}
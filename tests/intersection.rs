@@ -0,0 +1,285 @@
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::intersection::{refractive_indices, Intersection};
+use tracer::material::Material;
+use tracer::ray::Ray;
+use tracer::shape::{Plane, Shape, Sphere};
+use tracer::world::World;
+use tracer::util;
+
+#[test]
+fn schlick_under_total_internal_reflection() {
+    let shape = Sphere::glass_sphere();
+    let half = 2.0_f32.sqrt() / 2.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, half), Vec4::vector(0.0, 1.0, 0.0));
+    let xs = vec![Intersection::new(&shape, -half), Intersection::new(&shape, half)];
+    let comp = xs[1].prepare_computations(&ray, Some(&xs));
+
+    assert!(util::equals_f32(&comp.schlick(), &1.0));
+}
+
+#[test]
+fn schlick_with_perpendicular_viewing_angle() {
+    let shape = Sphere::glass_sphere();
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.0), Vec4::vector(0.0, 1.0, 0.0));
+    let xs = vec![Intersection::new(&shape, -1.0), Intersection::new(&shape, 1.0)];
+    let comp = xs[1].prepare_computations(&ray, Some(&xs));
+
+    assert!(util::equals_f32(&comp.schlick(), &0.04));
+}
+
+#[test]
+fn schlick_with_small_angle_and_n2_greater_than_n1() {
+    let shape = Sphere::glass_sphere();
+
+    let ray = Ray::new(Vec4::point(0.0, 0.99, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = vec![Intersection::new(&shape, 1.8589)];
+    let comp = xs[0].prepare_computations(&ray, Some(&xs));
+
+    assert!(util::equals_f32(&comp.schlick(), &0.48873));
+}
+
+#[test]
+fn refractive_indices_at_overlapping_glass_spheres() {
+    let mut a = Sphere::glass_sphere();
+    a.set_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+    a.material.refraction = 1.5;
+
+    let mut b = Sphere::glass_sphere();
+    b.set_transform(Matrix4x4::translation(0.0, 0.0, -0.25));
+    b.material.refraction = 2.0;
+
+    let mut c = Sphere::glass_sphere();
+    c.set_transform(Matrix4x4::translation(0.0, 0.0, 0.25));
+    c.material.refraction = 2.5;
+
+    let xs = vec![
+        Intersection::new(&a, 2.0),
+        Intersection::new(&b, 2.75),
+        Intersection::new(&c, 3.25),
+        Intersection::new(&b, 4.75),
+        Intersection::new(&c, 5.25),
+        Intersection::new(&a, 6.0),
+    ];
+
+    let expected = [(1.0, 1.5), (1.5, 2.0), (2.0, 2.5), (2.5, 2.5), (2.5, 1.5), (1.5, 1.0)];
+
+    for (i, (n1, n2)) in expected.iter().enumerate() {
+        assert_eq!(refractive_indices(&xs[i], &xs), (*n1, *n2), "intersection {}", i);
+    }
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -4.0), Vec4::vector(0.0, 0.0, 1.0));
+    let comp = xs[2].prepare_computations(&ray, Some(&xs));
+    assert_eq!((comp.n1, comp.n2), (2.0, 2.5));
+}
+
+#[test]
+fn refractive_indices_ignore_intersection_order() {
+    let mut a = Sphere::glass_sphere();
+    a.set_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+
+    let mut b = Sphere::glass_sphere();
+    b.material.refraction = 2.0;
+
+    let xs = vec![
+        Intersection::new(&b, 1.0),
+        Intersection::new(&a, 2.0),
+        Intersection::new(&b, -1.0),
+        Intersection::new(&a, -2.0),
+    ];
+
+    assert_eq!(refractive_indices(&xs[0], &xs), (2.0, 1.5));
+    assert_eq!(refractive_indices(&xs[1], &xs), (1.5, 1.0));
+}
+
+#[test]
+fn camera_inside_glass_sphere() {
+    let mut world = World::new();
+    let mut sphere = Sphere::glass_sphere();
+    sphere.set_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+    world.add_object(Box::new(sphere));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.0), Vec4::vector(0.0, 0.0, 1.0));
+    let mut xs = world.intersect_world(ray);
+    let all = xs.clone();
+    let hit = Intersection::hit(&mut xs).unwrap();
+    let comp = hit.prepare_computations(&ray, Some(&all));
+
+    assert!(comp.inside);
+    assert_eq!((comp.n1, comp.n2), (1.5, 1.0));
+}
+
+#[test]
+fn camera_inside_nested_glass() {
+    let mut world = World::new();
+    let mut outer = Sphere::glass_sphere();
+    outer.set_transform(Matrix4x4::scale(4.0, 4.0, 4.0));
+    outer.material.refraction = 1.33;
+    world.add_object(Box::new(outer));
+
+    let mut inner = Sphere::glass_sphere();
+    inner.set_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+    world.add_object(Box::new(inner));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.0), Vec4::vector(0.0, 0.0, 1.0));
+    let all = world.intersect_world(ray);
+    let positive: Vec<Intersection> = all.iter().copied().filter(|x| x.t > 0.0).collect();
+
+    let first = positive[0].prepare_computations(&ray, Some(&all));
+    assert_eq!((first.n1, first.n2), (1.5, 1.33));
+
+    let second = positive[1].prepare_computations(&ray, Some(&all));
+    assert_eq!((second.n1, second.n2), (1.33, 1.0));
+}
+
+#[test]
+fn schlick_exiting_glass_perpendicular() {
+    let shape = Sphere::glass_sphere();
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.0), Vec4::vector(0.0, 1.0, 0.0));
+    let xs = vec![Intersection::new(&shape, -1.0), Intersection::new(&shape, 1.0)];
+    let comp = xs[1].prepare_computations(&ray, Some(&xs));
+
+    assert!(comp.inside);
+    assert_eq!((comp.n1, comp.n2), (1.5, 1.0));
+    assert_eq!(comp.normalv, Vec4::vector(0.0, -1.0, 0.0));
+    assert!(util::equals_f32(&comp.schlick(), &0.04));
+}
+
+#[test]
+fn schlick_exiting_glass_at_an_angle() {
+    let shape = Sphere::glass_sphere();
+    let exit = (1.0_f32 - 0.09).sqrt();
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.3), Vec4::vector(0.0, 1.0, 0.0));
+    let xs = vec![Intersection::new(&shape, -exit), Intersection::new(&shape, exit)];
+    let comp = xs[1].prepare_computations(&ray, Some(&xs));
+
+    assert!(comp.inside);
+    assert!(comp.eyev.dot(&comp.normalv) > 0.0);
+    assert!(util::equals_f32(&comp.schlick(), &0.0400134));
+}
+
+#[test]
+fn schlick_is_symmetric_for_perpendicular_entry_and_exit() {
+    let shape = Sphere::glass_sphere();
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = vec![Intersection::new(&shape, 1.0), Intersection::new(&shape, 3.0)];
+    let entering = xs[0].prepare_computations(&ray, Some(&xs));
+    let exiting = xs[1].prepare_computations(&ray, Some(&xs));
+
+    assert!(!entering.inside && exiting.inside);
+    assert!(util::equals_f32(&entering.schlick(), &exiting.schlick()));
+}
+
+#[test]
+fn coincident_hits_go_to_the_lowest_id() {
+    let low = Plane::with_id(Material::default(), uuid::Uuid::from_u128(1));
+    let high = Plane::with_id(Material::default(), uuid::Uuid::from_u128(2));
+
+    let mut xs = vec![Intersection::new(&high, 2.0), Intersection::new(&low, 2.0)];
+    assert_eq!(*Intersection::hit(&mut xs).unwrap().object.id(), *low.id());
+
+    let mut xs = vec![Intersection::new(&low, 2.0), Intersection::new(&high, 2.0)];
+    assert_eq!(*Intersection::hit(&mut xs).unwrap().object.id(), *low.id());
+
+    // Rounding error in either direction is still a tie
+    let mut xs = vec![Intersection::new(&high, 1.999999), Intersection::new(&low, 2.000001)];
+    assert_eq!(*Intersection::hit(&mut xs).unwrap().object.id(), *low.id());
+
+    // A genuinely nearer surface still wins
+    let mut xs = vec![Intersection::new(&high, 1.9), Intersection::new(&low, 2.0)];
+    assert_eq!(*Intersection::hit(&mut xs).unwrap().object.id(), *high.id());
+}
+
+#[test]
+fn touching_planes_show_one_color_everywhere() {
+    let mut world = World::new();
+    world.add_light(tracer::light::Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::white()));
+
+    // Reaches the same plane through a different transform, so its ts differ by rounding
+    let mut upper = Plane::with_id(Material::matte(Color::RED), uuid::Uuid::from_u128(2));
+    upper.set_transform(Matrix4x4::translation(0.0, 0.3, 0.0) * Matrix4x4::rotatation_y(0.7) * Matrix4x4::translation(0.0, -0.3, 0.0));
+    world.add_object(Box::new(upper));
+    world.add_object(Box::new(Plane::with_id(Material::matte(Color::BLUE), uuid::Uuid::from_u128(1))));
+
+    for i in 0..50 {
+        let x = i as f32 * 0.37 - 9.0;
+        let ray = Ray::new(Vec4::point(x, 5.0, -5.0), Vec4::vector(0.1, -1.0, 0.3).normalize());
+        let color = world.color_at(ray, 5);
+        assert_eq!(*color.r(), 0.0, "ray {} hit the red plane", i);
+    }
+}
+
+#[test]
+fn glass_stack_scene_refractive_indices() {
+    let world = World::glass_stack_scene();
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -4.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = world.intersect_world(ray);
+
+    let expected = [
+        (0, 2.0, 1.0, 1.5),
+        (1, 2.75, 1.5, 2.0),
+        (2, 3.25, 2.0, 2.5),
+        (1, 4.75, 2.5, 2.5),
+        (2, 5.25, 2.5, 1.5),
+        (0, 6.0, 1.5, 1.0),
+    ];
+    assert_eq!(xs.len(), expected.len());
+
+    for (i, (object, t, n1, n2)) in expected.iter().enumerate() {
+        assert_eq!(xs[i].object.id(), world.objects[*object].id(), "intersection {}", i);
+        assert!(util::equals_f32(&xs[i].t, t), "intersection {}", i);
+
+        let comp = xs[i].prepare_computations(&ray, Some(&xs));
+        assert_eq!((comp.n1, comp.n2), (*n1, *n2), "intersection {}", i);
+    }
+}
+
+#[test]
+fn ray_through_a_mesh_edge_crosses_it_once() {
+    let glass = Material { transparency: 1.0, refraction: 1.5, ..Material::default() };
+    let model = tracer::model::Model::new(glass, "obj_files/tetrahedron.stl");
+
+    let mut backdrop = Sphere::glass_sphere();
+    backdrop.set_transform(Matrix4x4::translation(0.5, 1.5, 1.5) * Matrix4x4::scale(0.5, 0.5, 0.5));
+    backdrop.material.refraction = 2.0;
+
+    let mut world = World::new();
+    world.add_object(Box::new(model));
+    world.add_object(Box::new(backdrop));
+
+    // Enters through the edge shared by the bottom and front faces, leaves through the slanted face
+    let ray = Ray::new(Vec4::point(0.5, -1.0, -1.0), Vec4::vector(0.0, 1.0, 1.0));
+    let xs = world.intersect_world(ray);
+    let on_mesh: Vec<&Intersection> = xs.iter().filter(|x| x.object.id() == world.objects[0].id()).collect();
+    assert_eq!(on_mesh.len(), 3);
+    assert!(util::equals_f32(&on_mesh[0].t, &on_mesh[1].t));
+
+    let expected = [(1.0, 1.5), (1.0, 1.5), (1.5, 1.0), (1.0, 2.0), (2.0, 1.0)];
+    assert_eq!(xs.len(), expected.len());
+    for (i, (n1, n2)) in expected.iter().enumerate() {
+        assert_eq!(refractive_indices(&xs[i], &xs), (*n1, *n2), "intersection {}", i);
+    }
+}
+
+#[test]
+fn grazing_hits_on_one_shape_still_enter_and_leave() {
+    let mut a = Sphere::glass_sphere();
+    a.material.refraction = 2.0;
+    let b = Sphere::glass_sphere();
+
+    // A tangent ray reports the same t twice, then the ray goes on into b
+    let xs = vec![
+        Intersection::new(&a, 1.0),
+        Intersection::new(&a, 1.0),
+        Intersection::new(&b, 3.0),
+        Intersection::new(&b, 5.0),
+    ];
+
+    assert_eq!(refractive_indices(&xs[2], &xs), (1.0, 1.5));
+    assert_eq!(refractive_indices(&xs[3], &xs), (1.5, 1.0));
+}
@@ -0,0 +1,256 @@
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::group::Group;
+use tracer::model::Model;
+use tracer::ray::Ray;
+use tracer::shape::{Cube, Plane, Shape, Sphere};
+use tracer::util;
+use tracer::world::World;
+
+#[test]
+fn point_light_samples_its_position() {
+    let world = World::new();
+    let light = Light::point_light(Vec4::point(1.0, 2.0, 3.0), Color::new(1.0, 1.0, 1.0));
+
+    let points = light.sample_points(&world, 4);
+
+    assert_eq!(points, vec![Vec4::point(1.0, 2.0, 3.0)]);
+}
+
+#[test]
+fn shape_light_samples_lie_on_the_sphere() {
+    let mut world = World::new();
+    let mut sphere = Sphere::new(Material::default());
+    sphere.set_transform(Matrix4x4::translation(0.0, 5.0, 0.0) * Matrix4x4::scale(2.0, 2.0, 2.0));
+    let id = *sphere.id();
+    world.add_object(Box::new(sphere));
+
+    let light = Light::from_shape(id, Color::new(1.0, 1.0, 1.0));
+    let points = light.sample_points(&world, 4);
+
+    assert_eq!(points.len(), 16);
+    for point in points {
+        let distance = (point - Vec4::point(0.0, 5.0, 0.0)).magnitude();
+        assert!((distance - 2.0).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn shape_light_samples_lie_on_the_cube() {
+    let mut world = World::new();
+    let cube = Cube::new(Material::default());
+    let id = *cube.id();
+    world.add_object(Box::new(cube));
+
+    let light = Light::from_shape(id, Color::new(1.0, 1.0, 1.0));
+
+    for point in light.sample_points(&world, 6) {
+        let maxc = util::max_f32(&[point.x().abs(), point.y().abs(), point.z().abs()]).unwrap();
+        assert!(util::equals_f32(&maxc, &1.0));
+    }
+}
+
+#[test]
+fn shape_light_with_missing_shape_falls_back_to_position() {
+    let world = World::demo();
+    let light = Light::from_shape(uuid::Uuid::new_v4(), Color::new(1.0, 1.0, 1.0));
+
+    assert_eq!(light.sample_points(&world, 3), vec![Vec4::point(0.0, 0.0, 0.0)]);
+}
+
+#[test]
+fn temperature_light_is_scaled_by_intensity() {
+    let light = Light::from_temperature(Vec4::point(0.0, 5.0, 0.0), 3200.0, 2.0);
+
+    assert_eq!(light.position, Vec4::point(0.0, 5.0, 0.0));
+    assert_eq!(light.intensity, Color::from_temperature(3200.0) * 2.0);
+    assert_eq!(*light.intensity.r(), 2.0);
+}
+
+#[test]
+fn sphere_light_samples_face_the_target() {
+    let center = Vec4::point(0.0, 10.0, 0.0);
+    let light = Light::sphere(center, 2.0, Color::white(), 4);
+    let target = Vec4::point(3.0, 0.0, 1.0);
+
+    let points = light.sample_points_toward(&target);
+    assert_eq!(points.len(), 16);
+
+    for point in points {
+        assert!(util::equals_f32(&(point - center).magnitude(), &2.0));
+        assert!((point - center).dot(&(target - center)) > 0.0);
+    }
+}
+
+#[test]
+fn sphere_light_of_radius_zero_is_a_point_light() {
+    let position = Vec4::point(-10.0, 10.0, -10.0);
+    let sphere = Light::sphere(position, 0.0, Color::white(), 4);
+    assert_eq!(sphere.sample_points_toward(&Vec4::ORIGIN), vec![position]);
+
+    let mut point_world = World::demo();
+    point_world.lights = vec![Light::point_light(position, Color::white())];
+    let mut sphere_world = World::demo();
+    sphere_world.lights = vec![sphere];
+
+    for x in 0..10 {
+        let ray = tracer::ray::Ray::new(Vec4::point(-1.0 + x as f32 * 0.2, 0.3, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+        assert_eq!(sphere_world.color_at(ray, 5), point_world.color_at(ray, 5));
+    }
+}
+
+// Fraction of points along the x axis under a ball at height 5 that are partly lit
+fn penumbra_points(radius: f32) -> usize {
+    let mut world = World::new();
+    let mut ball = Sphere::new(Material::default());
+    ball.set_transform(Matrix4x4::translation(0.0, 5.0, 0.0));
+    world.add_object(Box::new(ball));
+
+    let light = Light::sphere(Vec4::point(0.0, 10.0, 0.0), radius, Color::white(), 8);
+    assert_eq!(world.intensity_at(&Vec4::ORIGIN, &light), 0.0);
+    assert_eq!(world.intensity_at(&Vec4::point(20.0, 0.0, 0.0), &light), 1.0);
+
+    return (0..200)
+        .map(|i| world.intensity_at(&Vec4::point(i as f32 * 0.05, 0.0, 0.0), &light))
+        .filter(|visible| *visible > 0.0 && *visible < 1.0)
+        .count();
+}
+
+#[test]
+fn penumbra_widens_with_light_radius() {
+    let small = penumbra_points(0.5);
+    let large = penumbra_points(2.0);
+
+    assert!(small > 0);
+    assert!(large > small * 2, "{} vs {}", large, small);
+}
+
+#[test]
+fn each_light_is_shadowed_separately() {
+    let mut world = World::new();
+    let mut floor_material = Material::matte(Color::white());
+    floor_material.ambient = 0.0;
+    world.add_object(Box::new(tracer::shape::Plane::new(floor_material)));
+
+    // Blocks the light straight above the origin but not the one off to the side
+    let mut blocker = Sphere::new(Material::default());
+    blocker.set_transform(Matrix4x4::translation(0.0, 5.0, 0.0));
+    world.add_object(Box::new(blocker));
+
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::white()));
+    world.add_light(Light::point_light(Vec4::point(10.0, 10.0, 0.0), Color::white()));
+
+    let ray = tracer::ray::Ray::new(Vec4::point(0.0, 1.0, -1.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+    let diffuse_from_side = 0.9 * 2.0_f32.sqrt() / 2.0;
+    assert_eq!(world.color_at(ray, 5), Color::new(diffuse_from_side, diffuse_from_side, diffuse_from_side));
+}
+
+#[test]
+fn total_lights_intensity_sums_every_light() {
+    let mut world = World::new();
+    assert_eq!(world.total_lights_intensity(), Color::black());
+
+    world.add_light(Light::point_light(Vec4::point(0.0, 1.0, 0.0), Color::new(1.0, 0.5, 0.25)));
+    world.add_light(Light::point_light(Vec4::point(1.0, 0.0, 0.0), Color::new(0.5, 0.5, 0.5)));
+    assert_eq!(world.total_lights_intensity(), Color::new(1.5, 1.0, 0.75));
+}
+
+// A matte floor under a small glowing ball at (0, 4, 0), which is the world's only light
+fn ball_light_world(in_group: bool) -> World {
+    let mut world = World::new();
+    let mut floor_material = Material::matte(Color::white());
+    floor_material.ambient = 0.0;
+    world.add_object(Box::new(Plane::new(floor_material)));
+
+    let mut ball = Sphere::new(Material::default());
+    ball.set_transform(Matrix4x4::translation(0.0, 4.0, 0.0) * Matrix4x4::scale(0.25, 0.25, 0.25));
+    let id = *ball.id();
+    if in_group {
+        let mut group = Group::new(Material::default());
+        group.add_child(Box::new(ball));
+        world.add_object(Box::new(group));
+    } else {
+        world.add_object(Box::new(ball));
+    }
+
+    world.add_light(Light::from_shape(id, Color::white()));
+    return world;
+}
+
+#[test]
+fn shape_light_shines_from_its_surface() {
+    let world = ball_light_world(false);
+    let light = &world.lights[0];
+
+    // The lower half of the ball can see the floor below it, its own upper half is hidden behind it
+    let visible = world.intensity_at(&Vec4::point(0.0, 0.0001, 0.0), light);
+    assert!(visible > 0.3 && visible < 0.7, "{} of the ball is visible", visible);
+    assert!(!world.is_shadowed_from(&Vec4::point(0.0, 0.0001, 0.0), light));
+
+    // Lit from above rather than from the world origin, which is level with the floor
+    let ray = Ray::new(Vec4::point(0.0, 1.0, -1.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+    let below = world.color_at(ray, 5);
+    assert!(*below.r() > 0.3, "{:?}", below);
+
+    // Further out along the floor the light arrives at a grazing angle
+    let ray = Ray::new(Vec4::point(6.0, 1.0, -1.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+    assert!(*world.color_at(ray, 5).r() < *below.r());
+}
+
+#[test]
+fn shape_light_is_blocked_by_other_shapes() {
+    let mut world = ball_light_world(false);
+    let mut blocker = Sphere::new(Material::default());
+    blocker.set_transform(Matrix4x4::translation(0.0, 2.0, 0.0));
+    world.add_object(Box::new(blocker));
+
+    let point = Vec4::point(0.0, 0.0001, 0.0);
+    assert_eq!(world.intensity_at(&point, &world.lights[0]), 0.0);
+    assert!(world.is_shadowed_from(&point, &world.lights[0]));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.5, -0.5), Vec4::vector(0.0, -1.0, 1.0).normalize());
+    assert_eq!(world.color_at(ray, 5), Color::black());
+}
+
+#[test]
+fn shape_light_can_be_part_of_a_group() {
+    let world = ball_light_world(true);
+    let light = &world.lights[0];
+
+    let points = light.sample_points(&world, 3);
+    assert_eq!(points.len(), 9);
+    for point in points {
+        assert!(util::equals_f32(&(point - Vec4::point(0.0, 4.0, 0.0)).magnitude(), &0.25));
+    }
+
+    let flat = ball_light_world(false);
+    let ray = Ray::new(Vec4::point(0.0, 1.0, -1.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+    assert_eq!(world.color_at(ray, 5), flat.color_at(ray, 5));
+}
+
+#[test]
+fn shape_light_can_be_a_triangle_of_a_model() {
+    let mut model = Model::new(Material::default(), "obj_files/crlf_triangle.obj");
+    model.set_transform(Matrix4x4::translation(0.0, 4.0, 0.0));
+    let triangle_id = *model.triangles[0].id();
+
+    // Inside a group as well, whose transform is baked into the model
+    let mut group = Group::new(Material::default());
+    group.add_child(Box::new(model));
+    group.set_transform(Matrix4x4::translation(2.0, 0.0, 0.0));
+
+    let mut world = World::new();
+    world.add_object(Box::new(group));
+    world.add_light(Light::from_shape(triangle_id, Color::white()));
+
+    // The triangle spans (0, 1, 0), (-1, 0, 0) and (1, 0, 0) in model space
+    let points = world.lights[0].sample_points(&world, 3);
+    assert_eq!(points.len(), 9);
+    for point in points {
+        assert!(*point.x() >= 1.0 && *point.x() <= 3.0, "{:?}", point);
+        assert!(*point.y() >= 4.0 && *point.y() <= 5.0, "{:?}", point);
+        assert!(point.z().abs() < util::THRESHOLD_F32, "{:?}", point);
+    }
+}
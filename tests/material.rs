@@ -0,0 +1,205 @@
+use tracer::color::Color;
+use tracer::geometry::Vec4;
+use tracer::intersection::Comp;
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::shape::Sphere;
+
+#[test]
+fn lighting_with_eye_in_the_path_of_the_reflection() {
+    let sphere = Sphere::new(Material::default());
+    let material = Material::default();
+    let light = Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::white());
+
+    let half = 2.0_f32.sqrt() / 2.0;
+    let eyev = Vec4::vector(0.0, -half, -half);
+    let normalv = Vec4::vector(0.0, 0.0, -1.0);
+
+    let color = material.lighting(&sphere, &light, &Vec4::ORIGIN, &eyev, &normalv, false);
+    assert_eq!(color, Color::new(1.63639, 1.63639, 1.63639));
+}
+
+#[test]
+fn matte_material_has_no_specular_highlight() {
+    let sphere = Sphere::new(Material::default());
+    let material = Material::matte(Color::new(1.0, 0.5, 0.25));
+    let light = Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::white());
+    let normalv = Vec4::vector(0.0, 0.0, -1.0);
+
+    assert_eq!(material.specular, 0.0);
+
+    // Diffuse term for a light 45 degrees off the normal
+    let expected = Color::new(1.0, 0.5, 0.25) * (0.1 + 0.9 * 2.0_f32.sqrt() / 2.0);
+
+    for i in 0..=20 {
+        let angle = -std::f32::consts::PI / 2.0 + i as f32 * std::f32::consts::PI / 20.0;
+        let eyev = Vec4::vector(0.0, angle.sin(), -angle.cos());
+
+        let color = material.lighting(&sphere, &light, &Vec4::ORIGIN, &eyev, &normalv, false);
+        assert_eq!(color, expected, "angle {}", angle);
+    }
+}
+
+// The canonical cases from the book: a surface at the origin facing -z, lit and viewed from various
+// angles, built as a Comp so the lighting sees exactly the geometry under test
+fn lighting_at(light_position: Vec4, eyev: Vec4, in_shadow: bool) -> Color {
+    let sphere = Sphere::new(Material::default());
+    let light = Light::point_light(light_position, Color::white());
+    let comp = Comp::from_surface(&sphere, Vec4::ORIGIN, eyev, Vec4::vector(0.0, 0.0, -1.0));
+
+    return comp.object.material().lighting(comp.object, &light, &comp.point, &comp.eyev, &comp.normalv, in_shadow);
+}
+
+#[test]
+fn lighting_with_eye_between_light_and_surface() {
+    let color = lighting_at(Vec4::point(0.0, 0.0, -10.0), Vec4::vector(0.0, 0.0, -1.0), false);
+    assert_eq!(color, Color::new(1.9, 1.9, 1.9));
+}
+
+#[test]
+fn lighting_with_eye_offset_45_degrees() {
+    let half = 2.0_f32.sqrt() / 2.0;
+    let color = lighting_at(Vec4::point(0.0, 0.0, -10.0), Vec4::vector(0.0, half, -half), false);
+    assert_eq!(color, Color::new(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn lighting_with_light_offset_45_degrees() {
+    let color = lighting_at(Vec4::point(0.0, 10.0, -10.0), Vec4::vector(0.0, 0.0, -1.0), false);
+    assert_eq!(color, Color::new(0.7364, 0.7364, 0.7364));
+}
+
+#[test]
+fn lighting_with_light_behind_surface() {
+    let color = lighting_at(Vec4::point(0.0, 0.0, 10.0), Vec4::vector(0.0, 0.0, -1.0), false);
+    assert_eq!(color, Color::new(0.1, 0.1, 0.1));
+}
+
+#[test]
+fn lighting_with_surface_in_shadow() {
+    let color = lighting_at(Vec4::point(0.0, 0.0, -10.0), Vec4::vector(0.0, 0.0, -1.0), true);
+    assert_eq!(color, Color::new(0.1, 0.1, 0.1));
+}
+
+#[test]
+fn comp_from_surface_derives_the_rest_of_the_hit() {
+    let sphere = Sphere::new(Material::default());
+    let half = 2.0_f32.sqrt() / 2.0;
+    let comp = Comp::from_surface(&sphere, Vec4::ORIGIN, Vec4::vector(0.0, half, -half), Vec4::vector(0.0, 0.0, -1.0));
+
+    assert_eq!(comp.reflectv, Vec4::vector(0.0, -half, -half));
+    assert!(!comp.inside);
+    assert_eq!((comp.n1, comp.n2), (1.0, 1.0));
+    assert!(*comp.over_point.z() < 0.0);
+
+    // A normal facing away from the eye is flipped, as for a hit from inside the object
+    let comp = Comp::from_surface(&sphere, Vec4::ORIGIN, Vec4::vector(0.0, 0.0, -1.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert!(comp.inside);
+    assert_eq!(comp.normalv, Vec4::vector(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn lighting_components_sum_to_lighting() {
+    let material = Material::default();
+    let light = Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::new(1.0, 0.8, 0.6));
+    let normalv = Vec4::vector(0.0, 0.0, -1.0);
+    let color = Color::new(0.9, 0.5, 0.3);
+
+    for i in 0..=20 {
+        let angle = -std::f32::consts::PI / 2.0 + i as f32 * std::f32::consts::PI / 20.0;
+        let eyev = Vec4::vector(0.0, angle.sin(), -angle.cos());
+
+        for in_shadow in [false, true] {
+            let (ambient, diffuse, specular) = material.lighting_components(color, &light, &Vec4::ORIGIN, &eyev, &normalv, in_shadow);
+            let total = material.lighting_with_color(color, &light, &Vec4::ORIGIN, &eyev, &normalv, in_shadow);
+            assert_eq!(ambient + diffuse + specular, total, "angle {} shadow {}", angle, in_shadow);
+        }
+    }
+}
+
+#[test]
+fn lighting_components_separate_the_terms() {
+    let material = Material::default();
+    let light = Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white());
+    let eyev = Vec4::vector(0.0, 0.0, -1.0);
+    let normalv = Vec4::vector(0.0, 0.0, -1.0);
+
+    let (ambient, diffuse, specular) = material.lighting_components(Color::white(), &light, &Vec4::ORIGIN, &eyev, &normalv, false);
+    assert_eq!(ambient, Color::new(0.1, 0.1, 0.1));
+    assert_eq!(diffuse, Color::new(0.9, 0.9, 0.9));
+    assert_eq!(specular, Color::new(0.9, 0.9, 0.9));
+
+    // In shadow only ambient remains, a matte surface never has a specular term
+    let (ambient, diffuse, specular) = material.lighting_components(Color::white(), &light, &Vec4::ORIGIN, &eyev, &normalv, true);
+    assert_eq!((ambient, diffuse, specular), (Color::new(0.1, 0.1, 0.1), Color::black(), Color::black()));
+
+    let matte = Material::matte(Color::white());
+    let (_, diffuse, specular) = matte.lighting_components(Color::white(), &light, &Vec4::ORIGIN, &eyev, &normalv, false);
+    assert_eq!((diffuse, specular), (Color::new(0.9, 0.9, 0.9), Color::black()));
+}
+
+// (ambient, diffuse, specular, shininess, reflective, transparency, refraction)
+fn parameters(material: &Material) -> (f32, f32, f32, f32, f32, f32, f32) {
+    return (
+        material.ambient,
+        material.diffuse,
+        material.specular,
+        material.shininess,
+        material.reflective,
+        material.transparency,
+        material.refraction,
+    );
+}
+
+#[test]
+fn material_presets() {
+    let gold = Color::new(1.0, 0.8, 0.3);
+
+    let plastic = Material::plastic(gold);
+    assert_eq!(plastic.color, gold);
+    assert_eq!(parameters(&plastic), (0.1, 0.8, 0.5, 150.0, 0.0, 0.0, 1.0));
+
+    let metal = Material::metal(gold);
+    assert_eq!(metal.color, gold);
+    assert_eq!(parameters(&metal), (0.1, 0.3, 0.9, 300.0, 0.7, 0.0, 1.0));
+
+    let glass = Material::glass();
+    assert_eq!(parameters(&glass), (0.0, 0.1, 1.0, 300.0, 0.9, 0.9, 1.5));
+
+    let mirror = Material::mirror();
+    assert_eq!(mirror.color, Color::black());
+    assert_eq!(parameters(&mirror), (0.0, 0.0, 1.0, 300.0, 1.0, 0.0, 1.0));
+
+    let rubber = Material::rubber(gold);
+    assert_eq!(rubber.color, gold);
+    assert_eq!(parameters(&rubber), (0.1, 0.9, 0.1, 10.0, 0.0, 0.0, 1.0));
+}
+
+#[test]
+fn rubber_highlight_is_broader_and_dimmer_than_plastic() {
+    let light = Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white());
+    let normalv = Vec4::vector(0.0, 0.0, -1.0);
+
+    let specular = |material: &Material, angle: f32| {
+        let eyev = Vec4::vector(0.0, angle.sin(), -angle.cos());
+        let (_, _, specular) = material.lighting_components(Color::white(), &light, &Vec4::ORIGIN, &eyev, &normalv, false);
+        return *specular.r();
+    };
+
+    let plastic = Material::plastic(Color::white());
+    let rubber = Material::rubber(Color::white());
+    assert!(specular(&rubber, 0.0) < specular(&plastic, 0.0));
+    assert!(specular(&rubber, 0.3) > specular(&plastic, 0.3));
+}
+
+#[test]
+#[should_panic(expected = "refraction")]
+fn zero_refraction_is_rejected() {
+    Material::new(Color::white(), 0.1, 0.9, 0.9, 200.0, 0.0, 1.0, 0.0, None);
+}
+
+#[test]
+#[should_panic(expected = "refraction")]
+fn nan_refraction_is_rejected() {
+    Material::new(Color::white(), 0.1, 0.9, 0.9, 200.0, 0.0, 1.0, f32::NAN, None);
+}
@@ -0,0 +1,391 @@
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::intersection::Intersection;
+use tracer::light::Light;
+use tracer::material::{Material, Wireframe};
+use tracer::model::{Model, SmoothTriangle, Triangle};
+use tracer::pattern::StripePattern;
+use tracer::ray::Ray;
+use tracer::group::Group;
+use tracer::shape::{Cube, Shape, Sphere};
+use tracer::world::World;
+
+#[test]
+fn loads_obj_with_crlf_line_endings_and_trailing_whitespace() {
+    let model = Model::new(Material::default(), "obj_files/crlf_triangle.obj");
+
+    assert_eq!(model.triangles.len(), 2);
+
+    let ray = Ray::new(Vec4::point(0.0, 0.5, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = Intersection::intersect(&model, ray);
+
+    assert_eq!(xs.len(), 2);
+    assert!(xs.iter().all(|x| (x.t - 2.0).abs() < 0.0001));
+}
+
+#[test]
+fn hits_report_the_model_and_triangle() {
+    let model = Model::new(Material::default(), "obj_files/crlf_triangle.obj");
+
+    let ray = Ray::new(Vec4::point(0.0, 0.5, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = Intersection::intersect(&model, ray);
+
+    assert!(xs.iter().all(|x| x.object.id() == model.id()));
+    assert_eq!(xs[0].child, 0);
+    assert_eq!(xs[1].child, 1);
+}
+
+#[test]
+fn normals_follow_the_model_transform() {
+    let mut model = Model::new(Material::default(), "obj_files/crlf_triangle.obj");
+    model.set_transform(Matrix4x4::rotatation_y(std::f32::consts::PI / 2.0));
+
+    let ray = Ray::new(Vec4::point(-2.0, 0.5, 0.0), Vec4::vector(1.0, 0.0, 0.0));
+    let xs = Intersection::intersect(&model, ray);
+    let comp = xs[0].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(comp.normalv, Vec4::vector(-1.0, 0.0, 0.0));
+}
+
+#[test]
+fn pattern_is_evaluated_in_model_space() {
+    let material = Material { pattern: Some(Box::new(StripePattern::default())), ..Material::default() };
+    let mut model = Model::new(material, "obj_files/crlf_triangle.obj");
+    model.set_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+
+    let ray = Ray::new(Vec4::point(1.5, 0.1, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = Intersection::intersect(&model, ray);
+    let comp = xs[0].prepare_computations(&ray, Some(&xs));
+    let color = comp.object.material().color_at_object(comp.object, &comp.point);
+
+    assert_eq!(color, Color::new(1.0, 1.0, 1.0));
+}
+
+fn smooth_triangle() -> SmoothTriangle {
+    return SmoothTriangle::new(
+        Material::default(),
+        Vec4::point(0.0, 1.0, 0.0),
+        Vec4::point(-1.0, 0.0, 0.0),
+        Vec4::point(1.0, 0.0, 0.0),
+        Vec4::vector(0.0, 1.0, 0.0),
+        Vec4::vector(-1.0, 0.0, 0.0),
+        Vec4::vector(1.0, 0.0, 0.0),
+    );
+}
+
+#[test]
+fn smooth_triangle_intersection_stores_uv() {
+    let tri = smooth_triangle();
+
+    let ray = Ray::new(Vec4::point(-0.2, 0.3, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = tri.local_intersect(&ray);
+
+    assert_eq!(xs.len(), 1);
+    assert!((xs[0].t - 2.0).abs() < 0.0001);
+    assert!((xs[0].u - 0.45).abs() < 0.0001);
+    assert!((xs[0].v - 0.25).abs() < 0.0001);
+    assert_eq!(tri.world_normal_at(&Vec4::ORIGIN, xs[0]), Vec4::vector(-0.5547, 0.83205, 0.0));
+}
+
+#[test]
+fn smooth_triangle_hits_match_flat_triangle() {
+    let smooth = smooth_triangle();
+    let flat = Triangle::new(Material::default(), Vec4::point(0.0, 1.0, 0.0), Vec4::point(-1.0, 0.0, 0.0), Vec4::point(1.0, 0.0, 0.0));
+
+    for i in 0..50 {
+        let x = -1.2 + i as f32 * 0.05;
+        let ray = Ray::new(Vec4::point(x, 0.3, -2.0), Vec4::vector(0.1, 0.05, 1.0).normalize());
+
+        let smooth_ts: Vec<f32> = smooth.local_intersect(&ray).iter().map(|x| x.t).collect();
+        let flat_ts: Vec<f32> = flat.local_intersect(&ray).iter().map(|x| x.t).collect();
+
+        assert_eq!(smooth_ts, flat_ts, "x = {}", x);
+    }
+
+    let parallel = Ray::new(Vec4::point(0.0, -1.0, -2.0), Vec4::vector(0.0, 1.0, 0.0));
+    assert!(smooth.local_intersect(&parallel).is_empty());
+}
+
+#[test]
+fn wireframe_marks_points_near_triangle_edges() {
+    let wireframe = Wireframe::new(Color::new(1.0, 0.0, 0.0), 0.05);
+
+    assert!(!wireframe.is_edge(1.0 / 3.0, 1.0 / 3.0));
+    assert!(wireframe.is_edge(0.01, 0.5));
+    assert!(wireframe.is_edge(0.5, 0.01));
+    assert!(wireframe.is_edge(0.5, 0.48));
+}
+
+#[test]
+fn wireframe_is_drawn_over_model_edges() {
+    let material = Material { wireframe: Some(Wireframe::new(Color::new(1.0, 0.0, 0.0), 0.05)), ..Material::default() };
+
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white()));
+    world.add_object(Box::new(Model::new(material, "obj_files/crlf_triangle.obj")));
+
+    let center = Ray::new(Vec4::point(0.0, 1.0 / 3.0, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert!(world.color_at(center, 5) != Color::new(1.0, 0.0, 0.0));
+
+    let edge = Ray::new(Vec4::point(0.0, 0.01, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(world.color_at(edge, 5), Color::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn wireframe_is_ignored_by_other_shapes() {
+    let red = Color::new(1.0, 0.0, 0.0);
+    let material = Material { wireframe: Some(Wireframe::new(red, 0.05)), ..Material::default() };
+
+    // Set directly on a sphere, and pushed down onto the shapes of a group alongside a model
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white()));
+    world.add_object(Box::new(Sphere::new(material.clone())));
+
+    let mut group = Group::new(Material::default());
+    let mut cube = Cube::new(Material::default());
+    cube.set_transform(Matrix4x4::translation(5.0, 0.0, 0.0));
+    group.add_child(Box::new(cube));
+    group.add_child(Box::new(Model::new(Material::default(), "obj_files/crlf_triangle.obj")));
+    group.set_material_recursive(&material);
+    world.add_object(Box::new(group));
+
+    let sphere = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_ne!(world.color_at(sphere, 5), red);
+    let cube = Ray::new(Vec4::point(5.0, 0.3, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_ne!(world.color_at(cube, 5), red);
+
+    world.objects.remove(0);
+    let edge = Ray::new(Vec4::point(0.0, 0.01, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(world.color_at(edge, 5), red);
+}
+
+#[test]
+fn model_triangles_keep_the_pattern() {
+    let material = Material { pattern: Some(Box::new(StripePattern::default())), ..Material::default() };
+    let model = Model::load(material, "obj_files/crlf_triangle.obj").unwrap();
+
+    assert!(model.triangles.iter().all(|tri| tri.material().pattern.is_some()));
+}
+
+#[test]
+fn four_component_vertices_ignore_w() {
+    let model = Model::new(Material::default(), "obj_files/homogeneous_vertices.obj");
+    assert_eq!(model.triangles.len(), 1);
+
+    // The last vertex has w = 0.5 but stays at x = 1
+    let ray = Ray::new(Vec4::point(0.9, 0.05, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(Intersection::intersect(&model, ray).len(), 1);
+}
+
+#[test]
+fn parameter_space_vertices_are_skipped() {
+    let model = Model::new(Material::default(), "obj_files/parameter_vertices.obj");
+    assert_eq!(model.triangles.len(), 1);
+
+    let ray = Ray::new(Vec4::point(0.0, 0.5, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(Intersection::intersect(&model, ray).len(), 1);
+}
+
+#[test]
+fn vertex_lines_with_the_wrong_number_of_values_are_rejected() {
+    let error = Model::load(Material::default(), "obj_files/malformed_vertices.obj").err().unwrap();
+    assert!(error.contains("line 3") && error.contains("found 5"), "{}", error);
+
+    let error = Model::load(Material::default(), "obj_files/malformed_parameter.obj").err().unwrap();
+    assert!(error.contains("line 4") && error.contains("'vp'"), "{}", error);
+}
+
+#[test]
+fn model_caches_its_inverse_transform() {
+    let mut model = Model::new(Material::default(), "obj_files/crlf_triangle.obj");
+    let matrix = Matrix4x4::translation(1.0, 2.0, 3.0) * Matrix4x4::scale(2.0, 2.0, 2.0);
+    model.set_transform(matrix);
+
+    assert_eq!(model.inverse_transform(), matrix.invert());
+}
+
+#[test]
+fn triangle_contains_points_inside_its_projection() {
+    let tri = Triangle::new(Material::default(), Vec4::point(0.0, 1.0, 0.0), Vec4::point(-1.0, 0.0, 0.0), Vec4::point(1.0, 0.0, 0.0));
+
+    // Same coordinates a hit at that point reports
+    let (u, v) = tri.contains(&Vec4::point(-0.2, 0.3, 0.0)).unwrap();
+    assert!((u - 0.45).abs() < 0.0001 && (v - 0.25).abs() < 0.0001);
+
+    // Points off the plane are projected onto it first
+    let (u, v) = tri.contains(&Vec4::point(-0.2, 0.3, 5.0)).unwrap();
+    assert!((u - 0.45).abs() < 0.0001 && (v - 0.25).abs() < 0.0001);
+
+    // The vertices themselves are inside
+    assert_eq!(tri.contains(&Vec4::point(0.0, 1.0, 0.0)), Some((0.0, 0.0)));
+
+    for outside in [Vec4::point(0.0, -0.1, 0.0), Vec4::point(-0.8, 0.5, 0.0), Vec4::point(2.0, 0.5, 0.0)] {
+        assert_eq!(tri.contains(&outside), None, "{:?}", outside);
+    }
+}
+
+#[test]
+fn contains_matches_intersections_across_the_triangle() {
+    let smooth = smooth_triangle();
+
+    for i in 0..40 {
+        let point = Vec4::point(-1.1 + i as f32 * 0.055, 0.2 + (i % 7) as f32 * 0.1, 0.0);
+        let ray = Ray::new(point - Vec4::vector(0.0, 0.0, 2.0), Vec4::vector(0.0, 0.0, 1.0));
+        let xs = smooth.local_intersect(&ray);
+
+        match smooth.contains(&point) {
+            Some((u, v)) => {
+                assert_eq!(xs.len(), 1, "{:?}", point);
+                assert!((xs[0].u - u).abs() < 0.0001 && (xs[0].v - v).abs() < 0.0001);
+            },
+            None => assert!(xs.is_empty(), "{:?}", point),
+        }
+    }
+}
+
+#[test]
+fn degenerate_triangle_contains_nothing() {
+    let line = Triangle::new(Material::default(), Vec4::point(0.0, 0.0, 0.0), Vec4::point(1.0, 0.0, 0.0), Vec4::point(2.0, 0.0, 0.0));
+    assert_eq!(line.contains(&Vec4::point(1.0, 0.0, 0.0)), None);
+
+    let point = Triangle::new(Material::default(), Vec4::point(0.5, 0.5, 0.0), Vec4::point(0.5, 0.5, 0.0), Vec4::point(0.5, 0.5, 0.0));
+    assert_eq!(point.contains(&Vec4::point(0.5, 0.5, 0.0)), None);
+}
+
+#[test]
+fn small_triangle_is_not_degenerate() {
+    let tri = Triangle::new(Material::default(), Vec4::point(0.0, 0.0, 0.0), Vec4::point(0.05, 0.0, 0.0), Vec4::point(0.0, 0.05, 0.0));
+
+    let (u, v) = tri.contains(&Vec4::point(0.01, 0.02, 0.0)).unwrap();
+    assert!((u - 0.2).abs() < 0.0001 && (v - 0.4).abs() < 0.0001);
+    assert_eq!(tri.contains(&Vec4::point(0.04, 0.04, 0.0)), None);
+}
+
+fn assert_is_tetrahedron(model: &Model) {
+    assert_eq!(model.triangles.len(), 4);
+
+    // Straight through the middle crosses the bottom and the slanted face
+    let ray = Ray::new(Vec4::point(0.25, 0.25, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = Intersection::intersect(model, ray);
+    assert_eq!(xs.len(), 2);
+    assert!((xs[0].t - 2.0).abs() < 0.0001 && (xs[1].t - 2.5).abs() < 0.0001);
+
+    let miss = Ray::new(Vec4::point(0.75, 0.75, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert!(Intersection::intersect(model, miss).is_empty());
+}
+
+#[test]
+fn loads_ascii_stl() {
+    assert_is_tetrahedron(&Model::new(Material::default(), "obj_files/tetrahedron.stl"));
+}
+
+#[test]
+fn loads_binary_stl_with_a_solid_header() {
+    assert_is_tetrahedron(&Model::new(Material::default(), "obj_files/tetrahedron_binary.STL"));
+}
+
+#[test]
+fn zero_area_stl_facets_are_skipped() {
+    let model = Model::new(Material::default(), "obj_files/degenerate_facets.stl");
+
+    assert_is_tetrahedron(&model);
+    assert!(model.triangles.iter().all(|tri| {
+        let normal = tri.local_normal_at(&Vec4::point(0.0, 0.0, 0.0), Intersection::new(tri.as_ref(), 0.0));
+        return normal.x().is_finite() && normal.y().is_finite() && normal.z().is_finite();
+    }));
+}
+
+#[test]
+fn stl_facets_need_three_vertices() {
+    let error = Model::load(Material::default(), "obj_files/malformed_facet.stl").err().unwrap();
+    assert!(error.contains("malformed_facet.stl") && error.contains("line 14") && error.contains("found 2"), "{}", error);
+}
+
+#[test]
+fn vertex_colors_are_blended_across_the_face() {
+    let mut material = Material::matte(Color::new(0.5, 0.5, 0.5));
+    material.ambient = 1.0;
+    material.diffuse = 0.0;
+    let model = Model::new(material, "obj_files/vertex_colors.obj");
+    assert_eq!(model.triangles.len(), 2);
+
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white()));
+    world.add_object(Box::new(model));
+
+    // The centroid of the painted triangle mixes all three corners evenly
+    let ray = Ray::new(Vec4::point(0.0, 1.0 / 3.0, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let third = 1.0 / 3.0;
+    assert_eq!(world.color_at(ray, 5), Color::new(third, third, third));
+
+    // Next to the top corner it is nearly pure red
+    let ray = Ray::new(Vec4::point(0.0, 0.9, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let color = world.color_at(ray, 5);
+    assert!(*color.r() > 0.85 && *color.g() < 0.1 && *color.b() < 0.1, "{:?}", color);
+
+    // The unpainted triangle keeps the material color
+    let ray = Ray::new(Vec4::point(2.0, 0.5, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(world.color_at(ray, 5), Color::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn flat_triangles_interpolate_vertex_colors() {
+    let mut triangle = Triangle::new(
+        Material::default(),
+        Vec4::point(0.0, 1.0, 0.0),
+        Vec4::point(-1.0, 0.0, 0.0),
+        Vec4::point(1.0, 0.0, 0.0),
+    );
+    let hit = Intersection::from_uv(&triangle, 1.0, 0.25, 0.5);
+    assert_eq!(triangle.vertex_color(hit), None);
+
+    triangle.set_vertex_colors(Color::new(1.0, 0.0, 0.0), Color::new(0.0, 1.0, 0.0), Color::new(0.0, 0.0, 1.0));
+    let hit = Intersection::from_uv(&triangle, 1.0, 0.25, 0.5);
+    assert_eq!(triangle.vertex_color(hit), Some(Color::new(0.25, 0.25, 0.5)));
+}
+
+#[test]
+fn recoloring_a_model_reaches_every_triangle() {
+    let mut model = Model::new(Material::default(), "obj_files/vertex_colors.obj");
+    model.set_material_recursive(&Material::plastic(Color::new(0.2, 0.4, 0.6)));
+
+    assert_eq!(model.material.color, Color::new(0.2, 0.4, 0.6));
+    assert!(model.triangles.iter().all(|triangle| triangle.material().color == Color::new(0.2, 0.4, 0.6)));
+}
+
+#[test]
+fn cloned_materials_keep_their_pattern() {
+    let material = Material {
+        pattern: Some(Box::new(StripePattern::new(Color::white(), Color::black(), Matrix4x4::scale(4.0, 1.0, 1.0)))),
+        ..Material::default()
+    };
+    let copy = material.clone();
+
+    let triangle = Triangle::new(Material::default(), Vec4::point(0.0, 1.0, 0.0), Vec4::point(-1.0, 0.0, 0.0), Vec4::point(1.0, 0.0, 0.0));
+    let colors: Vec<Color> = [0.1, 0.3, 0.6, 0.8]
+        .iter()
+        .map(|x| copy.color_at_object(&triangle, &Vec4::point(*x, 0.0, 0.0)))
+        .collect();
+    assert_eq!(colors, vec![Color::white(), Color::black(), Color::white(), Color::black()]);
+}
+
+#[test]
+fn smoothing_groups_pick_smooth_or_flat_triangles() {
+    let model = Model::new(Material::default(), "obj_files/smoothing_groups.obj");
+    assert_eq!(model.triangles.len(), 4);
+
+    // Away from the centre the bent vertex normals tilt a smooth triangle's normal, a flat one always
+    // faces straight along z
+    let normals: Vec<Vec4> = model
+        .triangles
+        .iter()
+        .map(|triangle| triangle.world_normal_at(&Vec4::ORIGIN, Intersection::from_uv(triangle.as_ref(), 0.0, 0.6, 0.1)))
+        .collect();
+
+    let faceted = |normal: &Vec4| normal.x().abs() < 0.0001 && normal.y().abs() < 0.0001;
+    assert!(!faceted(&normals[0]), "{:?}", normals[0]);
+    assert!(faceted(&normals[1]), "{:?}", normals[1]);
+    assert!(faceted(&normals[2]), "{:?}", normals[2]);
+    assert!(!faceted(&normals[3]), "{:?}", normals[3]);
+    assert_eq!(normals[0], normals[3]);
+}
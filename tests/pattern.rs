@@ -0,0 +1,196 @@
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::material::Material;
+use tracer::pattern::{BlendMode, BlendedPattern, CheckeredPattern, Pattern, RingPattern, StripeAxis, StripePattern};
+use tracer::shape::Sphere;
+
+fn solid(color: Color) -> Box<dyn Pattern> {
+    return Box::new(StripePattern::new(color, color, Matrix4x4::identity()));
+}
+
+#[test]
+fn blend_defaults_to_even_mix() {
+    let pattern = BlendedPattern::new(
+        solid(Color::new(1.0, 0.0, 0.0)),
+        solid(Color::new(0.0, 0.0, 1.0)),
+        Matrix4x4::identity(),
+    );
+
+    assert_eq!(pattern.weight, 0.5);
+    assert_eq!(pattern.mode, BlendMode::Mix);
+    assert_eq!(pattern.color_at(&Vec4::point(0.0, 0.0, 0.0)), Color::new(0.5, 0.0, 0.5));
+}
+
+#[test]
+fn weighted_blend() {
+    let pattern = BlendedPattern::weighted(
+        solid(Color::new(1.0, 0.0, 0.0)),
+        solid(Color::new(0.0, 0.0, 1.0)),
+        0.7,
+        Matrix4x4::identity(),
+    );
+
+    assert_eq!(pattern.color_at(&Vec4::point(0.0, 0.0, 0.0)), Color::new(0.7, 0.0, 0.3));
+}
+
+#[test]
+fn blend_modes() {
+    let mut pattern = BlendedPattern::new(
+        solid(Color::new(0.2, 0.5, 0.8)),
+        solid(Color::new(0.5, 0.5, 0.5)),
+        Matrix4x4::identity(),
+    );
+    let point = Vec4::point(0.0, 0.0, 0.0);
+
+    pattern.mode = BlendMode::Multiply;
+    assert_eq!(pattern.color_at(&point), Color::new(0.1, 0.25, 0.4));
+
+    pattern.mode = BlendMode::Screen;
+    assert_eq!(pattern.color_at(&point), Color::new(0.6, 0.75, 0.9));
+
+    pattern.mode = BlendMode::Overlay;
+    assert_eq!(pattern.color_at(&point), Color::new(0.2, 0.5, 0.8));
+}
+
+#[test]
+fn material_pattern_tint() {
+    let sphere = Sphere::new(Material::default());
+    let point = Vec4::point(0.0, 0.0, 0.0);
+
+    let mut material = Material {
+        color: Color::new(1.0, 0.5, 0.0),
+        pattern: Some(solid(Color::new(0.4, 0.8, 1.0))),
+        ..Material::default()
+    };
+
+    assert_eq!(material.pattern_tint, 0.0);
+    assert_eq!(material.color_at_object(&sphere, &point), Color::new(0.4, 0.8, 1.0));
+
+    material.pattern_tint = 1.0;
+    assert_eq!(material.color_at_object(&sphere, &point), Color::new(0.4, 0.4, 0.0));
+
+    material.pattern_tint = 0.5;
+    assert_eq!(material.color_at_object(&sphere, &point), Color::new(0.4, 0.6, 0.5));
+}
+
+#[test]
+fn checker_without_footprint_is_point_sampled() {
+    let pattern = CheckeredPattern::default();
+    let sphere = Sphere::new(Material::default());
+
+    for point in [Vec4::point(0.2, 0.0, 0.3), Vec4::point(1.2, 0.0, 0.3), Vec4::point(-0.5, 0.0, 2.5)] {
+        assert_eq!(pattern.color_at_footprint(&sphere, &point, &Vec4::ZERO, &Vec4::ZERO), pattern.color_at(&point));
+    }
+}
+
+#[test]
+fn checker_footprint_averages_covered_squares() {
+    let pattern = CheckeredPattern::default();
+    let sphere = Sphere::new(Material::default());
+    let point = Vec4::point(0.5, 0.5, 0.5);
+
+    // Half a square each side of the centre stays within one square
+    let small = pattern.color_at_footprint(&sphere, &point, &Vec4::vector(0.5, 0.0, 0.0), &Vec4::ZERO);
+    assert_eq!(small, Color::white());
+
+    // Two squares wide covers as much white as black
+    let wide = pattern.color_at_footprint(&sphere, &point, &Vec4::vector(2.0, 0.0, 0.0), &Vec4::ZERO);
+    assert_eq!(wide, Color::new(0.5, 0.5, 0.5));
+
+    // One square wide centred on an edge is an even split as well
+    let edge = Vec4::point(1.0, 0.5, 0.5);
+    let split = pattern.color_at_footprint(&sphere, &edge, &Vec4::vector(0.5, 0.0, 0.0), &Vec4::vector(0.5, 0.0, 0.0));
+    assert_eq!(split, Color::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn checker_footprint_follows_pattern_transform() {
+    let pattern = CheckeredPattern::new(Color::white(), Color::black(), Matrix4x4::scale(4.0, 4.0, 4.0));
+    let sphere = Sphere::new(Material::default());
+
+    let color = pattern.color_at_footprint(&sphere, &Vec4::point(0.125, 0.125, 0.125), &Vec4::vector(0.5, 0.0, 0.0), &Vec4::ZERO);
+    assert_eq!(color, Color::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn pattern_boundaries_are_stable_under_float_error() {
+    let checker = CheckeredPattern::default();
+    let stripe = StripePattern::default();
+    let ring = RingPattern::default();
+
+    // Values a hair either side of an integer pick the same square as the integer itself
+    for error in [-1e-7, 0.0, 1e-7] {
+        assert_eq!(checker.color_at(&Vec4::point(0.3, error, 0.3)), Color::white(), "y {}", error);
+        assert_eq!(checker.color_at(&Vec4::point(error, 0.0, 0.3)), Color::white(), "x {}", error);
+        assert_eq!(stripe.color_at(&Vec4::point(1.0 + error, 0.0, 0.0)), Color::black(), "x {}", error);
+        assert_eq!(ring.color_at(&Vec4::point(1.0 + error, 0.0, 0.0)), Color::black(), "r {}", error);
+    }
+
+    // Real offsets across the boundary still switch squares
+    assert_eq!(checker.color_at(&Vec4::point(-0.01, 0.0, 0.3)), Color::black());
+    assert_eq!(checker.color_at(&Vec4::point(0.3, -0.01, 0.3)), Color::black());
+    assert_eq!(stripe.color_at(&Vec4::point(0.99, 0.0, 0.0)), Color::white());
+    assert_eq!(ring.color_at(&Vec4::point(0.99, 0.0, 0.0)), Color::white());
+
+    // The over_point of a ceiling at y = 0 sits below the boundary
+    assert_eq!(checker.color_at(&Vec4::point(0.3, -0.00001, 0.3)), Color::black());
+}
+
+#[test]
+fn stripes_default_to_the_x_axis() {
+    let stripe = StripePattern::default();
+    assert_eq!(stripe.axis, StripeAxis::X);
+
+    assert_eq!(stripe.color_at(&Vec4::point(0.5, 1.5, 1.5)), Color::white());
+    assert_eq!(stripe.color_at(&Vec4::point(1.5, 0.5, 0.5)), Color::black());
+    assert_eq!(stripe.color_at(&Vec4::point(-0.5, 0.0, 0.0)), Color::black());
+}
+
+#[test]
+fn stripes_alternate_along_the_chosen_axis() {
+    let along_y = StripePattern::with_axis(Color::white(), Color::black(), Matrix4x4::identity(), StripeAxis::Y);
+    assert_eq!(along_y.color_at(&Vec4::point(1.5, 0.5, 1.5)), Color::white());
+    assert_eq!(along_y.color_at(&Vec4::point(0.5, 1.5, 0.5)), Color::black());
+    assert_eq!(along_y.color_at(&Vec4::point(0.0, 2.0, 0.0)), Color::white());
+
+    let along_z = StripePattern::with_axis(Color::white(), Color::black(), Matrix4x4::identity(), StripeAxis::Z);
+    assert_eq!(along_z.color_at(&Vec4::point(1.5, 1.5, 0.5)), Color::white());
+    assert_eq!(along_z.color_at(&Vec4::point(0.5, 0.5, 1.5)), Color::black());
+    assert_eq!(along_z.color_at(&Vec4::point(0.0, 0.0, -0.5)), Color::black());
+}
+
+#[test]
+fn blended_layers_keep_their_own_transforms() {
+    // Stripes two units wide blended evenly with stripes one unit wide
+    let wide = StripePattern::new(Color::new(1.0, 0.0, 0.0), Color::black(), Matrix4x4::scale(0.5, 1.0, 1.0));
+    let narrow = StripePattern::new(Color::new(0.0, 0.0, 1.0), Color::black(), Matrix4x4::identity());
+    let pattern = BlendedPattern::new(Box::new(wide), Box::new(narrow), Matrix4x4::identity());
+
+    assert_eq!(pattern.color_at(&Vec4::point(0.5, 0.0, 0.0)), Color::new(0.5, 0.0, 0.5));
+    assert_eq!(pattern.color_at(&Vec4::point(1.5, 0.0, 0.0)), Color::new(0.5, 0.0, 0.0));
+    assert_eq!(pattern.color_at(&Vec4::point(2.5, 0.0, 0.0)), Color::new(0.0, 0.0, 0.5));
+}
+
+#[test]
+fn rotating_a_blend_turns_both_layers_together() {
+    let layers = || -> (Box<dyn Pattern>, Box<dyn Pattern>) {
+        let wide = StripePattern::new(Color::new(1.0, 0.0, 0.0), Color::black(), Matrix4x4::scale(0.5, 1.0, 1.0));
+        let narrow = StripePattern::new(Color::new(0.0, 0.0, 1.0), Color::black(), Matrix4x4::identity());
+        return (Box::new(wide), Box::new(narrow));
+    };
+
+    let (first, second) = layers();
+    let upright = BlendedPattern::new(first, second, Matrix4x4::identity());
+
+    // The blend's transform takes object space into pattern space, so a quarter turn about y lays both
+    // sets of stripes out along z instead of x
+    let (first, second) = layers();
+    let turned = BlendedPattern::new(first, second, Matrix4x4::rotatation_y(std::f32::consts::PI / 2.0));
+    let sphere = Sphere::new(Material::default());
+
+    for offset in [0.5, 1.5, 2.5, 3.5] {
+        let along_x = upright.color_at_object(&sphere, &Vec4::point(offset, 0.0, 0.0));
+        let along_z = turned.color_at_object(&sphere, &Vec4::point(0.0, 0.0, offset));
+        assert_eq!(along_z, along_x, "offset {}", offset);
+    }
+}
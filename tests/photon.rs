@@ -0,0 +1,67 @@
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::ray::Ray;
+use tracer::shape::{Plane, Shape, Sphere};
+use tracer::world::World;
+
+fn glass_over_floor() -> World {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0)));
+
+    world.add_object(Box::new(Plane::new(Material::default())));
+
+    let mut glass = Sphere::glass_sphere();
+    glass.material.reflective = 0.0;
+    glass.set_transform(Matrix4x4::translation(0.0, 2.0, 0.0));
+    world.add_object(Box::new(glass));
+
+    return world;
+}
+
+#[test]
+fn photons_only_land_after_passing_through_glass() {
+    let mut world = glass_over_floor();
+    world.build_caustics(5000, 0.25);
+
+    let photon_map = world.photon_map.as_ref().unwrap();
+
+    assert!(!photon_map.photons.is_empty());
+    for photon in &photon_map.photons {
+        assert!(photon.position.y().abs() < 0.001);
+    }
+
+    let focused = photon_map
+        .photons
+        .iter()
+        .filter(|photon| (photon.position - Vec4::point(0.0, 0.0, 0.0)).magnitude() < 1.0)
+        .count();
+    assert!(focused * 2 > photon_map.photons.len());
+}
+
+#[test]
+fn caustic_focuses_light_beneath_the_glass() {
+    let mut world = glass_over_floor();
+    world.build_caustics(5000, 0.25);
+
+    let photon_map = world.photon_map.as_ref().unwrap();
+    let up = Vec4::vector(0.0, 1.0, 0.0);
+    let beneath = photon_map.irradiance_at(&Vec4::point(0.0, 0.0, 0.0), &up);
+    let aside = photon_map.irradiance_at(&Vec4::point(4.0, 0.0, 0.0), &up);
+
+    assert_ne!(beneath, Color::new(0.0, 0.0, 0.0));
+    assert_eq!(aside, Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn caustics_brighten_the_shadow_of_the_glass() {
+    let mut world = glass_over_floor();
+    let ray = Ray::new(Vec4::point(0.0, 0.5, -5.0), Vec4::vector(0.0, -0.1, 1.0).normalize());
+
+    let without = world.color_at(ray, 5);
+    world.build_caustics(5000, 0.25);
+    let with = world.color_at(ray, 5);
+
+    assert!(with.rgb() & 0xff > without.rgb() & 0xff);
+}
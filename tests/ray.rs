@@ -0,0 +1,62 @@
+use tracer::geometry::Vec4;
+use tracer::ray::Ray;
+use tracer::util;
+
+// Small linear congruential generator so the samples are the same on every run
+fn lcg(seed: u32) -> impl FnMut() -> f32 {
+    let mut state = seed;
+    return move || {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        return (state >> 8) as f32 / (1 << 24) as f32;
+    };
+}
+
+#[test]
+fn jitter_of_zero_returns_the_original_ray() {
+    let ray = Ray::new(Vec4::point(1.0, 2.0, 3.0), Vec4::vector(0.0, 0.5, 2.0));
+    let mut calls = 0;
+    let mut rng = || {
+        calls += 1;
+        return 0.5;
+    };
+
+    let jittered = ray.jittered(0.0, 0.0, &mut rng);
+    assert_eq!(jittered.origin, ray.origin);
+    assert_eq!(jittered.direction, ray.direction);
+    assert_eq!(calls, 0);
+}
+
+#[test]
+fn jittered_origin_stays_on_the_disk() {
+    let ray = Ray::new(Vec4::point(1.0, 2.0, 3.0), Vec4::vector(1.0, 1.0, 0.0));
+    let mut rng = lcg(7);
+
+    for _ in 0..200 {
+        let jittered = ray.jittered(0.5, 0.0, &mut rng);
+        let offset = jittered.origin - ray.origin;
+
+        assert!(offset.magnitude() <= 0.5 + util::THRESHOLD_F32);
+        assert!(offset.dot(&ray.direction).abs() < 0.0001);
+        assert_eq!(jittered.direction, ray.direction);
+    }
+}
+
+#[test]
+fn jittered_direction_stays_in_the_cone() {
+    let ray = Ray::new(Vec4::ORIGIN, Vec4::vector(0.0, 0.0, 3.0));
+    let mut rng = lcg(11);
+    let mut widest: f32 = 0.0;
+
+    for _ in 0..500 {
+        let jittered = ray.jittered(0.0, 0.2, &mut rng);
+        let angle = jittered.direction.angle_between(&ray.direction);
+
+        assert!(angle <= 0.2 + 0.001, "{}", angle);
+        assert!(util::equals_f32(&jittered.direction.magnitude(), &3.0));
+        assert_eq!(jittered.origin, ray.origin);
+        widest = widest.max(angle);
+    }
+
+    // Samples reach out towards the edge of the cone rather than bunching around the axis
+    assert!(widest > 0.18);
+}
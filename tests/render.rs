@@ -0,0 +1,495 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tracer::camera::Camera;
+use tracer::canvas::Canvas;
+use tracer::color::Color;
+use tracer::environment::Environment;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::ray::Ray;
+use tracer::shape::{Cube, Shape};
+use tracer::world::World;
+
+fn default_camera(hsize: f32, vsize: f32) -> Camera {
+    let mut camera = Camera::new(hsize, vsize, std::f32::consts::PI / 2.0);
+    camera.set_view_transform(
+        Vec4::point(0.0, 0.0, -5.0),
+        Vec4::point(0.0, 0.0, 0.0),
+        Vec4::vector(0.0, 1.0, 0.0),
+    );
+    return camera;
+}
+
+#[test]
+fn color_when_ray_misses() {
+    let world = World::demo();
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 1.0, 0.0));
+
+    assert_eq!(world.color_at(ray, 5), Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn color_when_ray_hits() {
+    let world = World::demo();
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+
+    assert_eq!(world.color_at(ray, 5), Color::new(0.38066, 0.47583, 0.28550));
+}
+
+#[test]
+fn render_default_world_center_pixel() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    let image = camera.render(&world);
+
+    assert_eq!(image.width, 11);
+    assert_eq!(image.height, 11);
+    assert_eq!(*image.color_at(5, 5), Color::new(0.38066, 0.47583, 0.28550).rgb());
+}
+
+#[test]
+fn render_default_world_golden_pixels() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    let image = camera.render(&world);
+
+    // Recorded from a known good render: the lit side of the outer sphere faces up and left, the rest
+    // of the frame looks past the spheres into the background
+    let golden: [(usize, usize, u32); 9] = [
+        (5, 5, 0x617949),
+        (5, 4, 0x85a663),
+        (4, 5, 0x85a663),
+        (6, 5, 0x14190f),
+        (5, 6, 0x14190f),
+        (0, 0, 0x000000),
+        (10, 0, 0x000000),
+        (0, 10, 0x000000),
+        (10, 10, 0x000000),
+    ];
+    for (x, y, expected) in golden {
+        assert_eq!(*image.color_at(x, y), expected, "pixel ({}, {})", x, y);
+    }
+}
+
+#[test]
+fn render_reports_progress_per_row() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    let mut reports: Vec<(usize, usize)> = Vec::new();
+    camera.render_with_progress(&world, |progress| {
+        reports.push((progress.pixels_done, progress.total_pixels));
+    });
+
+    assert_eq!(reports.len(), 11);
+    assert_eq!(reports[0], (11, 121));
+    assert_eq!(*reports.last().unwrap(), (121, 121));
+    assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+
+    // Wider than tall, one report per row of the full image
+    let mut last = (0, 0, 0.0);
+    let mut count = 0;
+    default_camera(7.0, 5.0).render_with_progress(&world, |progress| {
+        last = (progress.pixels_done, progress.total_pixels, progress.fraction());
+        count += 1;
+    });
+    assert_eq!(count, 5);
+    assert_eq!(last, (35, 35, 1.0));
+}
+
+#[test]
+fn render_to_colors_is_row_major_and_unquantized() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 7.0);
+
+    let colors = camera.render_to_colors(&world);
+
+    assert_eq!(colors.len(), 11 * 7);
+    assert_eq!(colors[5 + 3 * 11], world.color_at(camera.ray_for_pixel(5.0, 3.0), 5));
+    assert_eq!(colors[2 + 6 * 11], world.color_at(camera.ray_for_pixel(2.0, 6.0), 5));
+    assert_eq!(colors[5 + 3 * 11], Color::new(0.38066, 0.47583, 0.28550));
+}
+
+#[test]
+fn render_to_colors_matches_render() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    let colors = camera.render_to_colors(&world);
+    let image = camera.render(&world);
+
+    assert_eq!(colors.len(), image.width * image.height);
+    for y in 0..11 {
+        for x in 0..11 {
+            assert_eq!(colors[x + y * 11].rgb(), *image.color_at(x, y));
+        }
+    }
+    assert_eq!(image.hdr.unwrap(), colors);
+}
+
+#[test]
+fn render_aovs_outputs_surface_data() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    let aovs = camera.render_aovs(&world);
+    let colors = camera.render_to_colors(&world);
+
+    assert_eq!(*aovs.beauty.color_at(5, 5), colors[5 + 5 * 11].rgb());
+    assert_eq!(*aovs.albedo.color_at(5, 5), Color::new(0.8, 1.0, 0.6).rgb());
+    assert_eq!(*aovs.normal.color_at(5, 5), Color::new(0.5, 0.5, 0.0).rgb());
+    assert!((aovs.depth_values[5 + 5 * 11] - 4.0).abs() < 0.0001);
+    assert_eq!(*aovs.depth.color_at(5, 5), Color::white().rgb());
+}
+
+#[test]
+fn render_aovs_beauty_matches_render() {
+    let world = World::demo();
+    let mut camera = default_camera(11.0, 11.0);
+    camera.samples = 2;
+
+    let aovs = camera.render_aovs(&world);
+    let image = camera.render(&world);
+
+    assert_eq!(aovs.beauty.buffer, image.buffer);
+    assert_eq!(aovs.beauty.hdr, image.hdr);
+}
+
+#[test]
+fn render_aovs_leaves_misses_empty() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    let aovs = camera.render_aovs(&world);
+
+    assert!(aovs.depth_values[0].is_infinite());
+    assert_eq!(*aovs.normal.color_at(0, 0), 0);
+    assert_eq!(*aovs.depth.color_at(0, 0), 0);
+    assert_eq!(*aovs.albedo.color_at(0, 0), 0);
+}
+
+#[test]
+fn render_stereo_pair() {
+    let world = World::demo();
+    let camera = default_camera(21.0, 21.0);
+
+    let (left, right) = camera.render_stereo(&world, 0.0);
+    let mono = camera.render(&world);
+    assert_eq!(left.buffer, mono.buffer);
+    assert_eq!(right.buffer, mono.buffer);
+
+    let (left, right) = camera.render_stereo(&world, 1.0);
+    assert_ne!(left.buffer, right.buffer);
+    assert_eq!((left.width, left.height), (21, 21));
+}
+
+#[test]
+fn render_counts_and_blanks_nan_pixels() {
+    let mut world = World::demo();
+    world.environment = Some(Environment::Solid(Color::new(f32::NAN, 0.5, f32::INFINITY)));
+    let camera = default_camera(11.0, 11.0);
+
+    let mut invalid = 0;
+    let image = camera.render_with_progress(&world, |progress| invalid = progress.invalid_pixels);
+
+    // Only the background is broken, the spheres in the middle shade normally
+    assert!(invalid > 0 && invalid < 121, "{} invalid pixels", invalid);
+    assert_eq!(*image.color_at(0, 0), 0x00_7f_ff);
+
+    let mut clean = 0;
+    camera.render_with_progress(&World::demo(), |progress| clean = progress.invalid_pixels);
+    assert_eq!(clean, 0);
+}
+
+// A long box off to one side of its own origin, so every step of a turn looks different
+fn turntable_world() -> World {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(-10.0, 10.0, -10.0), Color::white()));
+
+    let mut bar = Cube::new(Material::matte(Color::new(0.8, 0.3, 0.2)));
+    bar.set_transform(Matrix4x4::translation(0.5, 0.0, 0.0) * Matrix4x4::scale(1.5, 0.3, 0.3));
+    world.add_object(Box::new(bar));
+
+    return world;
+}
+
+fn temp_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("{}-{}.gif", name, std::process::id()));
+    return path.to_string_lossy().into_owned();
+}
+
+#[test]
+fn turntable_renders_each_step_and_restores_the_shape() {
+    let mut world = turntable_world();
+    let id = *world.objects[0].id();
+    let original = *world.objects[0].transform();
+    let camera = default_camera(21.0, 21.0);
+
+    let frames = camera.render_turntable(&mut world, id, Vec4::vector(0.0, 1.0, 0.0), 4).unwrap();
+
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0].buffer, camera.render(&world).buffer);
+    assert_ne!(frames[0].buffer, frames[1].buffer);
+    assert_eq!(*world.objects[0].transform(), original);
+}
+
+#[test]
+fn turntable_of_a_missing_shape_is_an_error() {
+    let mut world = turntable_world();
+    let camera = default_camera(11.0, 11.0);
+    let missing = *Cube::new(Material::default()).id();
+
+    assert!(camera.render_turntable(&mut world, missing, Vec4::vector(0.0, 1.0, 0.0), 4).is_err());
+}
+
+#[test]
+fn turntable_gif_has_a_frame_per_step() {
+    let mut world = turntable_world();
+    let id = *world.objects[0].id();
+    let camera = default_camera(21.0, 11.0);
+    let path = temp_path("turntable");
+
+    camera.render_turntable_gif(&mut world, id, Vec4::vector(0.0, 1.0, 0.0), 6, Duration::from_secs(3), &path).unwrap();
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (21, 11));
+
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(delays, vec![50; 6]);
+}
+
+#[test]
+fn gif_frames_must_share_a_size() {
+    let path = temp_path("mismatched");
+    assert!(Canvas::write_gif(&[], &path, 10).is_err());
+    assert!(Canvas::write_gif(&[Canvas::new(2, 2), Canvas::new(3, 2)], &path, 10).is_err());
+}
+
+#[test]
+fn uncancelled_render_matches_render() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+    let cancel = AtomicBool::new(false);
+
+    let image = camera.render_cancellable(&world, &cancel).unwrap();
+    assert_eq!(image.buffer, camera.render(&world).buffer);
+}
+
+#[test]
+fn render_stops_once_cancelled() {
+    let world = World::demo();
+    let cancel = AtomicBool::new(true);
+    assert!(default_camera(11.0, 11.0).render_cancellable(&world, &cancel).is_none());
+
+    // Far too big to finish before the other thread raises the flag
+    let camera = default_camera(2000.0, 2000.0);
+    let cancel = AtomicBool::new(false);
+    let result = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            std::thread::sleep(Duration::from_millis(20));
+            cancel.store(true, Ordering::Relaxed);
+        });
+        camera.render_cancellable(&world, &cancel)
+    });
+
+    assert!(result.is_none());
+}
+
+#[test]
+fn scanlines_assemble_into_the_full_render() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 9.0);
+
+    let rows: Vec<Vec<Color>> = (0..9).map(|y| camera.render_scanline(&world, y)).collect();
+    assert!(rows.iter().all(|row| row.len() == 11));
+
+    let assembled: Vec<Color> = rows.concat();
+    assert_eq!(assembled, camera.render_to_colors(&world));
+
+    let image = camera.render(&world);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, color) in row.iter().enumerate() {
+            assert_eq!(color.rgb(), *image.color_at(x, y));
+        }
+    }
+
+    assert!(camera.render_scanline(&world, 9).is_empty());
+}
+
+#[test]
+fn render_draws_the_last_row_and_column() {
+    let world = World::demo();
+
+    // A single pixel looks straight at the spheres
+    let image = default_camera(1.0, 1.0).render(&world);
+    assert_eq!(*image.color_at(0, 0), Color::new(0.38066, 0.47583, 0.28550).rgb());
+
+    // The edges of a larger image see the background
+    let mut world = World::demo();
+    let sky = Color::new(0.2, 0.4, 0.6);
+    world.environment = Some(Environment::Solid(sky));
+    let image = default_camera(11.0, 11.0).render(&world);
+    assert_eq!(*image.color_at(10, 5), sky.rgb());
+    assert_eq!(*image.color_at(5, 10), sky.rgb());
+    assert_eq!(image.hdr_color_at(10, 10), sky);
+}
+
+#[test]
+fn intersection_heatmap_counts_every_surface_along_the_ray() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    // Through both spheres in the middle, only the outer one further out, nothing in the corner
+    let counts = camera.intersection_counts(&world);
+    assert_eq!(counts.len(), 11 * 11);
+    assert_eq!(counts[5 + 5 * 11], 4);
+    assert_eq!(counts[5 + 4 * 11], 2);
+    assert_eq!(counts[0], 0);
+
+    let heatmap = camera.render_intersection_heatmap(&world);
+    assert_eq!(*heatmap.color_at(5, 5), Color::RED.rgb());
+    assert_eq!(*heatmap.color_at(5, 4), Color::GREEN.rgb());
+    assert_eq!(*heatmap.color_at(0, 0), Color::BLACK.rgb());
+}
+
+#[test]
+fn render_keeps_the_unquantized_colors() {
+    let mut world = World::demo();
+    world.lights[0].intensity = Color::new(5.0, 5.0, 5.0);
+    let camera = default_camera(11.0, 11.0);
+
+    let image = camera.render(&world);
+    let expected = world.color_at(camera.ray_for_pixel(5.0, 5.0), 5);
+
+    assert!(*expected.r() > 1.0);
+    assert_eq!(image.hdr_color_at(5, 5), expected);
+    assert_eq!(*image.color_at(5, 5), expected.rgb());
+}
+
+fn antialiased_camera(seed: u64) -> Camera {
+    let mut camera = default_camera(21.0, 15.0);
+    camera.samples = 3;
+    camera.seed = seed;
+    return camera;
+}
+
+#[test]
+fn single_sample_pixels_use_the_centre_ray() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    assert_eq!(camera.samples, 1);
+    assert_eq!(camera.pixel_color(&world, 4, 6), world.color_at(camera.ray_for_pixel(4.0, 6.0), 5));
+}
+
+#[test]
+fn antialiasing_softens_edges_without_changing_flat_areas() {
+    let world = World::demo();
+    let sharp = default_camera(21.0, 15.0).render_to_colors(&world);
+    let smooth = antialiased_camera(7).render_to_colors(&world);
+
+    // The background corner is black either way, but the silhouette of the sphere blends
+    assert_eq!(smooth[0], Color::black());
+    assert_ne!(sharp, smooth);
+}
+
+#[test]
+fn antialiased_renders_are_repeatable_in_any_order() {
+    let world = World::demo();
+    let camera = antialiased_camera(7);
+
+    let serial = camera.render(&world);
+    assert_eq!(serial.buffer, camera.render(&world).buffer);
+
+    for threads in [1, 3, 8] {
+        let parallel = camera.render_parallel(&world, threads);
+        assert_eq!(parallel.buffer, serial.buffer, "{} threads", threads);
+        assert_eq!(parallel.hdr, serial.hdr, "{} threads", threads);
+    }
+
+    // including the last row and column, which see the background
+    let mut sky_world = World::demo();
+    let sky = Color::new(0.2, 0.4, 0.6);
+    sky_world.environment = Some(Environment::Solid(sky));
+    let parallel = camera.render_parallel(&sky_world, 3);
+    assert_eq!(parallel.hdr_color_at(20, 7), sky);
+    assert_eq!(parallel.hdr_color_at(10, 14), sky);
+    assert_eq!(parallel.buffer, camera.render(&sky_world).buffer);
+
+    // Rows rendered bottom up come out the same as top down
+    let mut bottom_up: Vec<Vec<Color>> = (0..15).rev().map(|y| camera.render_scanline(&world, y)).collect();
+    bottom_up.reverse();
+    assert_eq!(bottom_up.concat(), camera.render_to_colors(&world));
+
+    // while another seed moves the jitter
+    assert_ne!(antialiased_camera(8).render_to_colors(&world), camera.render_to_colors(&world));
+}
+
+#[test]
+fn accumulation_keeps_flat_areas_and_restarts_at_frame_zero() {
+    let world = World::demo();
+    let camera = default_camera(11.0, 11.0);
+
+    let mut accumulated = vec![Color::white(); 3];
+    camera.render_accumulate(&world, &mut accumulated, 0);
+    assert_eq!(accumulated.len(), 11 * 11);
+    assert_eq!(accumulated[0], Color::black());
+
+    for frame in 1..4 {
+        camera.render_accumulate(&world, &mut accumulated, frame);
+    }
+    assert_eq!(accumulated[0], Color::black());
+
+    // Starting over throws the history away
+    let mut fresh = Vec::new();
+    camera.render_accumulate(&world, &mut fresh, 0);
+    let first = fresh.clone();
+    camera.render_accumulate(&world, &mut fresh, 0);
+    assert_eq!(fresh, first);
+}
+
+#[test]
+fn accumulated_frames_average_towards_the_supersampled_image() {
+    let world = World::demo();
+    let camera = default_camera(21.0, 15.0);
+    let mut reference_camera = default_camera(21.0, 15.0);
+    reference_camera.samples = 8;
+    let reference = reference_camera.render_to_colors(&world);
+
+    let error = |colors: &[Color]| -> f32 {
+        colors
+            .iter()
+            .zip(reference.iter())
+            .map(|(a, b)| (a.r() - b.r()).abs() + (a.g() - b.g()).abs() + (a.b() - b.b()).abs())
+            .sum()
+    };
+
+    let mut accumulated = Vec::new();
+    camera.render_accumulate(&world, &mut accumulated, 0);
+    let after_one = error(&accumulated);
+
+    for frame in 1..64 {
+        camera.render_accumulate(&world, &mut accumulated, frame);
+    }
+    let after_many = error(&accumulated);
+
+    assert!(after_many < after_one / 3.0, "{} then {}", after_one, after_many);
+
+    // The same frames accumulate to the same image every time
+    let mut again = Vec::new();
+    for frame in 0..64 {
+        camera.render_accumulate(&world, &mut again, frame);
+    }
+    assert_eq!(again, accumulated);
+}
@@ -0,0 +1,117 @@
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::intersection::Intersection;
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::ray::Ray;
+use tracer::sdf::{smooth_min, SdfShape};
+use tracer::shape::{Shape, Sphere};
+use tracer::world::World;
+
+fn unit_sphere() -> SdfShape {
+    return SdfShape::new(Material::default(), |point| (point - Vec4::ORIGIN).magnitude() - 1.0);
+}
+
+fn ts(xs: &[Intersection]) -> Vec<f32> {
+    return xs.iter().map(|x| x.t).collect();
+}
+
+fn assert_close(a: f32, b: f32, tolerance: f32) {
+    assert!((a - b).abs() < tolerance, "{} != {}", a, b);
+}
+
+#[test]
+fn marching_a_sphere_finds_both_sides() {
+    let sdf = unit_sphere();
+    let ray = Ray::new(Vec4::point(0.3, 0.2, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+
+    let marched = ts(&sdf.local_intersect(&ray));
+    let exact = ts(&Sphere::new(Material::default()).local_intersect(&ray));
+
+    assert_eq!(marched.len(), 2);
+    assert_close(marched[0], exact[0], 0.001);
+    assert_close(marched[1], exact[1], 0.001);
+}
+
+#[test]
+fn rays_that_miss_or_run_out_of_steps_find_nothing() {
+    let mut sdf = unit_sphere();
+    assert!(sdf.local_intersect(&Ray::new(Vec4::point(0.0, 2.0, -5.0), Vec4::vector(0.0, 0.0, 1.0))).is_empty());
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    sdf.max_distance = 3.0;
+    assert!(sdf.local_intersect(&ray).is_empty());
+
+    sdf.max_distance = 100.0;
+    sdf.max_steps = 0;
+    assert!(sdf.local_intersect(&ray).is_empty());
+}
+
+#[test]
+fn transformed_sdf_reports_t_in_world_units() {
+    let mut sdf = unit_sphere();
+    sdf.set_transform(Matrix4x4::translation(0.0, 0.0, 1.0) * Matrix4x4::scale(2.0, 2.0, 2.0));
+
+    let xs = Intersection::intersect(&sdf, Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(xs.len(), 2);
+    assert_close(xs[0].t, 4.0, 0.001);
+    assert_close(xs[1].t, 8.0, 0.001);
+}
+
+#[test]
+fn normals_follow_the_distance_gradient() {
+    let mut sdf = unit_sphere();
+    sdf.set_transform(Matrix4x4::scale(1.0, 0.5, 1.0));
+    let sphere_normal = {
+        let mut sphere = Sphere::new(Material::default());
+        sphere.set_transform(Matrix4x4::scale(1.0, 0.5, 1.0));
+        let point = Vec4::point(0.0, 0.5_f32.sqrt() * 0.5, 0.5_f32.sqrt());
+        (sphere.world_normal_at(&point, Intersection::new(&sphere, 0.0)), point)
+    };
+
+    let normal = sdf.world_normal_at(&sphere_normal.1, Intersection::new(&sdf, 0.0));
+    assert!(normal.distance(&sphere_normal.0) < 0.001, "{:?}", normal);
+}
+
+#[test]
+fn smooth_min_blends_only_near_both_surfaces() {
+    assert_eq!(smooth_min(0.3, 0.5, 0.0), 0.3);
+    assert_eq!(smooth_min(0.2, 2.0, 0.5), 0.2);
+    assert_close(smooth_min(0.2, 0.2, 1.0), -0.05, 0.00001);
+}
+
+#[test]
+fn blended_spheres_bridge_the_gap_between_them() {
+    let first = Vec4::point(-1.2, 0.0, 0.0);
+    let second = Vec4::point(1.2, 0.0, 0.0);
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+
+    // Plain spheres leave a gap down the middle, the smooth blend fills it in
+    let separate = SdfShape::blended_spheres(Material::default(), first, second, 0.0);
+    assert!(separate.local_intersect(&ray).is_empty());
+
+    let blended = SdfShape::blended_spheres(Material::default(), first, second, 1.0);
+    let hits = ts(&blended.local_intersect(&ray));
+    assert_eq!(hits.len(), 2);
+    assert!(hits[0] > 4.0 && hits[0] < 5.0, "{}", hits[0]);
+
+    // and shades like any other shape, facing the camera in the middle of the neck
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white()));
+    world.add_object(Box::new(blended));
+    let normal = world.objects[0].world_normal_at(&ray.at(hits[0]), Intersection::new(world.objects[0].as_ref(), hits[0]));
+    assert!(normal.distance(&Vec4::vector(0.0, 0.0, -1.0)) < 0.001, "{:?}", normal);
+    assert_ne!(world.color_at(ray, 5), Color::black());
+}
+
+#[test]
+fn surface_samples_lie_on_the_surface() {
+    let mut sdf = unit_sphere();
+    sdf.set_transform(Matrix4x4::translation(3.0, 0.0, 0.0));
+
+    for (u, v) in [(0.0, 0.5), (0.25, 0.1), (0.7, 0.9)] {
+        let point = sdf.sample_surface(u, v);
+        assert_close(point.distance(&Vec4::point(3.0, 0.0, 0.0)), 1.0, 0.001);
+    }
+}
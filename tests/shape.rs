@@ -0,0 +1,590 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use tracer::color::Color;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::group::Group;
+use tracer::intersection::Intersection;
+use tracer::material::Material;
+use tracer::model::{Model, SmoothTriangle, Triangle};
+use tracer::pattern::StripePattern;
+use tracer::ray::Ray;
+use tracer::sdf::SdfShape;
+use tracer::shape::{Cone, Cube, CubeFace, Cylinder, Plane, Shape, Sphere};
+use tracer::util;
+
+fn assert_uv(point: Vec4, face: CubeFace, u: f32, v: f32) {
+    let (actual_face, actual_u, actual_v) = Cube::uv_at(&point);
+
+    assert_eq!(actual_face, face);
+    assert!(util::equals_f32(&actual_u, &u), "u was {} expected {}", actual_u, u);
+    assert!(util::equals_f32(&actual_v, &v), "v was {} expected {}", actual_v, v);
+}
+
+#[test]
+fn face_from_point() {
+    assert_eq!(Cube::face_from_point(&Vec4::point(-1.0, 0.5, -0.25)), CubeFace::Left);
+    assert_eq!(Cube::face_from_point(&Vec4::point(1.1, -0.75, 0.8)), CubeFace::Right);
+    assert_eq!(Cube::face_from_point(&Vec4::point(0.1, 0.6, 0.9)), CubeFace::Front);
+    assert_eq!(Cube::face_from_point(&Vec4::point(-0.7, 0.0, -2.0)), CubeFace::Back);
+    assert_eq!(Cube::face_from_point(&Vec4::point(0.5, 1.0, 0.9)), CubeFace::Up);
+    assert_eq!(Cube::face_from_point(&Vec4::point(-0.2, -1.3, 1.1)), CubeFace::Down);
+}
+
+#[test]
+fn uv_on_front_face() {
+    assert_uv(Vec4::point(-0.5, 0.5, 1.0), CubeFace::Front, 0.25, 0.75);
+    assert_uv(Vec4::point(0.5, -0.5, 1.0), CubeFace::Front, 0.75, 0.25);
+}
+
+#[test]
+fn uv_at_face_edges_stays_on_the_edge() {
+    // Both ends of a face used to wrap around to 0
+    assert_uv(Vec4::point(-1.0, 1.0, -1.0), CubeFace::Left, 0.0, 1.0);
+    assert_uv(Vec4::point(1.0, -1.0, 1.0), CubeFace::Right, 0.0, 0.0);
+    assert_uv(Vec4::point(1.0, 0.5, 1.0), CubeFace::Right, 0.0, 0.75);
+    assert_uv(Vec4::point(0.2, 1.0, -1.0), CubeFace::Up, 0.6, 1.0);
+    // Points that land just outside the cube through float error
+    assert_uv(Vec4::point(1.00001, 0.0, 0.0), CubeFace::Right, 0.5, 0.5);
+    assert_uv(Vec4::point(0.0, 0.0, 1.00001), CubeFace::Front, 0.5, 0.5);
+}
+
+#[test]
+fn uv_on_back_face() {
+    assert_uv(Vec4::point(0.5, 0.5, -1.0), CubeFace::Back, 0.25, 0.75);
+    assert_uv(Vec4::point(-0.5, -0.5, -1.0), CubeFace::Back, 0.75, 0.25);
+}
+
+#[test]
+fn uv_on_left_face() {
+    assert_uv(Vec4::point(-1.0, 0.5, -0.5), CubeFace::Left, 0.25, 0.75);
+    assert_uv(Vec4::point(-1.0, -0.5, 0.5), CubeFace::Left, 0.75, 0.25);
+}
+
+#[test]
+fn uv_on_right_face() {
+    assert_uv(Vec4::point(1.0, 0.5, 0.5), CubeFace::Right, 0.25, 0.75);
+    assert_uv(Vec4::point(1.0, -0.5, -0.5), CubeFace::Right, 0.75, 0.25);
+}
+
+#[test]
+fn uv_on_up_face() {
+    assert_uv(Vec4::point(-0.5, 1.0, -0.5), CubeFace::Up, 0.25, 0.75);
+    assert_uv(Vec4::point(0.5, 1.0, 0.5), CubeFace::Up, 0.75, 0.25);
+}
+
+#[test]
+fn uv_on_down_face() {
+    assert_uv(Vec4::point(-0.5, -1.0, 0.5), CubeFace::Down, 0.25, 0.75);
+    assert_uv(Vec4::point(0.5, -1.0, -0.5), CubeFace::Down, 0.75, 0.25);
+}
+
+fn local_ts(shape: &dyn Shape, origin: Vec4, direction: Vec4) -> Vec<f32> {
+    let ray = Ray::new(origin, direction.normalize());
+    return shape.local_intersect(&ray).iter().map(|x| x.t).collect();
+}
+
+#[test]
+fn infinite_cylinder_is_hit_at_any_height() {
+    let cylinder = Cylinder::infinite(Material::default());
+
+    assert!(cylinder.minimum.is_infinite() && cylinder.maximum.is_infinite());
+    assert!(!cylinder.closed);
+
+    for y in [-10000.0, -1.5, 0.0, 3.0, 10000.0] {
+        let ts = local_ts(&cylinder, Vec4::point(0.0, y, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+        assert_eq!(ts.len(), 2, "y = {}", y);
+        assert!(util::equals_f32(&ts[0], &4.0) && util::equals_f32(&ts[1], &6.0));
+    }
+}
+
+#[test]
+fn bounded_cylinder_misses_outside_its_range() {
+    let cylinder = Cylinder::bounded(Material::default(), 1.0, 2.0, false);
+
+    assert_eq!(local_ts(&cylinder, Vec4::point(0.0, 1.5, -2.0), Vec4::vector(0.0, 0.0, 1.0)).len(), 2);
+    assert!(local_ts(&cylinder, Vec4::point(0.0, 3.0, -5.0), Vec4::vector(0.0, 0.0, 1.0)).is_empty());
+    assert!(local_ts(&cylinder, Vec4::point(0.0, 1.0, -5.0), Vec4::vector(0.0, 0.0, 1.0)).is_empty());
+}
+
+#[test]
+fn intersecting_infinite_cone() {
+    let cone = Cone::infinite(Material::default());
+
+    let ts = local_ts(&cone, Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert!(util::equals_f32(&ts[0], &5.0) && util::equals_f32(&ts[1], &5.0));
+
+    let ts = local_ts(&cone, Vec4::point(0.0, 0.0, -5.0), Vec4::vector(1.0, 1.0, 1.0));
+    assert!((ts[0] - 8.66025).abs() < 0.0001 && (ts[1] - 8.66025).abs() < 0.0001);
+
+    let ts = local_ts(&cone, Vec4::point(1.0, 1.0, -5.0), Vec4::vector(-0.5, -1.0, 1.0));
+    assert!((ts[0] - 4.55006).abs() < 0.0001 && (ts[1] - 49.44994).abs() < 0.001);
+}
+
+#[test]
+fn intersecting_cone_parallel_to_one_half() {
+    let cone = Cone::infinite(Material::default());
+
+    let ts = local_ts(&cone, Vec4::point(0.0, 0.0, -1.0), Vec4::vector(0.0, 1.0, 1.0));
+
+    assert_eq!(ts.len(), 1);
+    assert!((ts[0] - 0.35355).abs() < 0.0001);
+}
+
+#[test]
+fn intersecting_bounded_cone_caps() {
+    let cone = Cone::bounded(Material::default(), -0.5, 0.5, true);
+
+    assert_eq!(local_ts(&cone, Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 1.0, 0.0)).len(), 0);
+    assert_eq!(local_ts(&cone, Vec4::point(0.0, 0.0, -0.25), Vec4::vector(0.0, 1.0, 1.0)).len(), 2);
+    assert_eq!(local_ts(&cone, Vec4::point(0.0, 0.0, -0.25), Vec4::vector(0.0, 1.0, 0.0)).len(), 4);
+}
+
+#[test]
+fn cone_normals() {
+    let cone = Cone::infinite(Material::default());
+    let hit = Intersection::new(&cone, 0.0);
+
+    assert_eq!(cone.local_normal_at(&Vec4::point(0.0, 0.0, 0.0), hit), Vec4::vector(0.0, 0.0, 0.0));
+    assert_eq!(cone.local_normal_at(&Vec4::point(1.0, 1.0, 1.0), hit), Vec4::vector(1.0, -2.0_f32.sqrt(), 1.0));
+    assert_eq!(cone.local_normal_at(&Vec4::point(-1.0, -1.0, 0.0), hit), Vec4::vector(-1.0, 1.0, 0.0));
+}
+
+#[test]
+fn tangent_ray_hits_sphere_once_at_two_equal_ts() {
+    let sphere = Sphere::new(Material::default());
+
+    let ts = local_ts(&sphere, Vec4::point(0.0, 1.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+
+    assert_eq!(ts.len(), 2);
+    assert!(util::equals_f32(&ts[0], &5.0) && util::equals_f32(&ts[1], &5.0));
+}
+
+#[test]
+fn tangent_rays_hit_transformed_spheres() {
+    for i in 0..200 {
+        let angle = i as f32 * 0.0317;
+        let mut sphere = Sphere::new(Material::default());
+        sphere.set_transform(Matrix4x4::rotatation_z(angle) * Matrix4x4::rotatation_x(angle * 0.7) * Matrix4x4::scale(2.0, 2.0, 2.0));
+
+        let ray = Ray::new(Vec4::point(0.0, 2.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+        let xs = Intersection::intersect(&sphere, ray);
+
+        assert_eq!(xs.len(), 2, "angle {}", angle);
+        assert!((xs[0].t - 5.0).abs() < 0.01 && (xs[1].t - 5.0).abs() < 0.01);
+    }
+}
+
+#[test]
+fn scaled_sphere_ts_are_in_world_units() {
+    let mut sphere = Sphere::new(Material::default());
+    sphere.set_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = Intersection::intersect(&sphere, ray);
+
+    assert!(util::equals_f32(&xs[0].t, &3.0) && util::equals_f32(&xs[1].t, &7.0));
+}
+
+#[test]
+fn ray_intersects_cube_faces() {
+    let cube = Cube::new(Material::default());
+
+    let cases = [
+        (Vec4::point(5.0, 0.5, 0.0), Vec4::vector(-1.0, 0.0, 0.0), 4.0, 6.0),
+        (Vec4::point(0.5, -5.0, 0.0), Vec4::vector(0.0, 1.0, 0.0), 4.0, 6.0),
+        (Vec4::point(0.5, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0), 4.0, 6.0),
+        (Vec4::point(0.0, 0.5, 0.0), Vec4::vector(0.0, 0.0, 1.0), -1.0, 1.0),
+    ];
+
+    for (origin, direction, t1, t2) in cases {
+        let ts = local_ts(&cube, origin, direction);
+        assert!(util::equals_f32(&ts[0], &t1) && util::equals_f32(&ts[1], &t2));
+    }
+
+    assert!(local_ts(&cube, Vec4::point(-2.0, 0.0, 0.0), Vec4::vector(0.2673, 0.5345, 0.8018)).is_empty());
+    assert!(local_ts(&cube, Vec4::point(2.0, 2.0, 0.0), Vec4::vector(-1.0, 0.0, 0.0)).is_empty());
+}
+
+fn plane_normal(plane: &Plane) -> Vec4 {
+    return plane.world_normal_at(&Vec4::ORIGIN, Intersection::new(plane, 0.0));
+}
+
+#[test]
+fn plane_from_normal_faces_the_given_direction() {
+    let half = 2.0_f32.sqrt() / 2.0;
+    let normals = [
+        Vec4::vector(0.0, 1.0, 0.0),
+        Vec4::vector(0.0, 0.0, 1.0),
+        Vec4::vector(-1.0, 0.0, 0.0),
+        Vec4::vector(half, half, 0.0),
+        Vec4::vector(0.0, -half, half),
+    ];
+
+    for normal in normals {
+        let plane = Plane::from_normal(Material::default(), normal);
+        assert_eq!(plane_normal(&plane), normal, "{:?}", normal);
+    }
+
+    // Only the direction matters
+    let plane = Plane::from_normal(Material::default(), Vec4::vector(0.0, 0.0, 5.0));
+    assert_eq!(plane_normal(&plane), Vec4::vector(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn plane_from_normal_handles_facing_straight_down() {
+    let plane = Plane::from_normal(Material::default(), Vec4::vector(0.0, -1.0, 0.0));
+    assert_eq!(plane_normal(&plane), Vec4::vector(0.0, -1.0, 0.0));
+}
+
+#[test]
+fn wall_from_normal_is_hit_along_its_normal() {
+    let mut wall = Plane::from_normal(Material::default(), Vec4::vector(0.0, 0.0, 1.0));
+    wall.set_transform(Matrix4x4::translation(0.0, 0.0, 3.0) * *wall.transform());
+
+    let ray = Ray::new(Vec4::point(1.0, 2.0, 10.0), Vec4::vector(0.0, 0.0, -1.0));
+    let xs = Intersection::intersect(&wall, ray);
+    assert_eq!(xs.len(), 1);
+    assert!(util::equals_f32(&xs[0].t, &7.0));
+
+    let parallel = Ray::new(Vec4::point(0.0, 0.0, 10.0), Vec4::vector(1.0, 0.0, 0.0));
+    assert!(Intersection::intersect(&wall, parallel).is_empty());
+}
+
+#[test]
+#[should_panic]
+fn plane_from_zero_normal_panics() {
+    Plane::from_normal(Material::default(), Vec4::ZERO);
+}
+
+#[test]
+fn cached_inverse_follows_every_transform_change() {
+    let shapes: Vec<Box<dyn Shape>> = vec![
+        Box::new(Sphere::new(Material::default())),
+        Box::new(Plane::new(Material::default())),
+        Box::new(Cube::new(Material::default())),
+        Box::new(Cylinder::new(Material::default(), -1.0, 1.0, true)),
+        Box::new(Cone::new(Material::default(), -1.0, 1.0, true)),
+    ];
+
+    for mut shape in shapes {
+        assert_eq!(shape.inverse_transform(), Matrix4x4::identity());
+
+        // Moving the shape frame by frame, as an animation would
+        for frame in 1..4 {
+            let matrix = Matrix4x4::translation(frame as f32, 0.0, 0.0) * Matrix4x4::scale(2.0, 2.0, 2.0);
+            shape.set_transform(matrix);
+            assert_eq!(shape.inverse_transform(), matrix.invert());
+        }
+    }
+}
+
+#[test]
+fn animated_sphere_is_hit_where_it_was_last_moved() {
+    let mut sphere = Sphere::new(Material::default());
+    let ray = Ray::new(Vec4::point(3.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+
+    sphere.set_transform(Matrix4x4::translation(0.0, 0.0, 0.0));
+    assert!(Intersection::intersect(&sphere, ray).is_empty());
+
+    sphere.set_transform(Matrix4x4::translation(3.0, 0.0, 0.0));
+    let xs = Intersection::intersect(&sphere, ray);
+    assert_eq!(xs.len(), 2);
+    assert!(util::equals_f32(&xs[0].t, &4.0));
+
+    let normal = sphere.world_normal_at(&Vec4::point(3.0, 0.0, -1.0), xs[0]);
+    assert_eq!(normal, Vec4::vector(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn albedo_is_the_material_color_without_a_pattern() {
+    let sphere = Sphere::new(Material::matte(Color::new(0.2, 0.4, 0.6)));
+    let shape: &dyn Shape = &sphere;
+
+    assert_eq!(shape.albedo_at(&Vec4::point(0.0, 0.0, -1.0)), Color::new(0.2, 0.4, 0.6));
+}
+
+#[test]
+fn albedo_follows_the_pattern_in_object_space() {
+    let mut material = Material::matte(Color::new(0.2, 0.4, 0.6));
+    material.pattern = Some(Box::new(StripePattern::default()));
+    let mut sphere = Sphere::new(material);
+    sphere.set_transform(Matrix4x4::translation(1.0, 0.0, 0.0));
+    let shape: &dyn Shape = &sphere;
+
+    // World x 1.5 and 0.5 are object x 0.5 and -0.5, either side of the first stripe edge
+    assert_eq!(shape.albedo_at(&Vec4::point(1.5, 0.0, 0.0)), Color::white());
+    assert_eq!(shape.albedo_at(&Vec4::point(0.5, 0.0, 0.0)), Color::black());
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 0.0001, "{} != {}", actual, expected);
+}
+
+#[test]
+fn sphere_cube_and_plane_measurements() {
+    let sphere = Sphere::new(Material::default());
+    assert_close(sphere.surface_area(), 4.0 * PI);
+    assert_close(sphere.volume(), 4.0 / 3.0 * PI);
+
+    let cube = Cube::new(Material::default());
+    assert_close(cube.surface_area(), 24.0);
+    assert_close(cube.volume(), 8.0);
+
+    let plane = Plane::new(Material::default());
+    assert_eq!(plane.surface_area(), f32::INFINITY);
+    assert_eq!(plane.volume(), 0.0);
+}
+
+#[test]
+fn cylinder_measurements() {
+    let open = Cylinder::new(Material::default(), 1.0, 3.0, false);
+    assert_close(open.surface_area(), 4.0 * PI);
+    assert_close(open.volume(), 2.0 * PI);
+
+    let closed = Cylinder::new(Material::default(), 1.0, 3.0, true);
+    assert_close(closed.surface_area(), 6.0 * PI);
+
+    let infinite = Cylinder::infinite(Material::default());
+    assert_eq!(infinite.surface_area(), f32::INFINITY);
+    assert_eq!(infinite.volume(), f32::INFINITY);
+}
+
+#[test]
+fn cone_measurements() {
+    // Radius 1 and height 1, slant height sqrt(2)
+    let cone = Cone::new(Material::default(), 0.0, 1.0, true);
+    assert_close(cone.surface_area(), PI * 2.0_f32.sqrt() + PI);
+    assert_close(cone.volume(), PI / 3.0);
+
+    // Both halves of the double cone count, below the apex as much as above
+    let double = Cone::new(Material::default(), -1.0, 1.0, false);
+    assert_close(double.surface_area(), 2.0 * PI * 2.0_f32.sqrt());
+    assert_close(double.volume(), 2.0 * PI / 3.0);
+
+    // A frustum is the big cone minus the small one
+    let frustum = Cone::new(Material::default(), 1.0, 2.0, false);
+    assert_close(frustum.surface_area(), PI * 2.0_f32.sqrt() * (4.0 - 1.0));
+    assert_close(frustum.volume(), PI * (8.0 - 1.0) / 3.0);
+
+    let infinite = Cone::infinite(Material::default());
+    assert_eq!(infinite.surface_area(), f32::INFINITY);
+    assert_eq!(infinite.volume(), f32::INFINITY);
+}
+
+#[test]
+fn closed_cylinder_caps_are_solid() {
+    let cylinder = Cylinder::new(Material::default(), 1.0, 2.0, true);
+
+    // Straight down the axis and at angles through both caps, including through the rim at (0, 1, 0)
+    let through_caps = [
+        (Vec4::point(0.0, 3.0, 0.0), Vec4::vector(0.0, -1.0, 0.0)),
+        (Vec4::point(0.5, 3.0, 0.0), Vec4::vector(0.0, -1.0, 0.0)),
+        (Vec4::point(0.0, 3.0, -2.0), Vec4::vector(0.0, -1.0, 2.0)),
+        (Vec4::point(0.0, 4.0, -2.0), Vec4::vector(0.0, -1.0, 1.0)),
+        (Vec4::point(0.0, 0.0, -2.0), Vec4::vector(0.0, 1.0, 2.0)),
+        (Vec4::point(0.0, -1.0, -2.0), Vec4::vector(0.0, 1.0, 1.0)),
+    ];
+
+    for (origin, direction) in through_caps {
+        assert_eq!(local_ts(&cylinder, origin, direction).len(), 2, "{:?} {:?}", origin, direction);
+    }
+
+    // A line only crosses the solid twice, so a ray through a cap and the barrel also hits twice
+    let ts = local_ts(&cylinder, Vec4::point(0.0, 2.5, 0.0), Vec4::vector(0.0, -1.0, 1.0));
+    assert_eq!(ts.len(), 2);
+    let ray = Ray::new(Vec4::point(0.0, 2.5, 0.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+    let mut ys: Vec<f32> = ts.iter().map(|t| *ray.at(*t).y()).collect();
+    ys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert!(util::equals_f32(&ys[1], &2.0));
+    assert!(util::equals_f32(&ys[0], &1.5));
+
+    // The same vertical ray misses an open cylinder entirely
+    let open = Cylinder::new(Material::default(), 1.0, 2.0, false);
+    assert!(local_ts(&open, Vec4::point(0.0, 3.0, 0.0), Vec4::vector(0.0, -1.0, 0.0)).is_empty());
+}
+
+#[test]
+fn closed_cylinder_cap_normals() {
+    let cylinder = Cylinder::new(Material::default(), 1.0, 2.0, true);
+    let hit = Intersection::new(&cylinder, 0.0);
+
+    for point in [Vec4::point(0.0, 1.0, 0.0), Vec4::point(0.5, 1.0, 0.0), Vec4::point(0.0, 1.0, 0.5)] {
+        assert_eq!(cylinder.local_normal_at(&point, hit), Vec4::vector(0.0, -1.0, 0.0));
+    }
+
+    for point in [Vec4::point(0.0, 2.0, 0.0), Vec4::point(0.5, 2.0, 0.0), Vec4::point(0.0, 2.0, 0.5)] {
+        assert_eq!(cylinder.local_normal_at(&point, hit), Vec4::vector(0.0, 1.0, 0.0));
+    }
+}
+
+#[test]
+fn transform_helpers_match_the_inline_matrix_math() {
+    let mut sphere = Sphere::new(Material::default());
+    sphere.set_transform(Matrix4x4::translation(1.0, -2.0, 0.5) * Matrix4x4::rotatation_z(PI / 5.0) * Matrix4x4::scale(1.0, 0.5, 2.0));
+
+    let world_point = Vec4::point(1.3, -1.8, 1.2);
+    let inverse = sphere.transform().invert();
+    let object_point = sphere.world_to_object(&world_point);
+    assert_eq!(object_point, inverse * world_point);
+
+    let local_normal = Vec4::vector(0.3, 0.8, -0.52);
+    let inline = inverse.transpose() * local_normal;
+    let inline = Vec4::vector(*inline.x(), *inline.y(), *inline.z()).normalize();
+    let world_normal = sphere.object_to_world_normal(&local_normal);
+    assert_eq!(world_normal, inline);
+    assert_eq!(*world_normal.w(), 0.0);
+    assert!(util::equals_f32(&world_normal.magnitude(), &1.0));
+
+    // world_normal_at is built from the two
+    let on_surface = *sphere.transform() * Vec4::point(0.0, 0.6, 0.8);
+    let expected = sphere.object_to_world_normal(&Vec4::vector(0.0, 0.6, 0.8));
+    assert_eq!(sphere.world_normal_at(&on_surface, Intersection::new(&sphere, 0.0)), expected);
+
+    // and patterns look up their colors in object space through it
+    let material = Material { pattern: Some(Box::new(StripePattern::default())), ..Material::default() };
+    let stripes = material.pattern.as_ref().unwrap();
+    let point = *sphere.transform() * Vec4::point(1.5, 0.0, 0.0);
+    assert_eq!(stripes.color_at_object(&sphere, &point), Color::black());
+    assert_eq!(stripes.color_at_object(&sphere, &(*sphere.transform() * Vec4::point(0.5, 0.0, 0.0))), Color::white());
+}
+
+#[test]
+fn cloned_shape_keeps_its_configuration_with_a_new_id() {
+    let mut sphere = Sphere::new(Material::default());
+    sphere.set_transform(Matrix4x4::translation(2.0, 0.0, -1.0) * Matrix4x4::scale(0.5, 0.5, 0.5));
+    sphere.material.color = Color::new(0.2, 0.4, 0.9);
+    sphere.material.reflective = 0.3;
+
+    let copy = sphere.clone_shape().unwrap();
+    assert_eq!(copy.transform(), sphere.transform());
+    assert_eq!(copy.material().color, sphere.material.color);
+    assert_eq!(copy.material().reflective, sphere.material.reflective);
+    assert_ne!(copy.id(), sphere.id());
+
+    // and it is hit where the original is
+    let ray = Ray::new(Vec4::point(2.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let ts: Vec<f32> = Intersection::intersect(copy.as_ref(), ray).iter().map(|i| i.t).collect();
+    assert_eq!(ts, vec![3.5, 4.5]);
+}
+
+#[test]
+fn cloned_group_copies_its_children() {
+    let mut group = Group::new(Material::default());
+    group.add_child(Box::new(Sphere::new(Material::default())));
+    group.add_child(Box::new(Cube::new(Material::default())));
+
+    let mut copy = group.clone_shape().unwrap();
+    let originals = group.children().unwrap();
+    let copies = copy.children().unwrap();
+    assert_eq!(copies.len(), originals.len());
+    assert_ne!(copy.id(), group.id());
+
+    // The children are copied as they are, changing the copy leaves the original alone
+    let red = Material::new(Color::new(1.0, 0.0, 0.0), 0.1, 0.9, 0.9, 200.0, 0.0, 0.0, 1.0, None);
+    copy.set_material_recursive(&red);
+    assert_eq!(group.children().unwrap()[0].material().color, Material::default().color);
+    assert_eq!(copy.children().unwrap()[0].material().color, red.color);
+}
+
+#[test]
+fn cloned_model_shares_its_triangles_until_changed() {
+    let model = Model::load(Material::default(), "obj_files/smoothing_groups.obj").unwrap();
+    let mut copy = model.clone();
+    assert!(Arc::ptr_eq(&model.triangles, &copy.triangles));
+
+    let red = Material::new(Color::new(1.0, 0.0, 0.0), 0.1, 0.9, 0.9, 200.0, 0.0, 0.0, 1.0, None);
+    copy.set_material_recursive(&red);
+    assert!(!Arc::ptr_eq(&model.triangles, &copy.triangles));
+    assert_eq!(model.triangles[0].material().color, Material::default().color);
+    assert_eq!(copy.triangles[0].material().color, red.color);
+
+    let instance = model.clone_shape().unwrap();
+    assert_ne!(instance.id(), model.id());
+    assert_eq!(instance.children().unwrap().len(), model.triangles.len());
+}
+
+// Tangent, bitangent and normal at the object space point are unit length and perpendicular
+fn assert_orthonormal_frame(shape: &dyn Shape, local_point: Vec4, hit: Intersection) {
+    let world_point = *shape.transform() * local_point;
+    let normal = shape.world_normal_at(&world_point, hit);
+    let (tangent, bitangent) = shape.tangent_at(&world_point, hit);
+
+    for axis in [normal, tangent, bitangent] {
+        assert!((axis.magnitude() - 1.0).abs() < 1e-4, "{:?} at {:?} is not unit length", axis, local_point);
+        assert_eq!(*axis.w(), 0.0);
+    }
+    for (a, b) in [(normal, tangent), (normal, bitangent), (tangent, bitangent)] {
+        assert!(a.dot(&b).abs() < 1e-4, "{:?} and {:?} at {:?} are not perpendicular", a, b, local_point);
+    }
+    assert_eq!(bitangent, normal.cross(&tangent));
+}
+
+#[test]
+fn tangent_frames_are_orthonormal_on_every_shape() {
+    let skew = Matrix4x4::translation(1.0, -2.0, 3.0) * Matrix4x4::rotatation_z(PI / 5.0) * Matrix4x4::rotatation_x(PI / 3.0) * Matrix4x4::scale(1.0, 2.0, 0.5);
+    let mut shapes: Vec<(Box<dyn Shape>, Vec<Vec4>)> = vec![
+        // Including the sphere's poles and the centres of the caps, where the tangent has to fall back
+        (Box::new(Sphere::new(Material::default())), vec![Vec4::point(0.6, 0.0, 0.8), Vec4::point(0.0, 0.6, -0.8), Vec4::point(0.0, 1.0, 0.0), Vec4::point(0.0, -1.0, 0.0)]),
+        (Box::new(Plane::new(Material::default())), vec![Vec4::point(3.0, 0.0, -2.0)]),
+        (Box::new(Cube::new(Material::default())), vec![
+            Vec4::point(1.0, 0.3, -0.2), Vec4::point(-1.0, 0.3, -0.2),
+            Vec4::point(0.2, 1.0, 0.1), Vec4::point(0.2, -1.0, 0.1),
+            Vec4::point(0.1, 0.4, 1.0), Vec4::point(0.1, 0.4, -1.0),
+        ]),
+        (Box::new(Cylinder::bounded(Material::default(), 0.0, 1.0, true)), vec![Vec4::point(0.0, 0.5, -1.0), Vec4::point(0.3, 1.0, 0.2), Vec4::point(0.0, 0.0, 0.0)]),
+        (Box::new(Cone::bounded(Material::default(), -1.0, 1.0, true)), vec![Vec4::point(0.5, 0.5, 0.0), Vec4::point(0.3, -1.0, 0.2), Vec4::point(0.0, 1.0, 0.0)]),
+        (Box::new(Triangle::new(Material::default(), Vec4::point(0.0, 1.0, 0.0), Vec4::point(-1.0, 0.0, 0.0), Vec4::point(1.0, 0.0, 0.0))), vec![Vec4::point(0.0, 0.5, 0.0)]),
+        (Box::new(SdfShape::blended_spheres(Material::default(), Vec4::point(-0.5, 0.0, 0.0), Vec4::point(0.5, 0.0, 0.0), 0.0)), vec![Vec4::point(0.0, 0.0, 0.75_f32.sqrt()), Vec4::point(1.5, 0.0, 0.0)]),
+    ];
+
+    for (shape, points) in shapes.iter_mut() {
+        for transform in [Matrix4x4::identity(), skew] {
+            shape.set_transform(transform);
+            for point in points.iter() {
+                assert_orthonormal_frame(shape.as_ref(), *point, Intersection::new(shape.as_ref(), 1.0));
+            }
+        }
+    }
+
+    // Smooth triangles use the interpolated normal at the hit
+    let mut smooth = SmoothTriangle::new(
+        Material::default(),
+        Vec4::point(0.0, 1.0, 0.0), Vec4::point(-1.0, 0.0, 0.0), Vec4::point(1.0, 0.0, 0.0),
+        Vec4::vector(0.0, 1.0, 0.0), Vec4::vector(-1.0, 0.0, 0.0), Vec4::vector(1.0, 0.0, 0.0),
+    );
+    smooth.set_transform(skew);
+    let hit = Intersection::from_uv(&smooth, 1.0, 0.45, 0.25);
+    assert_orthonormal_frame(&smooth, Vec4::point(-0.2, 0.3, 0.0), hit);
+}
+
+#[test]
+fn tangents_follow_the_surface_coordinates() {
+    // Around the sphere's y axis, horizontal on the equator
+    let sphere = Sphere::new(Material::default());
+    let point = Vec4::point(1.0, 0.0, 0.0);
+    let (tangent, bitangent) = sphere.tangent_at(&point, Intersection::new(&sphere, 1.0));
+    assert_eq!(tangent, Vec4::vector(0.0, 0.0, -1.0));
+    assert_eq!(bitangent, Vec4::vector(0.0, 1.0, 0.0));
+
+    // Cube faces point along u of Cube::uv_at
+    let cube = Cube::new(Material::default());
+    for point in [
+        Vec4::point(1.0, 0.3, -0.2), Vec4::point(-1.0, 0.3, -0.2),
+        Vec4::point(0.2, 1.0, 0.1), Vec4::point(0.2, -1.0, 0.1),
+        Vec4::point(0.1, 0.4, 1.0), Vec4::point(0.1, 0.4, -1.0),
+    ] {
+        let (tangent, _) = cube.tangent_at(&point, Intersection::new(&cube, 1.0));
+        let (face, u, v) = Cube::uv_at(&point);
+        let (next_face, next_u, next_v) = Cube::uv_at(&(point + tangent * 0.1));
+        assert_eq!(next_face, face);
+        assert!(next_u > u, "u does not increase along the tangent at {:?}", point);
+        assert!(util::equals_f32(&next_v, &v));
+    }
+
+    // and tangents turn with the shape's transform
+    let mut plane = Plane::new(Material::default());
+    plane.set_transform(Matrix4x4::rotatation_y(PI / 2.0));
+    let (tangent, _) = plane.tangent_at(&Vec4::ORIGIN, Intersection::new(&plane, 1.0));
+    assert_eq!(tangent, Vec4::vector(0.0, 0.0, -1.0));
+}
@@ -0,0 +1,40 @@
+use tracer::util;
+
+#[test]
+fn max3_and_min3_pick_from_any_position() {
+    assert_eq!(util::max3(3.0, 1.0, 2.0), 3.0);
+    assert_eq!(util::max3(1.0, 3.0, 2.0), 3.0);
+    assert_eq!(util::max3(1.0, 2.0, 3.0), 3.0);
+
+    assert_eq!(util::min3(-3.0, 1.0, 2.0), -3.0);
+    assert_eq!(util::min3(1.0, -3.0, 2.0), -3.0);
+    assert_eq!(util::min3(1.0, 2.0, -3.0), -3.0);
+}
+
+#[test]
+fn max3_and_min3_handle_infinities() {
+    assert_eq!(util::max3(f32::NEG_INFINITY, 1.0, f32::INFINITY), f32::INFINITY);
+    assert_eq!(util::min3(f32::NEG_INFINITY, 1.0, f32::INFINITY), f32::NEG_INFINITY);
+}
+
+#[test]
+fn max3_and_min3_ignore_nan() {
+    assert_eq!(util::max3(f32::NAN, 1.0, 2.0), 2.0);
+    assert_eq!(util::max3(1.0, f32::NAN, 2.0), 2.0);
+    assert_eq!(util::max3(1.0, 2.0, f32::NAN), 2.0);
+
+    assert_eq!(util::min3(f32::NAN, 1.0, 2.0), 1.0);
+    assert_eq!(util::min3(1.0, f32::NAN, 2.0), 1.0);
+    assert_eq!(util::min3(1.0, 2.0, f32::NAN), 1.0);
+
+    assert!(util::max3(f32::NAN, f32::NAN, f32::NAN).is_nan());
+    assert!(util::min3(f32::NAN, f32::NAN, f32::NAN).is_nan());
+}
+
+#[test]
+fn clamp_sanitizes_nan_and_infinities() {
+    assert_eq!(util::clamp_f32(f32::NAN, 0.0, 1.0), 0.0);
+    assert_eq!(util::clamp_f32(f32::INFINITY, 0.0, 1.0), 1.0);
+    assert_eq!(util::clamp_f32(f32::NEG_INFINITY, 0.0, 1.0), 0.0);
+    assert_eq!(util::clamp_f32(0.25, 0.0, 1.0), 0.25);
+}
@@ -0,0 +1,862 @@
+use tracer::color::Color;
+use tracer::environment::Environment;
+use tracer::geometry::{Matrix4x4, Vec4};
+use tracer::intersection::{Comp, Intersection};
+use tracer::light::Light;
+use tracer::material::Material;
+use tracer::pattern::{Pattern, StripePattern};
+use tracer::ray::Ray;
+use tracer::shape::{Cube, Plane, Shape, Sphere};
+use tracer::util;
+use tracer::world::{Bounces, World};
+
+#[derive(Clone)]
+struct TestPattern {
+    transform: Matrix4x4,
+}
+
+impl Pattern for TestPattern {
+    fn color_at(&self, point: &Vec4) -> Color {
+        return Color::new(*point.x(), *point.y(), *point.z());
+    }
+
+    fn color_at_object(&self, shape: &dyn Shape, world_point: &Vec4) -> Color {
+        let object_point = shape.transform().invert() * *world_point;
+        let pattern_point = self.transform * object_point;
+
+        return self.color_at(&pattern_point);
+    }
+}
+
+fn reflective_plane_world() -> World {
+    let mut world = World::demo();
+
+    let material = Material { reflective: 0.5, ..Material::default() };
+    let mut plane = Plane::new(material);
+    plane.set_transform(Matrix4x4::translation(0.0, -1.0, 0.0));
+    world.add_object(Box::new(plane));
+
+    return world;
+}
+
+#[test]
+fn reflected_color_for_nonreflective_material() {
+    let mut world = World::demo();
+    world.objects[1].material_mut().ambient = 1.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.0), Vec4::vector(0.0, 0.0, 1.0));
+    let i = Intersection::new(&*world.objects[1], 1.0);
+    let comp = i.prepare_computations(&ray, Some(&vec![i]));
+
+    assert_eq!(world.reflected_color(&comp, 5), Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn reflected_color_for_reflective_material() {
+    let world = reflective_plane_world();
+    let half = 2.0_f32.sqrt() / 2.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -3.0), Vec4::vector(0.0, -half, half));
+    let i = Intersection::new(&*world.objects[2], 2.0_f32.sqrt());
+    let comp = i.prepare_computations(&ray, Some(&vec![i]));
+
+    assert_eq!(world.reflected_color(&comp, 5), Color::new(0.19033, 0.23792, 0.14275));
+}
+
+#[test]
+fn reflected_color_at_maximum_depth() {
+    let world = reflective_plane_world();
+    let half = 2.0_f32.sqrt() / 2.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -3.0), Vec4::vector(0.0, -half, half));
+    let i = Intersection::new(&*world.objects[2], 2.0_f32.sqrt());
+    let comp = i.prepare_computations(&ray, Some(&vec![i]));
+
+    assert_eq!(world.reflected_color(&comp, 0), Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn shade_hit_with_reflective_material() {
+    let world = reflective_plane_world();
+    let half = 2.0_f32.sqrt() / 2.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -3.0), Vec4::vector(0.0, -half, half));
+    let i = Intersection::new(&*world.objects[2], 2.0_f32.sqrt());
+    let comp = i.prepare_computations(&ray, Some(&vec![i]));
+
+    assert_eq!(world.shade_hit(&comp, 5), Color::new(0.87676, 0.92434, 0.82917));
+}
+
+#[test]
+fn refracted_color_with_opaque_surface() {
+    let world = World::demo();
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = vec![
+        Intersection::new(&*world.objects[0], 4.0),
+        Intersection::new(&*world.objects[0], 6.0),
+    ];
+    let comp = xs[0].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(world.refracted_color(&comp, 5), Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn refracted_color_at_maximum_depth() {
+    let mut world = World::demo();
+    world.objects[0].material_mut().transparency = 1.0;
+    world.objects[0].material_mut().refraction = 1.5;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = vec![
+        Intersection::new(&*world.objects[0], 4.0),
+        Intersection::new(&*world.objects[0], 6.0),
+    ];
+    let comp = xs[0].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(world.refracted_color(&comp, 0), Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn refracted_color_under_total_internal_reflection() {
+    let mut world = World::demo();
+    world.objects[0].material_mut().transparency = 1.0;
+    world.objects[0].material_mut().refraction = 1.5;
+    let half = 2.0_f32.sqrt() / 2.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, half), Vec4::vector(0.0, 1.0, 0.0));
+    let xs = vec![
+        Intersection::new(&*world.objects[0], -half),
+        Intersection::new(&*world.objects[0], half),
+    ];
+    let comp = xs[1].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(world.refracted_color(&comp, 5), Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn refracted_color_with_refracted_ray() {
+    let mut world = World::demo();
+    world.objects[0].material_mut().ambient = 1.0;
+    world.objects[0].material_mut().pattern = Some(Box::new(TestPattern { transform: Matrix4x4::identity() }));
+    world.objects[1].material_mut().transparency = 1.0;
+    world.objects[1].material_mut().refraction = 1.5;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.1), Vec4::vector(0.0, 1.0, 0.0));
+    let xs = vec![
+        Intersection::new(&*world.objects[0], -0.9899),
+        Intersection::new(&*world.objects[1], -0.4899),
+        Intersection::new(&*world.objects[1], 0.4899),
+        Intersection::new(&*world.objects[0], 0.9899),
+    ];
+    let comp = xs[2].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(world.refracted_color(&comp, 5), Color::new(0.0, 0.99887, 0.04722));
+}
+
+#[test]
+fn reflected_color_below_cutoff_is_pruned() {
+    let mut world = reflective_plane_world();
+    world.objects[2].material_mut().reflective = 0.001;
+    let half = 2.0_f32.sqrt() / 2.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -3.0), Vec4::vector(0.0, -half, half));
+    let i = Intersection::new(&*world.objects[2], 2.0_f32.sqrt());
+    let comp = i.prepare_computations(&ray, Some(&vec![i]));
+
+    assert_eq!(world.reflected_color(&comp, 5), Color::new(0.0, 0.0, 0.0));
+
+    world.reflection_cutoff = 0.0;
+    let i = Intersection::new(&*world.objects[2], 2.0_f32.sqrt());
+    let comp = i.prepare_computations(&ray, Some(&vec![i]));
+
+    assert_ne!(world.reflected_color(&comp, 5), Color::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn reflection_cutoff_terminates_parallel_mirrors() {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)));
+
+    let material = Material { reflective: 0.5, ..Material::default() };
+    let mut lower = Plane::new(material);
+    lower.set_transform(Matrix4x4::translation(0.0, -1.0, 0.0));
+    world.add_object(Box::new(lower));
+
+    let material = Material { reflective: 0.5, ..Material::default() };
+    let mut upper = Plane::new(material);
+    upper.set_transform(Matrix4x4::translation(0.0, 1.0, 0.0) * Matrix4x4::rotatation_x(std::f32::consts::PI));
+    world.add_object(Box::new(upper));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.0), Vec4::vector(0.0, 1.0, 0.0));
+    let pruned = world.color_at(ray, 64);
+
+    world.reflection_cutoff = 0.0;
+    let full = world.color_at(ray, 64);
+
+    assert_eq!(pruned.rgb(), full.rgb());
+}
+
+#[test]
+fn default_world_is_empty() {
+    let world = World::default();
+
+    assert!(world.objects.is_empty());
+    assert!(world.lights.is_empty());
+}
+
+#[test]
+fn demo_world_has_two_spheres_and_a_light() {
+    let world = World::demo();
+
+    assert_eq!(world.objects.len(), 2);
+    assert_eq!(world.lights.len(), 1);
+    assert_eq!(world.lights[0].position, Vec4::point(-10.0, 10.0, -10.0));
+    assert_eq!(*world.objects[1].transform(), Matrix4x4::scale(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn first_hit_reports_unshaded_surface() {
+    let world = World::demo();
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let sample = world.first_hit(ray).unwrap();
+
+    assert!((sample.depth - 4.0).abs() < 0.0001);
+    assert_eq!(sample.normal, Vec4::vector(0.0, 0.0, -1.0));
+    assert_eq!(sample.albedo, Color::new(0.8, 1.0, 0.6));
+
+    let miss = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 1.0, 0.0));
+    assert!(world.first_hit(miss).is_none());
+}
+
+#[test]
+fn default_bounce_budgets_are_five() {
+    assert_eq!(Bounces::default(), Bounces::new(5, 5));
+    assert_eq!(World::new().max_bounces, Bounces::uniform(5));
+}
+
+#[test]
+fn reflection_budget_does_not_limit_refraction() {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::white()));
+
+    let mut backdrop = Plane::new(Material::matte(Color::new(1.0, 0.0, 0.0)));
+    backdrop.set_transform(Matrix4x4::translation(0.0, 0.0, 5.0) * Matrix4x4::rotatation_x(std::f32::consts::PI / 2.0));
+    world.add_object(Box::new(backdrop));
+
+    let mut glass = Sphere::glass_sphere();
+    glass.material_mut().ambient = 0.0;
+    glass.material_mut().diffuse = 0.0;
+    glass.material_mut().specular = 0.0;
+    glass.material_mut().reflective = 0.0;
+    world.add_object(Box::new(glass));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let full = world.color_at_bounces(ray, Bounces::uniform(5));
+
+    assert!(full.r() > &0.0);
+    assert_eq!(world.color_at_bounces(ray, Bounces::new(0, 5)), full);
+    // Entering the sphere spends the only refraction, so the ray can't leave it again
+    assert_eq!(world.color_at_bounces(ray, Bounces::new(5, 1)), Color::black());
+}
+
+#[test]
+fn refraction_budget_does_not_limit_reflection() {
+    let world = reflective_plane_world();
+    let half = 2.0_f32.sqrt() / 2.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -3.0), Vec4::vector(0.0, -half, half));
+    let full = world.color_at_bounces(ray, Bounces::uniform(5));
+
+    assert_eq!(world.color_at_bounces(ray, Bounces::new(5, 0)), full);
+    assert_ne!(world.color_at_bounces(ray, Bounces::new(0, 5)), full);
+}
+
+#[test]
+fn stretched_occluders_shadow_by_world_distance() {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::white()));
+
+    // A tall thin cube from y = 17 to y = 23, beyond the light
+    let mut beyond = Cube::new(Material::default());
+    beyond.set_transform(Matrix4x4::translation(0.0, 20.0, 0.0) * Matrix4x4::scale(0.5, 3.0, 0.5));
+    world.add_object(Box::new(beyond));
+
+    assert!(!world.is_shadowed(&Vec4::ORIGIN));
+
+    // The same cube from y = 2 to y = 8, between the point and the light
+    let mut between = Cube::new(Material::default());
+    between.set_transform(Matrix4x4::translation(0.0, 5.0, 0.0) * Matrix4x4::scale(0.5, 3.0, 0.5));
+    world.add_object(Box::new(between));
+
+    assert!(world.is_shadowed(&Vec4::ORIGIN));
+}
+
+#[test]
+fn transformed_ray_keeps_t_in_world_units() {
+    let mut cube = Cube::new(Material::default());
+    cube.set_transform(Matrix4x4::scale(4.0, 1.0, 1.0));
+
+    let ray = Ray::new(Vec4::point(-10.0, 0.0, 0.0), Vec4::vector(1.0, 0.0, 0.0));
+    let xs = Intersection::intersect(&cube, ray);
+
+    assert!((xs[0].t - 6.0).abs() < 0.0001 && (xs[1].t - 14.0).abs() < 0.0001);
+    assert_eq!(ray.at(xs[0].t), Vec4::point(-4.0, 0.0, 0.0));
+}
+
+fn glossy_world(roughness: f32, sphere_x: f32) -> World {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::white()));
+
+    let mut material = Material::matte(Color::black());
+    material.ambient = 0.0;
+    material.diffuse = 0.0;
+    material.reflective = 1.0;
+    material.roughness = roughness;
+    world.add_object(Box::new(Plane::new(material)));
+
+    let mut glow = Material::matte(Color::white());
+    glow.ambient = 1.0;
+    glow.diffuse = 0.0;
+    let mut sphere = Sphere::new(glow);
+    sphere.set_transform(Matrix4x4::translation(sphere_x, 2.0, 2.0) * Matrix4x4::scale(0.5, 0.5, 0.5));
+    world.add_object(Box::new(sphere));
+
+    return world;
+}
+
+fn mirror_ray() -> Ray {
+    return Ray::new(Vec4::point(0.0, 1.0, -1.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+}
+
+#[test]
+fn smooth_material_reflects_sharply() {
+    let world = glossy_world(0.0, 0.0);
+    assert_eq!(world.color_at(mirror_ray(), 5), Color::white());
+
+    let world = glossy_world(0.0, 0.8);
+    assert_eq!(world.color_at(mirror_ray(), 5), Color::black());
+}
+
+#[test]
+fn rough_material_blurs_reflections() {
+    let world = glossy_world(0.2, 0.0);
+    let center = world.color_at(mirror_ray(), 5);
+    assert!(*center.r() > 0.0 && *center.r() < 1.0, "center {:?}", center);
+
+    // The mirror direction just misses the sphere, but part of the cone still catches it
+    let world = glossy_world(0.2, 0.8);
+    let edge = world.color_at(mirror_ray(), 5);
+    assert!(*edge.r() > 0.0 && *edge.r() < *center.r(), "edge {:?}", edge);
+}
+
+#[test]
+fn glossy_reflection_needs_samples_and_budget() {
+    let mut world = glossy_world(0.2, 0.8);
+
+    world.glossy_samples = 1;
+    assert_eq!(world.color_at(mirror_ray(), 5), Color::black());
+
+    world.glossy_samples = 16;
+    let mut bounces = Bounces::uniform(5);
+    bounces.glossy = 0;
+    assert_eq!(world.color_at_bounces(mirror_ray(), bounces), Color::black());
+}
+
+fn frosted_world(roughness: f32) -> World {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::white()));
+
+    let mut glass = Material::matte(Color::white());
+    glass.ambient = 0.0;
+    glass.diffuse = 0.0;
+    glass.transparency = 1.0;
+    glass.roughness = roughness;
+    let mut pane = Plane::new(glass);
+    pane.set_transform(Matrix4x4::rotatation_x(std::f32::consts::PI / 2.0));
+    world.add_object(Box::new(pane));
+
+    let mut glow = Material::matte(Color::white());
+    glow.ambient = 1.0;
+    glow.diffuse = 0.0;
+    let mut sphere = Sphere::new(glow);
+    sphere.set_transform(Matrix4x4::translation(0.0, 0.0, 3.0) * Matrix4x4::scale(0.5, 0.5, 0.5));
+    world.add_object(Box::new(sphere));
+
+    return world;
+}
+
+#[test]
+fn smooth_refraction_is_unchanged_by_zero_roughness() {
+    let mut world = World::demo();
+    world.objects[0].material_mut().ambient = 1.0;
+    world.objects[0].material_mut().pattern = Some(Box::new(TestPattern { transform: Matrix4x4::identity() }));
+    world.objects[1].material_mut().transparency = 1.0;
+    world.objects[1].material_mut().refraction = 1.5;
+    world.objects[1].material_mut().roughness = 0.0;
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, 0.1), Vec4::vector(0.0, 1.0, 0.0));
+    let xs = vec![
+        Intersection::new(&*world.objects[0], -0.9899),
+        Intersection::new(&*world.objects[1], -0.4899),
+        Intersection::new(&*world.objects[1], 0.4899),
+        Intersection::new(&*world.objects[0], 0.9899),
+    ];
+    let comp = xs[2].prepare_computations(&ray, Some(&xs));
+
+    assert_eq!(world.refracted_color(&comp, 5), Color::new(0.0, 0.99887, 0.04722));
+
+    let clear = frosted_world(0.0);
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(clear.color_at(ray, 5), Color::white());
+}
+
+#[test]
+fn frosted_glass_blurs_what_is_behind_it() {
+    let world = frosted_world(0.2);
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let center = world.color_at(ray, 5);
+    assert!(*center.r() > 0.0 && *center.r() < 1.0, "center {:?}", center);
+
+    // Aimed past the sphere, some of the spread still reaches it
+    let ray = Ray::new(Vec4::point(0.8, 0.0, -2.0), Vec4::vector(0.0, 0.0, 1.0));
+    let edge = world.color_at(ray, 5);
+    assert!(*edge.r() > 0.0 && *edge.r() < *center.r(), "edge {:?}", edge);
+}
+
+#[test]
+fn shade_hit_on_a_comp_built_by_hand() {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white()));
+
+    // The sphere isn't part of the world, so nothing can shadow or reflect the hit
+    let sphere = Sphere::new(Material::default());
+    let comp = Comp::from_surface(&sphere, Vec4::ORIGIN, Vec4::vector(0.0, 0.0, -1.0), Vec4::vector(0.0, 0.0, -1.0));
+    assert_eq!(world.shade_hit(&comp, 5), Color::new(1.9, 1.9, 1.9));
+
+    // Every light contributes its own highlight
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::new(0.5, 0.5, 0.5)));
+    assert_eq!(world.shade_hit(&comp, 5), Color::new(2.85, 2.85, 2.85));
+
+    // A blocker between the surface and the lights leaves only the ambient term of each
+    let mut blocker = Sphere::new(Material::default());
+    blocker.set_transform(Matrix4x4::translation(0.0, 0.0, -5.0));
+    world.add_object(Box::new(blocker));
+    assert_eq!(world.shade_hit(&comp, 5), Color::new(0.15, 0.15, 0.15));
+}
+
+#[test]
+fn emission_is_added_once_regardless_of_lights_and_shadows() {
+    let mut material = Material::matte(Color::black());
+    material.ambient = 0.0;
+    material.emission = Color::new(0.2, 0.4, 0.6);
+
+    let mut world = World::new();
+    world.add_object(Box::new(Sphere::new(material)));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(world.color_at(ray, 5), Color::new(0.2, 0.4, 0.6));
+
+    // Lights behind the sphere leave it in its own shadow, the glow doesn't change or double up
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, 10.0), Color::white()));
+    world.add_light(Light::point_light(Vec4::point(0.0, 1.0, 10.0), Color::white()));
+    assert_eq!(world.color_at(ray, 5), Color::new(0.2, 0.4, 0.6));
+}
+
+#[test]
+fn emission_pattern_textures_the_glow() {
+    let mut material = Material::matte(Color::black());
+    material.ambient = 0.0;
+    material.emission = Color::new(0.5, 0.5, 0.5);
+    material.emission_pattern = Some(Box::new(StripePattern::new(Color::RED, Color::BLUE, Matrix4x4::scale(2.0, 2.0, 2.0))));
+
+    let mut world = World::new();
+    world.add_object(Box::new(Plane::from_normal(material, Vec4::vector(0.0, 0.0, -1.0))));
+
+    let ray = |x: f32| Ray::new(Vec4::point(x, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(world.color_at(ray(0.25), 5), Color::RED);
+    assert_eq!(world.color_at(ray(0.75), 5), Color::BLUE);
+}
+
+#[test]
+fn shadows_are_queried_per_light() {
+    let mut world = World::new();
+    let above = Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::white());
+    let beside = Light::point_light(Vec4::point(10.0, 0.0, 0.0), Color::white());
+
+    // Sits between the origin and the light above only
+    let mut occluder = Sphere::new(Material::default());
+    occluder.set_transform(Matrix4x4::translation(0.0, 5.0, 0.0));
+    world.add_object(Box::new(occluder));
+
+    assert!(world.is_shadowed_from(&Vec4::ORIGIN, &above));
+    assert!(!world.is_shadowed_from(&Vec4::ORIGIN, &beside));
+
+    // A light closer than the occluder isn't blocked by it
+    let near = Light::point_light(Vec4::point(0.0, 2.0, 0.0), Color::white());
+    assert!(!world.is_shadowed_from(&Vec4::ORIGIN, &near));
+
+    // The aggregate reports a shadow as soon as one light is blocked
+    world.add_light(beside);
+    assert!(!world.is_shadowed(&Vec4::ORIGIN));
+    world.add_light(above);
+    assert!(world.is_shadowed(&Vec4::ORIGIN));
+}
+
+#[test]
+fn disabled_objects_are_invisible() {
+    let mut world = World::demo();
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    assert_eq!(world.intersect_world(ray).len(), 4);
+
+    // Switching off the outer sphere reveals the inner one
+    world.objects[0].set_enabled(false);
+    let xs = world.intersect_world(ray);
+    assert_eq!(xs.len(), 2);
+    assert!(xs.iter().all(|x| x.object.id() == world.objects[1].id()));
+
+    world.objects[1].set_enabled(false);
+    assert!(world.intersect_world(ray).is_empty());
+    assert_eq!(world.color_at(ray, 5), Color::black());
+
+    world.objects[0].set_enabled(true);
+    world.objects[1].set_enabled(true);
+    assert_eq!(world.intersect_world(ray).len(), 4);
+}
+
+#[test]
+fn disabled_objects_cast_no_shadow() {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 10.0, 0.0), Color::white()));
+
+    let mut blocker = Sphere::new(Material::default());
+    blocker.set_transform(Matrix4x4::translation(0.0, 5.0, 0.0));
+    world.add_object(Box::new(blocker));
+
+    assert!(world.is_shadowed(&Vec4::ORIGIN));
+
+    world.objects[0].set_enabled(false);
+    assert!(!world.is_shadowed(&Vec4::ORIGIN));
+}
+
+#[test]
+fn disabled_group_children_are_skipped() {
+    let mut group = tracer::group::Group::new(Material::default());
+    let mut near = Sphere::new(Material::default());
+    near.set_transform(Matrix4x4::translation(0.0, 0.0, -3.0));
+    group.add_child(Box::new(near));
+    group.add_child(Box::new(Sphere::new(Material::default())));
+    group.children[0].set_enabled(false);
+
+    let mut world = World::new();
+    world.add_object(Box::new(group));
+
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -10.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = world.intersect_world(ray);
+    assert_eq!(xs.len(), 2);
+    assert_eq!(xs[0].t, 9.0);
+}
+
+#[test]
+fn no_fog_by_default() {
+    let world = World::demo();
+    assert_eq!(world.fog_density, 0.0);
+
+    let miss = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 1.0, 0.0));
+    assert_eq!(world.color_at(miss, 5), Color::black());
+}
+
+#[test]
+fn fog_thickens_with_distance() {
+    let fog_color = Color::new(0.5, 0.6, 0.7);
+    let mut clear = World::new();
+    clear.add_light(Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::white()));
+    clear.add_object(Box::new(Sphere::new(Material::default())));
+
+    let mut foggy = World::new();
+    foggy.add_light(Light::point_light(Vec4::point(0.0, 10.0, -10.0), Color::white()));
+    foggy.add_object(Box::new(Sphere::new(Material::default())));
+    foggy.fog_color = fog_color;
+    foggy.fog_density = 0.1;
+
+    for distance in [2.0, 5.0, 20.0] {
+        let ray = Ray::new(Vec4::point(0.0, 0.0, -distance - 1.0), Vec4::vector(0.0, 0.0, 1.0));
+        let surface = clear.color_at(ray, 5);
+
+        let transmittance = (-0.1 * distance).exp();
+        let expected = surface * transmittance + fog_color * (1.0 - transmittance);
+        assert_eq!(foggy.color_at(ray, 5), expected);
+    }
+
+    let miss = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 1.0, 0.0));
+    assert_eq!(foggy.color_at(miss, 5), fog_color);
+}
+
+#[test]
+fn sky_dome_blends_sky_and_ground_by_direction() {
+    let dome = tracer::light::SkyDome::new(Color::new(0.4, 0.6, 1.0), Color::new(0.2, 0.1, 0.0));
+
+    assert_eq!(dome.color_at(&Vec4::vector(0.0, 1.0, 0.0)), dome.sky);
+    assert_eq!(dome.color_at(&Vec4::vector(0.0, -3.0, 0.0)), dome.ground);
+    assert_eq!(dome.color_at(&Vec4::vector(1.0, 0.0, 0.0)), Color::new(0.3, 0.35, 0.5));
+}
+
+#[test]
+fn sky_dome_replaces_the_flat_ambient_term() {
+    let sky = Color::new(0.4, 0.6, 1.0);
+    let ground = Color::new(0.2, 0.1, 0.0);
+    let mut material = Material::matte(Color::new(0.5, 1.0, 1.0));
+    material.ambient = 0.5;
+
+    let mut world = World::new();
+    world.add_object(Box::new(Plane::new(material)));
+    // Lit from below so only the ambient term reaches the top of the floor
+    world.add_light(Light::point_light(Vec4::point(0.0, -10.0, 0.0), Color::white()));
+
+    let from_above = Ray::new(Vec4::point(0.0, 1.0, 0.0), Vec4::vector(0.0, -1.0, 0.0));
+    let from_below = Ray::new(Vec4::point(0.0, -1.0, 0.0), Vec4::vector(0.0, 1.0, 0.0));
+    assert_eq!(world.color_at(from_above, 5), Color::new(0.25, 0.5, 0.5));
+
+    world.sky_dome = Some(tracer::light::SkyDome::new(sky, ground));
+    assert_eq!(world.color_at(from_above, 5), Color::new(0.1, 0.3, 0.5));
+
+    // Underneath faces the ground and is lit directly as well
+    let direct = Color::new(0.5, 1.0, 1.0) * 0.9;
+    assert_eq!(world.color_at(from_below, 5), Color::new(0.05, 0.05, 0.0) + direct);
+}
+
+// A glass ball in front of a self-lit black and white striped wall
+fn dispersion_world(dispersion: f32) -> World {
+    let mut glass = Material::glass();
+    glass.reflective = 0.0;
+    glass.transparency = 1.0;
+    glass.specular = 0.0;
+    glass.diffuse = 0.0;
+    glass.dispersion = dispersion;
+
+    let mut wall_material = Material::matte(Color::white());
+    wall_material.ambient = 1.0;
+    wall_material.diffuse = 0.0;
+    wall_material.pattern = Some(Box::new(StripePattern::new(Color::white(), Color::black(), Matrix4x4::scale(4.0, 1.0, 1.0))));
+    let mut wall = Plane::new(wall_material);
+    wall.set_transform(tracer::geometry::TransformBuilder::new().rotate_x(-std::f32::consts::PI / 2.0).translate(0.0, 0.0, 5.0).build());
+
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::point(0.0, 0.0, -10.0), Color::white()));
+    world.add_object(Box::new(Sphere::new(glass)));
+    world.add_object(Box::new(wall));
+
+    return world;
+}
+
+fn colors_across_the_ball(world: &World) -> Vec<Color> {
+    return (0..40)
+        .map(|i| {
+            let x = -0.8 + i as f32 * 0.04;
+            world.color_at(Ray::new(Vec4::point(x, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0)), 5)
+        })
+        .collect();
+}
+
+#[test]
+fn glass_without_dispersion_keeps_white_light_white() {
+    assert_eq!(Material::default().dispersion, 0.0);
+
+    for color in colors_across_the_ball(&dispersion_world(0.0)) {
+        assert!(util::equals_f32(color.r(), color.g()) && util::equals_f32(color.g(), color.b()), "{:?}", color);
+    }
+}
+
+#[test]
+fn dispersion_splits_white_light_into_fringes() {
+    let plain = colors_across_the_ball(&dispersion_world(0.0));
+    let dispersed = colors_across_the_ball(&dispersion_world(0.05));
+
+    // The green channel follows the undispersed path, red and blue land on different stripes near edges
+    for (plain, dispersed) in plain.iter().zip(&dispersed) {
+        assert!(util::equals_f32(plain.g(), dispersed.g()));
+    }
+    assert!(dispersed.iter().any(|color| (color.r() - color.b()).abs() > 0.5));
+}
+
+// A 5x5 grid of small spheres in the plane z = 0
+fn sphere_grid() -> World {
+    let mut world = World::new();
+    for i in 0..5 {
+        for j in 0..5 {
+            let mut sphere = Sphere::new(Material::default());
+            sphere.set_transform(Matrix4x4::translation(i as f32 * 2.0 - 4.0, j as f32 * 2.0 - 4.0, 0.0) * Matrix4x4::scale(0.5, 0.5, 0.5));
+            world.add_object(Box::new(sphere));
+        }
+    }
+    return world;
+}
+
+#[test]
+fn any_occluder_only_counts_hits_in_front_and_before_max_t() {
+    let world = sphere_grid();
+    let ray = Ray::new(Vec4::point(2.0, 2.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+
+    // The sphere at (2, 2, 0) spans t = 4.5 to 5.5
+    assert!(world.any_occluder(ray, 10.0));
+    assert!(world.any_occluder(ray, 4.6));
+    assert!(!world.any_occluder(ray, 4.4));
+
+    // Turned around it only has the sphere behind it
+    assert!(!world.any_occluder(Ray::new(ray.origin, -ray.direction), 10.0));
+
+    // Between the spheres there is nothing to hit
+    assert!(!world.any_occluder(Ray::new(Vec4::point(1.0, 1.0, -5.0), Vec4::vector(0.0, 0.0, 1.0)), 10.0));
+}
+
+#[test]
+fn any_occluder_agrees_with_the_nearest_hit() {
+    let world = sphere_grid();
+
+    for x in -10..=10 {
+        for y in -10..=10 {
+            let ray = Ray::new(Vec4::point(x as f32 * 0.5, y as f32 * 0.5, -5.0), Vec4::vector(0.1, -0.05, 1.0).normalize());
+            let mut xs = world.intersect_world(ray);
+            let nearest = Intersection::hit(&mut xs).map(|hit| hit.t);
+
+            for max_t in [4.8, 5.0, 6.0] {
+                let expected = nearest.is_some_and(|t| t < max_t);
+                assert_eq!(world.any_occluder(ray, max_t), expected, "({}, {}) within {}", x, y, max_t);
+            }
+        }
+    }
+}
+
+// A red, a green and a blue light shining on a matte floor from three sides
+fn three_colored_lights() -> Vec<Light> {
+    return vec![
+        Light::point_light(Vec4::point(-5.0, 5.0, 0.0), Color::new(1.0, 0.0, 0.0)),
+        Light::point_light(Vec4::point(5.0, 5.0, 0.0), Color::new(0.0, 1.0, 0.0)),
+        Light::point_light(Vec4::point(0.0, 5.0, -5.0), Color::new(0.0, 0.0, 1.0)),
+    ];
+}
+
+fn floor_world(lights: Vec<Light>, blocker: bool) -> World {
+    let mut world = World::new();
+    world.add_lights(lights);
+    world.add_object(Box::new(Plane::new(Material::matte(Color::new(0.8, 0.8, 0.8)))));
+
+    // A small ball just above the floor in the path of the red light only
+    if blocker {
+        let mut ball = Sphere::new(Material::default());
+        ball.set_transform(Matrix4x4::translation(-1.0, 1.0, 0.0) * Matrix4x4::scale(0.3, 0.3, 0.3));
+        world.add_object(Box::new(ball));
+    }
+
+    return world;
+}
+
+fn assert_colors_close(a: Color, b: Color) {
+    let close = (a.r() - b.r()).abs() < 0.0001 && (a.g() - b.g()).abs() < 0.0001 && (a.b() - b.b()).abs() < 0.0001;
+    assert!(close, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn add_lights_appends_in_order() {
+    let mut world = World::new();
+    world.add_light(Light::point_light(Vec4::ORIGIN, Color::white()));
+    world.add_lights(three_colored_lights());
+
+    assert_eq!(world.lights.len(), 4);
+    assert_eq!(world.lights[3].intensity, Color::new(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn several_lights_add_up_to_the_sum_of_each_alone() {
+    let ray = Ray::new(Vec4::point(0.0, 1.0, -1.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+
+    for blocker in [false, true] {
+        let together = floor_world(three_colored_lights(), blocker).color_at(ray, 5);
+        let alone = three_colored_lights()
+            .into_iter()
+            .map(|light| floor_world(vec![light], blocker).color_at(ray, 5))
+            .fold(Color::black(), |sum, color| sum + color);
+
+        assert_colors_close(together, alone);
+    }
+}
+
+#[test]
+fn a_shadow_only_removes_the_blocked_light() {
+    let ray = Ray::new(Vec4::point(0.0, 1.0, -1.0), Vec4::vector(0.0, -1.0, 1.0).normalize());
+    let lit = floor_world(three_colored_lights(), false).color_at(ray, 5);
+    let blocked = floor_world(three_colored_lights(), true).color_at(ray, 5);
+
+    // The point (0, 0, 0) hides from the red light behind the ball but still sees green and blue
+    let world = floor_world(three_colored_lights(), true);
+    assert!(world.is_shadowed_from(&Vec4::point(0.0, 0.00001, 0.0), &world.lights[0]));
+    assert!(!world.is_shadowed_from(&Vec4::point(0.0, 0.00001, 0.0), &world.lights[1]));
+    assert!(!world.is_shadowed_from(&Vec4::point(0.0, 0.00001, 0.0), &world.lights[2]));
+
+    // Red drops to its ambient share, green and blue are untouched
+    let red_ambient = 0.8 * Material::matte(Color::new(0.8, 0.8, 0.8)).ambient;
+    assert!((blocked.r() - red_ambient).abs() < 0.0001, "{:?}", blocked);
+    assert!(*lit.r() > *blocked.r());
+    assert!((lit.g() - blocked.g()).abs() < 0.0001);
+    assert!((lit.b() - blocked.b()).abs() < 0.0001);
+}
+
+fn glass_ball_world() -> World {
+    let mut world = World::new();
+    world.add_object(Box::new(Sphere::glass_sphere()));
+    world.environment = Some(Environment::Solid(Color::new(0.9, 0.9, 0.9)));
+    return world;
+}
+
+#[test]
+fn refraction_background_shows_through_glass_only() {
+    let through = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let past = Ray::new(Vec4::point(0.0, 2.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+
+    // Unset, glass shows the same environment as everything else
+    let world = glass_ball_world();
+    let plain = world.color_at(through, 5);
+    assert!((plain.r() - plain.b()).abs() < 0.0001, "{:?}", plain);
+
+    let mut world = glass_ball_world();
+    world.refraction_background = Some(Environment::Solid(Color::new(0.0, 0.2, 1.0)));
+
+    // Looking through the ball the light that made it out the back is blue, the little that reflects off the
+    // front still sees the gray environment
+    let tinted = world.color_at(through, 5);
+    assert!(*tinted.b() > 0.8 && *tinted.r() < 0.1, "{:?}", tinted);
+    assert_eq!(world.color_at(past, 5), Color::new(0.9, 0.9, 0.9));
+}
+
+#[test]
+fn zero_refraction_set_directly_does_not_produce_nan() {
+    let mut world = World::demo();
+    for object in world.objects.iter_mut() {
+        object.material_mut().transparency = 1.0;
+        object.material_mut().refraction = 0.0;
+    }
+
+    // Entering the inner sphere from the outer one, with an index of 0 on both sides
+    let ray = Ray::new(Vec4::point(0.0, 0.0, -5.0), Vec4::vector(0.0, 0.0, 1.0));
+    let xs = world.intersect_world(ray);
+    let comp = xs[1].prepare_computations(&ray, Some(&xs));
+    assert_eq!((comp.n1, comp.n2), (0.0, 0.0));
+
+    assert!(comp.refracted_direction().is_none());
+    assert_eq!(comp.schlick(), 1.0);
+    assert_eq!(world.refracted_color(&comp, 5), Color::black());
+    assert!(world.color_at(ray, 5).is_finite());
+}
+
+#[test]
+fn material_with_a_pattern_from_outside_the_crate_clones() {
+    let material = Material {
+        pattern: Some(Box::new(TestPattern { transform: Matrix4x4::translation(1.0, 0.0, 0.0) })),
+        ..Material::default()
+    };
+
+    let copy = material.clone();
+    let point = Vec4::point(0.5, 2.0, 3.0);
+    assert_eq!(copy.pattern.unwrap().color_at(&point), material.pattern.unwrap().color_at(&point));
+}